@@ -0,0 +1,45 @@
+// the placement shown to a practicing player, the landing spot that leaves the best board by a few weighted features
+use std::cmp::Ordering;
+
+use super::{Grid, Blocks, Tetrinome};
+
+// the weights of the well known hand tuned four feature player, per row of stack height, cleared line, hole and step between columns
+const HEIGHT_WEIGHT: f32 = -0.51;
+const LINES_WEIGHT: f32 = 0.76;
+const HOLES_WEIGHT: f32 = -0.36;
+const BUMPINESS_WEIGHT: f32 = -0.18;
+
+// the highest scoring of the spots the piece can land in, None when it has nowhere to go
+pub fn best_placement(grid: &Grid, piece: &Tetrinome) -> Option<Tetrinome> {
+    grid.placements(piece).into_iter()
+        .map(|placement| (evaluate(&grid.blocks, &placement), placement) )
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal) )
+        .map(|(_, placement)| placement )
+}
+
+// how good the board is once the piece is placed and the rows it fills are cleared
+fn evaluate(blocks: &Blocks, piece: &Tetrinome) -> f32 {
+    let width = blocks.size.width as usize;
+    let mut cells: Vec<bool> = blocks.data.iter().map(Option::is_some).collect();
+    for bone in piece.bones.iter() {
+        if let Some(i) = blocks.index(&bone.coord) {
+            cells[i] = true;
+        }
+    }
+    let rows: Vec<&[bool]> = cells.chunks(width).collect();
+    let lines = rows.iter().filter(|row| row.iter().all(|cell| *cell) ).count();
+    let rows: Vec<&[bool]> = rows.into_iter().filter(|row| !row.iter().all(|cell| *cell) ).collect(); // top row first
+
+    let mut heights = vec![0; width];
+    let mut holes = 0;
+    for x in 0..width {
+        if let Some(top) = rows.iter().position(|row| row[x] ) {
+            heights[x] = rows.len() - top;
+            holes += rows[top..].iter().filter(|row| !row[x] ).count();
+        }
+    }
+    let height: usize = heights.iter().sum();
+    let bumpiness: usize = heights.windows(2).map(|pair| (pair[0] as i32 - pair[1] as i32).unsigned_abs() as usize ).sum();
+
+    HEIGHT_WEIGHT * height as f32 + LINES_WEIGHT * lines as f32 + HOLES_WEIGHT * holes as f32 + BUMPINESS_WEIGHT * bumpiness as f32
+}
@@ -1,3 +1,5 @@
+const CARRY_ERROR: f64 = 1e-6; // milliseconds the float error of carrying the time between frames can leave a frame short
+
 #[derive(Clone, Debug)]
 pub struct FrameTimer {
     frames: Vec<f64>,
@@ -19,7 +21,7 @@ impl FrameTimer {
 
     fn set_frames(mut self, vec: Vec<f64>) -> Self {
         self.frames = vec;
-        return self
+        self
     }
 
     #[allow(dead_code)]
@@ -33,6 +35,13 @@ impl FrameTimer {
         Self::init_frameless(delay).set_frames(frames)
     }
 
+    // frames of about frame_duration that are done the duration after the delay, so an animation lasts as long at any update rate
+    // the first frame plays once the delay is over and each one after it waits a frame
+    pub fn lasting(duration: f64, frame_duration: f64, delay: f64) -> Self {
+        let n_frames = (duration / frame_duration).round().max(1.0);
+        Self::equal_sized(n_frames as usize + 1, duration / n_frames, delay)
+    }
+
     // update self.last_update to now
     fn set_update(&mut self, elapsed: f64) {
         self.last_update += elapsed;
@@ -46,17 +55,18 @@ impl FrameTimer {
 
         self.set_update(elapsed);
 
-        let curr_frame = &self.frames[self.next];
+        let curr_frame = self.frames[self.next];
 
+        // the time past the delay or frame carries over to the next frame so the frames don't stretch to whole updates
         if self.next == 0 {
             if self.last_update > self.delay { // for creating a delay before playing the animation. ie. do not play if now is before the initial last_update
                 self.next += 1;
-                self.last_update = 0.0;
+                self.last_update -= self.delay;
                 return FrameState::Ready
             }
-        } else if self.last_update >= *curr_frame {
+        } else if self.last_update + CARRY_ERROR >= curr_frame {
             self.next += 1;
-            self.last_update = 0.0;
+            self.last_update -= curr_frame;
             return FrameState::Ready
         }
         FrameState::Waiting
@@ -80,6 +90,16 @@ impl FrameTimer {
         FrameState::Waiting
     }
 
+    // the number of frames that have been played
+    pub fn frame(&self) -> usize {
+        self.next
+    }
+
+    // the fraction of the frames that have been played
+    pub fn progress(&self) -> f32 {
+        self.next as f32 / self.frames.len().max(1) as f32
+    }
+
     fn is_done(&self) -> bool {
         self.next == self.frames.len()
     }
@@ -0,0 +1,48 @@
+// headless benchmark of the collision and line clear hot paths, only built with `--features bench`
+use std::time::Instant;
+
+use super::{Config, Grid, Tetrinome, millis_per_update};
+
+// places the pieces where they land lowest, restarting whenever the stack reaches the spawn rows, and reports the timing
+pub fn run(config: &Config) {
+    let mut grid = Grid::new(config);
+
+    let mut lines = 0;
+    let mut restarts = 0;
+    let start = Instant::now();
+    for _ in 0..config.bench_placements {
+        grid.curr_piece = lowest_placement(&grid);
+        grid.finish_drop(); // commits the piece and starts clearing the rows it filled
+        grid.events.clear(); // there's no game to fire the hooks
+        while !grid.blocks.rows_full.is_empty() {
+            grid.animate(millis_per_update());
+            grid.finish_clear();
+        }
+        if topped_out(&grid) {
+            lines += grid.score.lines;
+            restarts += 1;
+            grid.restart();
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    lines += grid.score.lines;
+
+    println!("{} placements in {:.3}s, {:.0} placements/s", config.bench_placements, seconds, f64::from(config.bench_placements) / seconds);
+    println!("{} lines cleared, {} restarts", lines, restarts);
+}
+
+// the placement of the current piece that lands lowest
+fn lowest_placement(grid: &Grid) -> Tetrinome {
+    grid.placements(&grid.curr_piece).into_iter().max_by_key(depth).unwrap_or_else(|| grid.curr_piece.clone() )
+}
+
+// the sum of the bones' rows, larger is closer to the floor
+fn depth(piece: &Tetrinome) -> i16 {
+    piece.bones.iter().map(|bone| bone.coord.y ).sum()
+}
+
+// whether the stack has reached the rows new pieces spawn in
+fn topped_out(grid: &Grid) -> bool {
+    let spawn_cells = (grid.width() * Grid::SPAWN_ROWS) as usize;
+    grid.blocks.data[..spawn_cells].iter().any(Option::is_some)
+}
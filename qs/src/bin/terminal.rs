@@ -0,0 +1,395 @@
+// a terminal frontend drawing the game with ansi colors, built with `--features terminal`
+// raw mode is set with stty so it needs a unix terminal with truecolor support
+use std::io::{self, Read, Write};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tetrust::{Error, Game, PieceKind, GameState, Grid, Bone, Special, Tetrinome, Palette};
+use tetrust::timing::{set_updates_per_sec, millis_per_update, SECOND, COUNTDOWN_STEPS};
+use tetrust::primitives::{Coord, Color, Action};
+use tetrust::config::{Config, GhostColor};
+use tetrust::playback::Playback;
+
+const GHOST_BRIGHTNESS: f32 = 0.5; // of the piece's colors in the dimmed shadow piece
+
+// what is drawn in a cell of the board, later layers are drawn over earlier ones
+#[derive(Copy, Clone)]
+enum Cell {
+    Empty,
+    Shadow(Color, f32), // the color and its brightness
+    Advice, // the best spot for the current piece
+    Bone(Color, f32, bool), // the color, how far it's faded to white by a lock flash and whether it's a bomb
+}
+
+// puts the terminal in raw mode so keys arrive as they are pressed without being echoed, restored when dropped
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[2J\x1b[?25l"); // clear the screen and hide the cursor
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&["-raw", "echo"]);
+        print!("\x1b[0m\x1b[?25h\r\n");
+        let _ = io::stdout().flush();
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<()> {
+    Command::new("stty").args(args).status().map(|_| ())
+}
+
+// the game's actions for the bytes read since the last update, arrow and function keys arrive as escape sequences
+fn actions(bytes: &[u8]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (action, len) = match &bytes[i..] {
+            [0x1b, b'[', b'A', ..] => (Some(Action::RotateCW), 3),
+            [0x1b, b'[', b'B', ..] => (Some(Action::Down), 3),
+            [0x1b, b'[', b'C', ..] => (Some(Action::Right), 3),
+            [0x1b, b'[', b'D', ..] => (Some(Action::Left), 3),
+            [0x1b, b'O', b'S', ..] => (Some(Action::ToggleCoords), 3), // F4
+            [0x1b, b'[', b'1', b'5', b'~', ..] => (Some(Action::ToggleStep), 5), // F5
+            [0x1b, b'[', b'1', b'7', b'~', ..] => (Some(Action::SelectRecolor), 5), // F6
+            [0x1b, b'[', b'1', b'8', b'~', ..] => (Some(Action::Recolor), 5), // F7
+            [0x1b, b'[', b'1', b'9', b'~', ..] => (Some(Action::ResetColors), 5), // F8
+            [0x1b, b'[', b'2', b'0', b'~', ..] => (Some(Action::ToggleGhost), 5), // F9, the ghost key is left out since terminals don't send releases to hold it by
+            [0x1b, b'[', b'2', b'1', b'~', ..] => (Some(Action::ToggleMirror), 5), // F10
+            [byte, ..] => (key_action(*byte), 1),
+            [] => break,
+        };
+        actions.extend(action);
+        i += len;
+    }
+    actions
+}
+
+// the action for a single byte key, None for keys that do nothing
+fn key_action(byte: u8) -> Option<Action> {
+    let action = match byte {
+        b' ' => Action::Drop,
+        b'h' => Action::Hold,
+        b'z' => Action::RotateCCW,
+        b'x' => Action::RotateCW,
+        b'q' | 0x1b | 0x03 => Action::Quit, // escape and ctrl-c, raw mode doesn't send signals
+        b'p' => Action::Pause,
+        b'y' | b'\r' => Action::Confirm,
+        b'n' => Action::Cancel,
+        b'r' => Action::Restart,
+        b'b' => Action::ClearBoard,
+        0x7f | 0x08 => Action::Undo, // backspace
+        b'c' => Action::TogglePatterns,
+        b'm' => Action::ToggleMono,
+        b'a' => Action::ToggleAdvice,
+        _ => return None,
+    };
+    Some(action)
+}
+
+// the board's cells with the shadow, the advice, the stack, the instant drop and the current piece layered in that order
+fn board_cells(game: &mut Game) -> Vec<Cell> {
+    let shadow = game.grid_mut().shadow().clone();
+    let advice = game.grid_mut().advice().cloned();
+    let grid = game.grid();
+    let palette = grid.palette();
+    let mut cells = vec![Cell::Empty; (grid.width() * grid.height()) as usize];
+    let mut set = |bone: &Bone, cell: Cell| {
+        let coord = grid.mirror(bone.coord); // where it's drawn, flipped on a mirrored board
+        if coord.x >= 0 && coord.x < grid.width() && coord.y >= 0 && coord.y < grid.height() {
+            cells[(coord.y * grid.width() + coord.x) as usize] = cell;
+        }
+    };
+    if game.ghost_visible() {
+        for bone in shadow.bones().iter() {
+            let cell = match game.config().ghost_color {
+                GhostColor::Dimmed => Cell::Shadow(palette.color(bone), GHOST_BRIGHTNESS),
+                GhostColor::Gray => Cell::Shadow(Color::Gray, 1.0),
+            };
+            set(bone, cell);
+        }
+    }
+    for bone in advice.iter().flat_map(|advice| advice.bones().iter() ) {
+        set(bone, Cell::Advice);
+    }
+    for block in grid.blocks().data().iter().flatten().filter(|block| block.visibility() > 0.0 ) { // faded out in invisible mode
+        let color = if block.clearing() { block.bone.color } else { palette.color(&block.bone) }; // clearing blocks keep the colors of their animation
+        set(&block.bone, Cell::Bone(color, block.flash_alpha().unwrap_or(0.0), is_bomb(&block.bone)));
+    }
+    if let Some(instant_drop) = grid.instant_drop() {
+        for bone in instant_drop.piece().bones().iter() {
+            set(bone, Cell::Bone(palette.color(bone), 0.0, is_bomb(bone)));
+        }
+    }
+    for bone in grid.curr_piece().bones().iter() {
+        set(bone, Cell::Bone(palette.color(bone), 0.0, is_bomb(bone)));
+    }
+    cells
+}
+
+fn is_bomb(bone: &Bone) -> bool {
+    bone.special == Some(Special::Bomb)
+}
+
+// the rgb of a cell's color, a shade of aqua by row in mono mode, faded towards white by the amount
+fn rgb(grid: &Grid, color: Color, y: i16, white: f32) -> (u8, u8, u8) {
+    let (r, g, b) = if grid.mono {
+        let depth = f32::from(y.max(0)) / f32::from((grid.height() - 1).max(1));
+        let brightness = 1.0 - 0.75 * depth;
+        (0, (173.0 * brightness) as u8, (254.0 * brightness) as u8)
+    } else {
+        color.rgb()
+    };
+    let fade = |channel: u8| (f32::from(channel) + (255.0 - f32::from(channel)) * white) as u8;
+    (fade(r), fade(g), fade(b))
+}
+
+// two characters per cell so the cells are roughly square
+fn draw_cell(out: &mut String, grid: &Grid, cell: Cell, y: i16) {
+    match cell {
+        Cell::Empty => out.push_str("\x1b[0m\x1b[2m .\x1b[0m"),
+        Cell::Advice => out.push_str("\x1b[0m\x1b[1m<>"),
+        Cell::Shadow(color, brightness) => {
+            let (r, g, b) = rgb(grid, color, y, 0.0);
+            let (r, g, b) = ((f32::from(r) * brightness) as u8, (f32::from(g) * brightness) as u8, (f32::from(b) * brightness) as u8);
+            out.push_str(&format!("\x1b[0m\x1b[38;2;{};{};{}m[]", r, g, b));
+        },
+        Cell::Bone(color, white, bomb) => {
+            let (r, g, b) = rgb(grid, color, y, white);
+            let bone = if bomb { "\x1b[30m()" } else { "  " };
+            out.push_str(&format!("\x1b[48;2;{};{};{}m{}", r, g, b, bone));
+        },
+    }
+}
+
+// the lines of text to the right of the board
+fn sidebar(game: &Game, playback: Option<&Playback>) -> Vec<String> {
+    let grid = game.grid();
+    let score = grid.score();
+    let mut lines = vec![
+        format!("score {}", score.points),
+        format!("lines {}", score.lines),
+        format!("level {}", score.level),
+        format!("finesse {}", grid.finesse_faults()),
+        format!("pieces {} {:.2}/s", grid.spawned(), game.piece_rate()),
+        String::new(),
+    ];
+    if let Some(kind) = grid.palette().selected() {
+        lines.insert(5, format!("recolor {}", kind.letter()));
+    }
+    if game.stepping() {
+        lines.insert(5, "step, n advances".to_string());
+    }
+    if let Some(rise) = game.rise() {
+        lines.insert(5, format!("rise in {:.1}", rise.remaining() / SECOND));
+    }
+    if game.kill_screen() {
+        lines.insert(5, "KILL SCREEN".to_string());
+    } else if let Some(remaining) = game.kill().and_then(|kill| kill.remaining() ) {
+        lines.insert(5, format!("kill screen in {:.1}", remaining / SECOND));
+    }
+    if let Some(playback) = playback {
+        let status = if playback.finished(game) { "end" } else if playback.paused() { "paused" } else { "playing" };
+        lines.insert(5, format!("replay {} frame {}", status, game.frame()));
+    }
+    if let Some(drought) = grid.drought(PieceKind::I) {
+        lines.insert(5, format!("I drought {}", drought));
+    }
+    if let Some(caption) = game.config().caption_text() {
+        lines.insert(0, caption);
+    }
+    if grid.blind() {
+        lines.push("blind".to_string());
+        lines.push(String::new());
+    } else if grid.next_pieces().next().is_some() {
+        lines.push("next".to_string());
+        for piece in grid.next_pieces() {
+            lines.extend(mini_piece(&grid.mirror_piece(piece), grid.palette()));
+            lines.push(String::new());
+        }
+    }
+    if !grid.hold_enabled() {
+        lines.push("\x1b[2mno hold".to_string());
+    } else {
+        lines.push("hold".to_string());
+        for (i, piece) in grid.held_pieces().enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            lines.extend(mini_piece(&grid.mirror_piece(piece), grid.palette()));
+        }
+    }
+    lines.push(String::new());
+    match game.state() {
+        GameState::Countdown(frame_timer) => {
+            let steps_left = COUNTDOWN_STEPS.saturating_sub(frame_timer.frame().max(1));
+            lines.push(if steps_left > 0 { steps_left.to_string() } else { "GO".to_string() });
+        },
+        GameState::Menu(menu) => {
+            for (label, selected) in menu.lines() {
+                lines.push(format!("{} {}", if selected { ">" } else { " " }, label));
+            }
+            lines.push("enter to start".to_string());
+        },
+        GameState::Loading(_) => lines.push("LOADING".to_string()),
+        GameState::LoadFailed(_, err) => {
+            lines.push("LOAD FAILED".to_string());
+            lines.push(err.clone());
+            lines.push("enter to go on".to_string());
+        },
+        GameState::ConfirmQuit(_) => lines.push("QUIT? Y / N".to_string()),
+        GameState::Paused(_) => lines.push("PAUSED".to_string()),
+        GameState::Victory | GameState::GameOver => {
+            let seconds = game.play_time() / SECOND;
+            lines.push(if let GameState::Victory = game.state() { "YOU WIN" } else { "GAME OVER" }.to_string());
+            if let (GameState::GameOver, Some(reason)) = (game.state(), grid.game_over_reason()) {
+                lines.push(reason.to_string());
+            }
+            lines.push(format!("{}:{:05.2}", (seconds / 60.0) as u32, seconds % 60.0));
+        },
+        GameState::Playing => (),
+    }
+    for popup in grid.popups().iter() {
+        lines.extend(popup.lines().iter().cloned());
+    }
+    lines
+}
+
+// the piece's layout drawn with blocks, moved back to the origin from its spawn position
+fn mini_piece(piece: &Tetrinome, palette: &Palette) -> Vec<String> {
+    let mut piece = piece.clone();
+    let min_x = piece.bones().iter().map(|bone| bone.coord.x ).min().unwrap_or(0);
+    let min_y = piece.bones().iter().map(|bone| bone.coord.y ).min().unwrap_or(0);
+    piece.trans_change(&Coord{x: -min_x, y: -min_y});
+    let height = piece.bones().iter().map(|bone| bone.coord.y + 1 ).max().unwrap_or(0);
+    let width = piece.bones().iter().map(|bone| bone.coord.x + 1 ).max().unwrap_or(0);
+    (0..height).map(|y| {
+        let mut line = String::new();
+        for x in 0..width {
+            match piece.bones().iter().find(|bone| bone.coord.x == x && bone.coord.y == y ) {
+                Some(bone) => {
+                    let (r, g, b) = palette.color(bone).rgb();
+                    line.push_str(&format!("\x1b[48;2;{};{};{}m  \x1b[0m", r, g, b));
+                },
+                None => line.push_str("  "),
+            }
+        }
+        line
+    }).collect()
+}
+
+// the whole frame, drawn over the last one from the top left
+fn render(game: &mut Game, playback: Option<&Playback>) -> String {
+    let cells = board_cells(game);
+    let sidebar = sidebar(game, playback);
+    let grid = game.grid();
+    let mut out = String::from("\x1b[H");
+    for y in 0..grid.height() {
+        out.push('|');
+        for x in 0..grid.width() {
+            draw_cell(&mut out, grid, cells[(y * grid.width() + x) as usize], y);
+        }
+        out.push_str("\x1b[0m|  ");
+        out.push_str(sidebar.get(y as usize).map(String::as_str).unwrap_or(""));
+        out.push_str("\x1b[0m\x1b[K\r\n"); // clears what's left of the last frame's line
+    }
+    out.push('+');
+    out.push_str(&"--".repeat(grid.width() as usize));
+    out.push_str("+\x1b[K\r\n");
+    out
+}
+
+// writes the game's replay to the --record file and the game to the --save file
+fn save_record(game: &Game) {
+    let config = game.config();
+    let files = [(&config.record, game.replay().to_text()), (&config.save, game.save().to_text())];
+    for (path, text) in files.iter() {
+        if let Some(path) = path {
+            if let Err(err) = std::fs::write(path, text) {
+                eprintln!("{}", Error::Io(err));
+            }
+        }
+    }
+}
+
+// a new game of the config, picking up the --load save if there is one, a save that doesn't fit the config starts a new game
+fn new_game(config: Config) -> Game {
+    let save = config.load.clone();
+    let mut game = Game::new(config);
+    if let Some(save) = save {
+        if let Err(err) = game.load(&save) {
+            eprintln!("{}", err);
+            game = Game::new(game.config().clone());
+        }
+    }
+    game
+}
+
+fn main() -> io::Result<()> {
+    let config = Config::from_args();
+    set_updates_per_sec(config.updates_per_sec);
+    if config.print_sequence > 0 {
+        let n = config.print_sequence;
+        println!("{}", Game::new(config).grid().upcoming_pieces(n));
+        return Ok(())
+    }
+    let (mut playback, mut game) = match &config.replay {
+        Some(replay) => {
+            let (playback, game) = Playback::new(&config, replay.clone());
+            (Some(playback), game)
+        },
+        None => (None, new_game(config)),
+    };
+
+    // stdin blocks so it is read on its own thread, the bytes are taken every update
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0; 16];
+        while let Ok(n) = stdin.read(&mut buf) {
+            if n == 0 || sender.send(buf[..n].to_vec()).is_err() {
+                break
+            }
+        }
+    });
+
+    print!("\x1b]0;{}\x07", game.config().title); // the terminal window's title
+    let _raw_mode = RawMode::enable()?;
+    let mut stdout = io::stdout();
+    let update = Duration::from_micros((millis_per_update() * 1000.0) as u64);
+    let mut next_update = Instant::now();
+    loop {
+        let bytes: Vec<u8> = receiver.try_iter().flatten().collect();
+        for action in actions(&bytes) {
+            if let Some(playback) = &mut playback {
+                if playback.press(action, &mut game) {
+                    return Ok(())
+                }
+                continue
+            }
+            if game.press(action) {
+                save_record(&game);
+                return Ok(())
+            }
+            game.release(action); // terminals only send presses, the os key repeat stands in for holding
+        }
+        match &mut playback {
+            Some(playback) => playback.update(&mut game),
+            None => game.tick(millis_per_update()),
+        }
+        stdout.write_all(render(&mut game, playback.as_ref()).as_bytes())?;
+        stdout.flush()?;
+
+        next_update += update;
+        if let Some(wait) = next_update.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+}
@@ -0,0 +1,782 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::{Error, Grid, PieceKind, Tetrinome};
+use crate::piece_file;
+use crate::replay::Replay;
+use crate::save::Save;
+use crate::timing::{GravityCurve, LOCK_DELAY, CLEAR_DURATION, PIECE_RATE_WINDOW, KILL_SCREEN_DELAY, SECOND};
+
+pub const MAX_PREVIEW_COUNT: usize = 6;
+pub const MAX_PLAYERS: usize = 2;
+pub const MAX_HOLD_DEPTH: usize = 3;
+pub const MAX_HIDDEN_ROWS: i16 = 20;
+pub const MAX_START_LEVEL: u32 = 15;
+
+// how a rotation that collides with a wall or the stack is resolved
+//
+// Naive shifts the piece away from the collision by half its width, the original behavior.
+// Srs tries the guideline kick offsets in order for the piece's orientation change. Since pieces rotate around
+// their pivot bone rather than the true SRS centers, the I piece (its own kick table) and the O piece (no pivot, never kicks)
+// behave the least like the guideline, the J, L, S, T and Z pieces share the standard table.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RotationSystem {
+    Naive,
+    Srs,
+}
+
+impl RotationSystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            RotationSystem::Naive => "naive",
+            RotationSystem::Srs => "srs",
+        }
+    }
+}
+
+impl FromStr for RotationSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "naive" => Ok(RotationSystem::Naive),
+            "srs" => Ok(RotationSystem::Srs),
+            _ => Err("expected naive or srs".to_string()),
+        }
+    }
+}
+
+// how a newly spawned piece is rotated
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnOrientation {
+    Flat, // always the layout from from_piece, as in the guideline
+    Random, // a random 1 step rotation in either direction
+}
+
+impl FromStr for SpawnOrientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flat" => Ok(SpawnOrientation::Flat),
+            "random" => Ok(SpawnOrientation::Random),
+            _ => Err("expected flat or random".to_string()),
+        }
+    }
+}
+
+// the column a newly spawned piece starts in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnColumn {
+    Random, // anywhere in the middle of the board, drawn from the game's seeded rng
+    Center, // always the middle, so the same placements are the same inputs every time
+}
+
+impl FromStr for SpawnColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(SpawnColumn::Random),
+            "center" => Ok(SpawnColumn::Center),
+            _ => Err("expected random or center".to_string()),
+        }
+    }
+}
+
+// the ruleset for a game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    Marathon,
+    Practice, // placements can be undone
+    Survival, // garbage rises from the floor sooner and sooner until the stack is pushed over the top
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Marathon => "marathon",
+            Mode::Practice => "practice",
+            Mode::Survival => "survival",
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "marathon" => Ok(Mode::Marathon),
+            "practice" => Ok(Mode::Practice),
+            "survival" => Ok(Mode::Survival),
+            _ => Err("expected marathon, practice or survival".to_string()),
+        }
+    }
+}
+
+// the points awarded for clears and drops, see score::rules
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scoring {
+    Guideline,
+    Nes,
+}
+
+impl FromStr for Scoring {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "guideline" => Ok(Scoring::Guideline),
+            "nes" => Ok(Scoring::Nes),
+            _ => Err("expected guideline or nes".to_string()),
+        }
+    }
+}
+
+// the set of pieces that spawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Variant {
+    Standard, // the 7 tetrinomes
+    Pentomino, // the 12 five bone pieces
+    Custom, // up to 8 pieces read from a piece file, with or without the tetrinomes
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(Variant::Standard),
+            "pentomino" => Ok(Variant::Pentomino),
+            "custom" => Ok(Variant::Custom),
+            _ => Err("expected standard, pentomino or custom".to_string()),
+        }
+    }
+}
+
+// how the blocks above fall once rows are cleared
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineGravity {
+    Naive, // each row above drops by the number of rows cleared below it
+    Sticky, // connected groups of blocks fall together until they land
+    Cascade, // sticky, and rows filled by the falling groups clear as well
+}
+
+impl FromStr for LineGravity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "naive" => Ok(LineGravity::Naive),
+            "sticky" => Ok(LineGravity::Sticky),
+            "cascade" => Ok(LineGravity::Cascade),
+            _ => Err("expected naive, sticky or cascade".to_string()),
+        }
+    }
+}
+
+// the order the blocks of a full row start their clear animation in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClearWave {
+    LeftToRight,
+    CenterOut, // from the middle columns towards both walls
+    Random, // a fixed scramble of the columns that changes with the row, so replays look the same
+}
+
+impl ClearWave {
+    // how far through the wave the block in column x of the row starts, from 0 for the first to 1 for the last
+    pub fn offset(self, x: i16, row: i16, width: i16) -> f64 {
+        let last = f64::from((width - 1).max(1));
+        match self {
+            ClearWave::LeftToRight => f64::from(x) / last,
+            ClearWave::CenterOut => (f64::from(x) - last / 2.0).abs() / (last / 2.0),
+            ClearWave::Random => f64::from((x * 7 + row * 3).rem_euclid(width)) / last, // 7 is coprime to the board widths so each column gets its own step
+        }
+    }
+}
+
+impl FromStr for ClearWave {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" | "left-to-right" => Ok(ClearWave::LeftToRight),
+            "center" | "center-out" => Ok(ClearWave::CenterOut),
+            "random" => Ok(ClearWave::Random),
+            _ => Err("expected left-to-right, center-out or random".to_string()),
+        }
+    }
+}
+
+// when the shadow piece is drawn, cycled with the ghost toggle key
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ghost {
+    Off,
+    Always,
+    OnDemand, // only while the ghost key is held, so the landing spot is learned with the shadow there when it's needed
+}
+
+impl Ghost {
+    pub fn next(self) -> Self {
+        match self {
+            Ghost::Off => Ghost::Always,
+            Ghost::Always => Ghost::OnDemand,
+            Ghost::OnDemand => Ghost::Off,
+        }
+    }
+}
+
+impl FromStr for Ghost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Ghost::Off),
+            "always" => Ok(Ghost::Always),
+            "on-demand" => Ok(Ghost::OnDemand),
+            _ => Err("expected off, always or on-demand".to_string()),
+        }
+    }
+}
+
+// the color the shadow piece is drawn in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GhostColor {
+    Dimmed, // the piece's own colors darkened
+    Gray, // one neutral color whatever the piece
+}
+
+impl FromStr for GhostColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dimmed" => Ok(GhostColor::Dimmed),
+            "gray" | "grey" => Ok(GhostColor::Gray),
+            _ => Err("expected dimmed or gray".to_string()),
+        }
+    }
+}
+
+// which of the guideline's top out conditions end the game, a piece that breaks a rule that's off is left where it is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TopOutRules {
+    pub block_out: bool, // a new piece spawns over the stack
+    pub lock_out: bool, // a piece locks entirely in the hidden rows
+}
+
+impl Default for TopOutRules {
+    fn default() -> Self {
+        Self {
+            block_out: true,
+            lock_out: true,
+        }
+    }
+}
+
+impl FromStr for TopOutRules {
+    type Err = String;
+
+    // the rules separated by commas, ie. `block-out,lock-out`, or none
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Self{block_out: false, lock_out: false};
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(rules)
+        }
+        for rule in s.split(',') {
+            match rule.trim().to_lowercase().as_str() {
+                "block-out" => rules.block_out = true,
+                "lock-out" => rules.lock_out = true,
+                _ => return Err("expected block-out, lock-out, both separated by a comma or none".to_string()),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+// how much faster the piece falls while down is held
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SoftDrop {
+    Factor(f64), // the fall rate is divided by the factor
+    Rate(f64), // the piece moves down this many rows per second on top of the gravity, whatever the level
+    Instant, // the piece moves straight to where it would land without locking
+}
+
+impl FromStr for SoftDrop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "instant" => Ok(SoftDrop::Instant),
+            rate if rate.ends_with("/s") => rate.trim_end_matches("/s").parse().map(SoftDrop::Rate).map_err(|_| "expected rows per second, ie. 40/s".to_string()),
+            factor => factor.parse().map(SoftDrop::Factor).map_err(|_| "expected a factor, rows per second or instant".to_string()),
+        }
+    }
+}
+
+impl Display for SoftDrop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SoftDrop::Factor(factor) => write!(f, "x{}", factor),
+            SoftDrop::Rate(rate) => write!(f, "{}/s", rate),
+            SoftDrop::Instant => write!(f, "instant"),
+        }
+    }
+}
+
+// the timings competitive players tune, consulted by the game's input and gravity code
+#[derive(Copy, Clone, Debug)]
+pub struct Handling {
+    pub das: f64, // milliseconds a move key is held before it auto-repeats
+    pub arr: f64, // milliseconds between auto-repeated moves, 0 slides the piece to the wall at once
+    pub soft_drop: SoftDrop,
+    pub lock_delay: f64, // least milliseconds a landed piece rests before locking
+    pub lock_resets: u32, // moves or rotations while resting that restart the lock delay before the piece locks regardless
+}
+
+impl Default for Handling {
+    // the guideline's 10 and 2 frames at 60hz and 20x soft drop
+    fn default() -> Self {
+        Self {
+            das: 167.0,
+            arr: 33.0,
+            soft_drop: SoftDrop::Factor(20.0),
+            lock_delay: LOCK_DELAY,
+            lock_resets: 15,
+        }
+    }
+}
+
+impl Handling {
+    // reports and clamps the values that can't be played, a factor below 1 would slow the fall and a rate of 0 never repeats
+    fn validate(&mut self) {
+        for (flag, value) in [("--das", &mut self.das), ("--arr", &mut self.arr), ("--lock-delay", &mut self.lock_delay)].iter_mut() {
+            if **value < 0.0 {
+                eprintln!("{}", Error::InvalidConfig(format!("'{}' for '{}', expected a non-negative number of milliseconds", value, flag)));
+                **value = 0.0;
+            }
+        }
+        match &mut self.soft_drop {
+            SoftDrop::Factor(factor) if *factor < 1.0 => {
+                eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--soft-drop', expected a factor of at least 1", factor)));
+                *factor = 1.0;
+            },
+            SoftDrop::Rate(rate) if *rate <= 0.0 => {
+                eprintln!("{}", Error::InvalidConfig(format!("'{}/s' for '--soft-drop', expected more than 0 rows per second", rate)));
+                self.soft_drop = SoftDrop::Factor(1.0);
+            },
+            _ => (),
+        }
+    }
+}
+
+// text shown beside the board
+#[derive(Clone, Debug, PartialEq)]
+pub enum Caption {
+    None,
+    Mode, // the name of the mode being played
+    Text(String),
+}
+
+impl FromStr for Caption {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Caption::None),
+            "mode" => Ok(Caption::Mode),
+            _ => Ok(Caption::Text(s.to_string())),
+        }
+    }
+}
+
+// what is drawn behind the board's blocks
+#[derive(Clone, Debug, PartialEq)]
+pub enum Background {
+    Solid((u8, u8, u8)),
+    Gradient((u8, u8, u8), (u8, u8, u8)), // from the top color to the bottom color
+    Image(String), // a path under the static assets, dimmed so the blocks stand out, black if it can't be loaded
+}
+
+impl Background {
+    pub const BLACK: (u8, u8, u8) = (0, 0, 0);
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    // a hex color, `gradient:` and a top and bottom hex color separated by a comma, or `image:` and a path
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(colors) = s.strip_prefix("gradient:") {
+            let mut colors = colors.splitn(2, ',').map(parse_hex);
+            return match (colors.next(), colors.next()) {
+                (Some(top), Some(bottom)) => Ok(Background::Gradient(top?, bottom?)),
+                _ => Err("expected gradient:<top color>,<bottom color>".to_string()),
+            }
+        }
+        if let Some(path) = s.strip_prefix("image:") {
+            return Ok(Background::Image(path.to_string()))
+        }
+        match s.to_lowercase().as_str() {
+            "black" => Ok(Background::Solid(Self::BLACK)),
+            color => parse_hex(color).map(Background::Solid),
+        }
+    }
+}
+
+// a color written as rrggbb with or without a leading #
+fn parse_hex(s: &str) -> Result<(u8, u8, u8), String> {
+    let hex = s.trim().trim_start_matches('#');
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok() );
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok((r, g, b)),
+        _ => Err(format!("expected a color as rrggbb, found '{}'", s)),
+    }
+}
+
+// settings chosen at startup
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub mode: Mode,
+    pub players: usize, // boards side by side, 2 plays versus with clears sending garbage to the opponent
+    pub title: String, // the window title
+    pub caption: Caption,
+    pub background: Background,
+    pub rotation_system: RotationSystem,
+    pub rotate_repeat: bool, // holding a rotation key keeps rotating
+    pub rotation_preview: bool, // holding a rotation key outlines where the rotation lands, it's performed on release
+    pub spawn_orientation: SpawnOrientation,
+    pub spawn_column: SpawnColumn,
+    pub o_rotation: bool, // the O piece turns around its center bone, shifting a cell as it turns, instead of staying put
+    pub patterns: bool, // draw a pattern per piece kind so pieces don't rely on color alone
+    pub connected: bool, // a piece's bones and touching blocks of a color are drawn as one shape with an edge around it
+    pub blind: bool, // hide the next piece, composes with any mode
+    pub invisible: bool, // locked blocks fade out so the stack has to be remembered, composes with any mode
+    pub preview_count: usize, // next pieces shown, 0 to 6
+    pub variant: Variant,
+    pub custom_pieces: Vec<Tetrinome>, // the custom variant's pieces, read from the --pieces file
+    pub big: bool, // each cell is 2x2 on a board half the size
+    pub hidden_rows: i16, // rows above the visible board that pieces spawn into, not drawn unless show_hidden_rows
+    pub show_hidden_rows: bool, // draw the hidden rows above the board with smaller cells so spawning pieces are seen whole
+    pub top_out: TopOutRules,
+    pub debug: bool, // enables the debug keys, ie. clearing the board
+    pub line_gravity: LineGravity,
+    pub clear_wave: ClearWave,
+    pub clear_duration: f64, // milliseconds the line clear animation lasts, 0 clears at once
+    pub clear_scaling: f64, // share of clear_duration added for each row cleared at once past the first, 0 times every clear the same
+    pub handling: Handling,
+    pub scoring: Scoring,
+    pub soft_drop_points: u32, // times the scoring's points for each row the piece is soft dropped, 0 awards none
+    pub hard_drop_lock: bool, // pieces never lock from gravity or the lock delay, only an instant drop locks them
+    pub fall_reset: bool, // a left or right move restarts the wait for the piece's next fall, so sliding buys time
+    pub level_cap: u32, // marathon is won once the lines for this level are cleared, 0 plays forever
+    pub start_level: u32, // the level a game starts at, 1 to 15
+    pub menu: bool, // start on the menu and go back to it from the results
+    pub gravity_curve: GravityCurve, // milliseconds per row by level
+    pub smooth_fall: bool, // draw the falling piece between rows rather than snapping a row at a time
+    pub ghost: Ghost,
+    pub ghost_alpha: f32, // opacity of the shadow piece and the instant drop trail, 0 to 1
+    pub ghost_color: GhostColor,
+    pub ghost_height: i16, // the shadow piece is hidden once the stack is this many rows tall, 0 always shows it
+    pub piece_rate_window: f64, // milliseconds of play the pieces per second readout is taken over
+    pub updates_per_sec: f64, // how often the game logic runs, animations last as long at any rate
+    pub seed: u64, // seeds the game's random number generator so a game can be reproduced
+    pub sequence: Vec<PieceKind>, // pieces dealt in order instead of from the rng, the game ends once they run out, empty deals randomly
+    pub print_sequence: usize, // prints the letters of this many of the seed's pieces instead of playing, 0 plays normally
+    pub replay: Option<Replay>, // a recorded game to step through instead of playing, read from the --replay file
+    pub record: Option<String>, // the file the game's replay is written to on quitting
+    pub load: Option<Save>, // a saved game to pick up instead of starting a new one, read from the --load file
+    pub save: Option<String>, // the file the game is saved to on quitting
+    pub garbage_rows: i16, // rows of garbage the board starts with
+    pub kill_height: i16, // rows the stack stays at for kill_delay before the kill screen starts, 0 never starts it
+    pub kill_delay: f64, // milliseconds the stack stays at kill_height before the kill screen
+    pub garbage_mess: f64, // chance each garbage row moves its gap to a new random column, 0 lines the gaps up and 1 scatters them, 0 to 1
+    pub mono: bool, // draw the pieces in shades of one hue by their row instead of their color
+    pub mirror: bool, // start with the board mirrored left to right, the controls swapped to match
+    pub hold: bool, // the hold key swaps out the current piece, off for challenges without hold
+    pub hold_depth: usize, // pieces kept on hold, the hold key cycles through them once it's full
+    pub hold_animation: bool, // slide a held piece into the hold box, off for competitive play
+    pub juice: bool, // the lock flashes and the board shaking after a lock near the top
+    pub sound: bool, // a sound when a piece locks, loaded from the static folder
+    pub lock_pitch: bool, // the lock sound rises in pitch the higher the piece locks, one sound at any height without it
+    pub advice: bool, // outline the best spot for the current piece, for learning
+    pub items: bool, // pieces sometimes carry a bomb that clears its column when its row clears
+    pub editor: bool, // the cell under the mouse is highlighted and clicking it fills or empties it
+    #[cfg(feature = "bench")]
+    pub bench_placements: u32, // pieces placed by the headless benchmark instead of opening the window, 0 plays normally
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Marathon,
+            players: 1,
+            title: "Tetrust".to_string(),
+            caption: Caption::None,
+            background: Background::Solid(Background::BLACK),
+            rotation_system: RotationSystem::Srs,
+            rotate_repeat: false,
+            rotation_preview: false,
+            spawn_orientation: SpawnOrientation::Random,
+            spawn_column: SpawnColumn::Random,
+            o_rotation: false,
+            patterns: false,
+            connected: false,
+            blind: false,
+            invisible: false,
+            preview_count: 1,
+            variant: Variant::Standard,
+            custom_pieces: Vec::new(),
+            big: false,
+            hidden_rows: 0,
+            show_hidden_rows: false,
+            top_out: TopOutRules::default(),
+            debug: false,
+            line_gravity: LineGravity::Naive,
+            clear_wave: ClearWave::LeftToRight,
+            clear_duration: CLEAR_DURATION,
+            clear_scaling: 0.0,
+            handling: Handling::default(),
+            scoring: Scoring::Guideline,
+            soft_drop_points: 1,
+            hard_drop_lock: false,
+            fall_reset: false,
+            level_cap: 15,
+            start_level: 1,
+            menu: true,
+            gravity_curve: GravityCurve::default(),
+            smooth_fall: false,
+            ghost: Ghost::Always,
+            ghost_alpha: 1.0,
+            ghost_color: GhostColor::Dimmed,
+            ghost_height: 0,
+            piece_rate_window: PIECE_RATE_WINDOW,
+            updates_per_sec: 16.0,
+            seed: rand::random(),
+            sequence: Vec::new(),
+            print_sequence: 0,
+            replay: None,
+            record: None,
+            load: None,
+            save: None,
+            garbage_rows: 0,
+            kill_height: 0,
+            kill_delay: KILL_SCREEN_DELAY,
+            garbage_mess: 1.0,
+            mono: false,
+            mirror: false,
+            hold: true,
+            hold_depth: 1,
+            hold_animation: true,
+            juice: true,
+            sound: false,
+            lock_pitch: true,
+            advice: false,
+            items: false,
+            editor: false,
+            #[cfg(feature = "bench")]
+            bench_placements: 0,
+        }
+    }
+}
+
+impl Config {
+    // reads the settings from command line flags, ie. `--rotation naive`, falling back to the defaults
+    pub fn from_args() -> Self {
+        let mut config = Self::default();
+        let mut sequence = None; // read once the variant is known
+        let mut piece_file = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            let value = args.next().unwrap_or_default();
+            match flag.as_str() {
+                "--mode" => set_parsed(&mut config.mode, &value),
+                "--players" => set_parsed(&mut config.players, &value),
+                "--title" => config.title = value,
+                "--caption" => set_parsed(&mut config.caption, &value),
+                "--background" => set_parsed(&mut config.background, &value),
+                "--rotation" => set_parsed(&mut config.rotation_system, &value),
+                "--rotate-repeat" => set_parsed(&mut config.rotate_repeat, &value),
+                "--rotation-preview" => set_parsed(&mut config.rotation_preview, &value),
+                "--spawn" => set_parsed(&mut config.spawn_orientation, &value),
+                "--spawn-column" => set_parsed(&mut config.spawn_column, &value),
+                "--o-rotation" => set_parsed(&mut config.o_rotation, &value),
+                "--patterns" => set_parsed(&mut config.patterns, &value),
+                "--connected" => set_parsed(&mut config.connected, &value),
+                "--blind" => set_parsed(&mut config.blind, &value),
+                "--invisible" => set_parsed(&mut config.invisible, &value),
+                "--next" => set_parsed(&mut config.preview_count, &value),
+                "--variant" => set_parsed(&mut config.variant, &value),
+                "--pieces" => piece_file = Some(value),
+                "--big" => set_parsed(&mut config.big, &value),
+                "--hidden-rows" => set_parsed(&mut config.hidden_rows, &value),
+                "--show-hidden-rows" => set_parsed(&mut config.show_hidden_rows, &value),
+                "--top-out" => set_parsed(&mut config.top_out, &value),
+                "--debug" => set_parsed(&mut config.debug, &value),
+                "--gravity" => set_parsed(&mut config.line_gravity, &value),
+                "--clear-wave" => set_parsed(&mut config.clear_wave, &value),
+                "--clear-duration" => match value.as_str() {
+                    "instant" => config.clear_duration = 0.0,
+                    _ => set_parsed(&mut config.clear_duration, &value),
+                },
+                "--clear-scaling" => set_parsed(&mut config.clear_scaling, &value),
+                "--das" => set_parsed(&mut config.handling.das, &value),
+                "--arr" => set_parsed(&mut config.handling.arr, &value),
+                "--soft-drop" => set_parsed(&mut config.handling.soft_drop, &value),
+                "--scoring" => set_parsed(&mut config.scoring, &value),
+                "--soft-drop-points" => set_parsed(&mut config.soft_drop_points, &value),
+                "--hard-drop-lock" => set_parsed(&mut config.hard_drop_lock, &value),
+                "--fall-reset" => set_parsed(&mut config.fall_reset, &value),
+                "--lock-delay" => set_parsed(&mut config.handling.lock_delay, &value),
+                "--lock-resets" => set_parsed(&mut config.handling.lock_resets, &value),
+                "--level-cap" => set_parsed(&mut config.level_cap, &value),
+                "--level" => set_parsed(&mut config.start_level, &value),
+                "--menu" => set_parsed(&mut config.menu, &value),
+                "--gravity-curve" => set_parsed(&mut config.gravity_curve, &value),
+                "--smooth-fall" => set_parsed(&mut config.smooth_fall, &value),
+                "--ghost" => set_parsed(&mut config.ghost, &value),
+                "--ghost-alpha" => set_parsed(&mut config.ghost_alpha, &value),
+                "--ghost-color" => set_parsed(&mut config.ghost_color, &value),
+                "--ghost-height" => set_parsed(&mut config.ghost_height, &value),
+                "--pps-window" => set_parsed(&mut config.piece_rate_window, &value),
+                "--update-rate" => set_parsed(&mut config.updates_per_sec, &value),
+                "--seed" => set_parsed(&mut config.seed, &value),
+                "--sequence" => sequence = Some(value),
+                "--print-sequence" => set_parsed(&mut config.print_sequence, &value),
+                "--replay" => match read_replay(&value) {
+                    Ok(replay) => config.replay = Some(replay),
+                    Err(err) => eprintln!("{}", err),
+                },
+                "--record" => config.record = Some(value),
+                "--load" => match read_save(&value) {
+                    Ok(save) => config.load = Some(save),
+                    Err(err) => eprintln!("{}", err),
+                },
+                "--save" => config.save = Some(value),
+                "--garbage" => set_parsed(&mut config.garbage_rows, &value),
+                "--garbage-mess" => set_parsed(&mut config.garbage_mess, &value),
+                "--kill-height" => set_parsed(&mut config.kill_height, &value),
+                "--kill-delay" => set_parsed(&mut config.kill_delay, &value),
+                "--mono" => set_parsed(&mut config.mono, &value),
+                "--mirror" => set_parsed(&mut config.mirror, &value),
+                "--hold" => set_parsed(&mut config.hold, &value),
+                "--hold-depth" => set_parsed(&mut config.hold_depth, &value),
+                "--hold-animation" => set_parsed(&mut config.hold_animation, &value),
+                "--juice" => set_parsed(&mut config.juice, &value),
+                "--sound" => set_parsed(&mut config.sound, &value),
+                "--lock-pitch" => set_parsed(&mut config.lock_pitch, &value),
+                "--advice" => set_parsed(&mut config.advice, &value),
+                "--items" => set_parsed(&mut config.items, &value),
+                "--editor" => set_parsed(&mut config.editor, &value),
+                #[cfg(feature = "bench")]
+                "--bench" => set_parsed(&mut config.bench_placements, &value),
+                _ => eprintln!("ignoring unknown flag '{}'", flag),
+            }
+        }
+        match (config.variant, piece_file) {
+            (Variant::Custom, Some(path)) => match piece_file::load(&path) {
+                Ok(pieces) => config.custom_pieces = pieces,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    config.variant = Variant::Standard;
+                },
+            },
+            (Variant::Custom, None) => {
+                eprintln!("{}", Error::InvalidConfig("'--variant custom' needs a '--pieces' file".to_string()));
+                config.variant = Variant::Standard;
+            },
+            (_, Some(path)) => eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--pieces' is only read with '--variant custom'", path))),
+            (_, None) => (),
+        }
+        if let Some(sequence) = sequence {
+            match PieceKind::sequence(&sequence, &config.piece_kinds()) {
+                Ok(kinds) if kinds.is_empty() => eprintln!("{}", Error::InvalidConfig("'--sequence' has no pieces".to_string())),
+                Ok(kinds) => config.sequence = kinds,
+                Err(err) => eprintln!("{}", Error::InvalidConfig(format!("'{}', {}", sequence, err))),
+            }
+        }
+        config.ghost_alpha = if config.ghost_alpha.is_nan() { 0.0 } else { config.ghost_alpha.clamp(0.0, 1.0) };
+        if !(0.0..=1.0).contains(&config.garbage_mess) {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--garbage-mess', expected a chance from 0 to 1", config.garbage_mess)));
+            config.garbage_mess = if config.garbage_mess > 1.0 { 1.0 } else { 0.0 }; // NaN lines the gaps up
+        }
+        config.clear_duration = config.clear_duration.max(0.0);
+        config.clear_scaling = config.clear_scaling.max(0.0);
+        if config.piece_rate_window < SECOND {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--pps-window', expected at least {} milliseconds", config.piece_rate_window, SECOND)));
+            config.piece_rate_window = SECOND; // the rate is recalculated once a second
+        }
+        config.handling.validate();
+        if config.preview_count > MAX_PREVIEW_COUNT {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--next', expected 0 to {} pieces", config.preview_count, MAX_PREVIEW_COUNT)));
+            config.preview_count = MAX_PREVIEW_COUNT;
+        }
+        config.hidden_rows = config.hidden_rows.clamp(0, MAX_HIDDEN_ROWS);
+        if config.kill_height < 0 || config.kill_height > Grid::HEIGHT {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--kill-height', expected 0 to {} rows", config.kill_height, Grid::HEIGHT)));
+            config.kill_height = config.kill_height.clamp(0, Grid::HEIGHT);
+        }
+        config.kill_delay = config.kill_delay.max(0.0);
+        if config.start_level < 1 || config.start_level > MAX_START_LEVEL {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--level', expected 1 to {}", config.start_level, MAX_START_LEVEL)));
+            config.start_level = config.start_level.clamp(1, MAX_START_LEVEL);
+        }
+        if config.hold_depth < 1 || config.hold_depth > MAX_HOLD_DEPTH {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--hold-depth', expected 1 to {} pieces", config.hold_depth, MAX_HOLD_DEPTH)));
+            config.hold_depth = config.hold_depth.clamp(1, MAX_HOLD_DEPTH);
+        }
+        if config.players < 1 || config.players > MAX_PLAYERS {
+            eprintln!("{}", Error::InvalidConfig(format!("'{}' for '--players', expected 1 to {} players", config.players, MAX_PLAYERS)));
+            config.players = config.players.clamp(1, MAX_PLAYERS);
+        }
+        if config.replay.is_some() && config.players > 1 {
+            eprintln!("{}", Error::InvalidConfig("replays are of one player's game, '--players' is ignored".to_string()));
+            config.players = 1;
+        }
+        if config.load.is_some() && (config.replay.is_some() || config.players > 1) {
+            eprintln!("{}", Error::InvalidConfig("saves are of one player's game, '--load' is ignored with '--replay' or '--players'".to_string()));
+            config.load = None;
+        }
+        config.menu &= config.players == 1 && config.load.is_none(); // versus starts both boards at once, a saved game picks up where it was left
+        config.updates_per_sec = if config.updates_per_sec.is_nan() { 1.0 } else { config.updates_per_sec.clamp(1.0, 240.0) };
+        config
+    }
+
+    // the kinds of the pieces that spawn
+    pub fn piece_kinds(&self) -> Vec<PieceKind> {
+        match self.variant {
+            Variant::Custom => self.custom_pieces.iter().map(Tetrinome::kind).collect(),
+            variant => PieceKind::of_variant(variant),
+        }
+    }
+
+    // the text of the caption, None when there's no caption
+    pub fn caption_text(&self) -> Option<String> {
+        match &self.caption {
+            Caption::None => None,
+            Caption::Mode => Some(self.mode.name().to_string()),
+            Caption::Text(text) => Some(text.clone()),
+        }
+    }
+}
+
+// overwrites the setting if the value parses, otherwise reports the error and keeps the default
+fn set_parsed<T: FromStr>(setting: &mut T, value: &str) where T::Err: Display {
+    match value.parse() {
+        Ok(parsed) => *setting = parsed,
+        Err(err) => eprintln!("{}", Error::InvalidConfig(format!("'{}', {}", value, err))),
+    }
+}
+
+// reads a replay written by Replay::to_text
+fn read_replay(path: &str) -> Result<Replay, Error> {
+    let text = std::fs::read_to_string(path).map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", path, err)) )?;
+    Ok(Replay::from_text(&text)?)
+}
+
+// reads a save written by Save::to_text
+fn read_save(path: &str) -> Result<Save, Error> {
+    let text = std::fs::read_to_string(path).map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", path, err)) )?;
+    Ok(Save::from_text(&text)?)
+}
@@ -0,0 +1,49 @@
+// the ways the game can fail that a frontend reports to the player, kept apart from the frontend's own rendering errors
+use std::fmt;
+use std::io;
+
+use crate::replay::ReplayError;
+use crate::save::SaveError;
+
+#[derive(Debug)]
+pub enum Error {
+    AssetLoad(String), // the path of an image or other asset that couldn't be loaded
+    Io(io::Error), // reading or writing a save or replay file
+    InvalidConfig(String),
+    InvalidLayout(String), // a board or piece layout that can't be read
+    InvalidReplay(ReplayError),
+    InvalidSave(SaveError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::AssetLoad(path) => write!(f, "couldn't load the asset '{}'", path),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            Error::InvalidLayout(reason) => write!(f, "invalid layout: {}", reason),
+            Error::InvalidReplay(err) => write!(f, "{}", err),
+            Error::InvalidSave(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ReplayError> for Error {
+    fn from(err: ReplayError) -> Self {
+        Error::InvalidReplay(err)
+    }
+}
+
+impl From<SaveError> for Error {
+    fn from(err: SaveError) -> Self {
+        Error::InvalidSave(err)
+    }
+}
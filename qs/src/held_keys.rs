@@ -0,0 +1,53 @@
+// the gameplay keys held down and for how long, the auto-repeats and the soft drop read from it
+use crate::primitives::Action;
+
+#[derive(Clone, Debug, Default)]
+pub struct HeldKeys {
+    held: Vec<(Action, f64)>, // each held action and the milliseconds it's been held, oldest press first
+}
+
+impl HeldKeys {
+    // marks the action held, returns false if it already was, ie. an os key repeat
+    pub fn press(&mut self, action: Action) -> bool {
+        if self.is_held(action) {
+            return false
+        }
+        self.held.push((action, 0.0));
+        true
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.held.retain(|(held, _)| *held != action );
+    }
+
+    // forgets every held key, for when releases can be missed, ie. while paused or after the window loses focus
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.iter().any(|(held, _)| *held == action )
+    }
+
+    // the held actions, oldest press first
+    pub fn actions<'a>(&'a self) -> impl Iterator<Item = Action> + 'a {
+        self.held.iter().map(|(held, _)| *held )
+    }
+
+    // milliseconds the action has been held, None if it isn't
+    pub fn duration(&self, action: Action) -> Option<f64> {
+        self.held.iter().find(|(held, _)| *held == action ).map(|(_, duration)| *duration )
+    }
+
+    // the most recently pressed of the actions that's still held
+    pub fn latest(&self, actions: &[Action]) -> Option<Action> {
+        self.held.iter().rev().map(|(held, _)| *held ).find(|held| actions.contains(held) )
+    }
+
+    // ages the held keys by the milliseconds since the last update
+    pub fn update(&mut self, elapsed: f64) {
+        for (_, duration) in self.held.iter_mut() {
+            *duration += elapsed;
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// callbacks for code built on the library to follow a game without threading state through it, ie. overlays, bots and analytics
+// set through Game::hooks_mut, the grid records what happens during an update and the game fires the hooks for it once the update is done
+// the hooks are shared by a game's clones, so restoring a replay snapshot keeps them, while the grid's own copies, ie. the advice's simulations, never fire them
+use super::{Bone, PieceKind, Tetrinome};
+use crate::score::Score;
+
+// a piece committed to the stack
+#[derive(Clone, Debug)]
+pub struct LockInfo {
+    pub kind: PieceKind,
+    pub bones: Vec<Bone>, // where the piece locked, bones above the hidden rows included
+    pub hard_drop: bool, // locked by an instant drop rather than the lock delay
+    pub t_spin: bool,
+    pub lines: usize, // rows the piece filled, still animating when the clear takes time
+    pub height: f32, // how high the piece's top locked, 0 on the floor to 1 in the top visible row or above it
+}
+
+impl LockInfo {
+    // which of n lock sounds pitched from low to high fits the height, the lowest on the floor and the highest at the top
+    pub fn pitch(&self, n_pitches: usize) -> usize {
+        let highest = n_pitches.saturating_sub(1);
+        ((self.height * highest as f32).round() as usize).min(highest)
+    }
+}
+
+// rows scored as one clear, the rows the piece filled or the rows a cascade filled
+#[derive(Clone, Debug)]
+pub struct ClearInfo {
+    pub lines: usize,
+    pub t_spin: bool,
+    pub back_to_back: bool,
+    pub points: u32, // awarded for the clear, the level and any bonuses included
+    pub cascade: bool, // filled by blocks falling after an earlier clear with cascade gravity
+}
+
+pub type SpawnHook = Box<dyn FnMut(&Tetrinome)>;
+pub type LockHook = Box<dyn FnMut(&LockInfo)>;
+pub type ClearHook = Box<dyn FnMut(&ClearInfo)>;
+pub type LevelUpHook = Box<dyn FnMut(u32)>;
+pub type GameOverHook = Box<dyn FnMut(&Score)>;
+
+#[derive(Default)]
+pub struct Hooks {
+    pub on_spawn: Option<SpawnHook>, // a piece dealt onto the board, not one swapped in from hold
+    pub on_lock: Option<LockHook>,
+    pub on_clear: Option<ClearHook>,
+    pub on_level_up: Option<LevelUpHook>, // the new level
+    pub on_game_over: Option<GameOverHook>, // topped out, pushed over by garbage or out of scripted pieces
+}
+
+// what happened on the board, recorded by the grid until the game fires the hooks
+#[derive(Clone, Debug)]
+pub(crate) enum HookEvent {
+    Spawn(Tetrinome),
+    Lock(LockInfo),
+    Clear(ClearInfo),
+    LevelUp(u32),
+    GameOver(Score),
+}
+
+impl Hooks {
+    pub(crate) fn fire(&mut self, event: &HookEvent) {
+        match event {
+            HookEvent::Spawn(piece) => self.spawn(piece),
+            HookEvent::Lock(info) => self.lock(info),
+            HookEvent::Clear(info) => self.clear(info),
+            HookEvent::LevelUp(level) => self.level_up(*level),
+            HookEvent::GameOver(score) => self.game_over(score),
+        }
+    }
+
+    pub fn spawn(&mut self, piece: &Tetrinome) {
+        if let Some(on_spawn) = &mut self.on_spawn {
+            on_spawn(piece);
+        }
+    }
+
+    pub fn lock(&mut self, info: &LockInfo) {
+        if let Some(on_lock) = &mut self.on_lock {
+            on_lock(info);
+        }
+    }
+
+    pub fn clear(&mut self, info: &ClearInfo) {
+        if let Some(on_clear) = &mut self.on_clear {
+            on_clear(info);
+        }
+    }
+
+    pub fn level_up(&mut self, level: u32) {
+        if let Some(on_level_up) = &mut self.on_level_up {
+            on_level_up(level);
+        }
+    }
+
+    pub fn game_over(&mut self, score: &Score) {
+        if let Some(on_game_over) = &mut self.on_game_over {
+            on_game_over(score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    use crate::PieceKind;
+    use crate::primitives::Action;
+    use crate::test_util::{playing, fill_row, piece_columns};
+
+    #[test]
+    fn each_hook_fires_once() {
+        let mut game = playing(&[PieceKind::I]); // the game ends once the piece locks
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let mut hooks = game.hooks_mut();
+        let log = fired.clone();
+        hooks.on_spawn = Some(Box::new(move |piece| log.borrow_mut().push(format!("spawn {}", piece.kind().letter())) ));
+        let log = fired.clone();
+        hooks.on_lock = Some(Box::new(move |info| log.borrow_mut().push(format!("lock {}", info.lines)) ));
+        let log = fired.clone();
+        hooks.on_clear = Some(Box::new(move |info| log.borrow_mut().push(format!("clear {}", info.lines)) ));
+        let log = fired.clone();
+        hooks.on_level_up = Some(Box::new(move |level| log.borrow_mut().push(format!("level {}", level)) ));
+        let log = fired.clone();
+        hooks.on_game_over = Some(Box::new(move |score| log.borrow_mut().push(format!("game over {}", score.lines)) ));
+        drop(hooks);
+
+        let floor = game.grid().height() - 1;
+        let open = piece_columns(&game);
+        fill_row(&mut game, floor, &open);
+        game.grid.score.lines = 9; // the next line reaches level 2
+        game.press(Action::Drop);
+        game.tick(1.0);
+
+        assert_eq!(*fired.borrow(), ["spawn I", "lock 1", "clear 1", "level 2", "game over 10"]);
+    }
+
+    #[test]
+    fn clones_share_the_hooks() {
+        let mut game = playing(&[PieceKind::I, PieceKind::I]);
+        let spawns = Rc::new(RefCell::new(0));
+        let count = spawns.clone();
+        game.hooks_mut().on_spawn = Some(Box::new(move |_| *count.borrow_mut() += 1 ));
+        let mut snapshot = game.clone();
+        game.grid().upcoming_pieces(2); // simulated on a copy of the grid
+        snapshot.tick(1.0);
+        assert_eq!(*spawns.borrow(), 1);
+    }
+
+    #[test]
+    fn the_lock_pitch_rises_with_the_stack() {
+        let mut game = playing(&[PieceKind::O; 3]);
+        let pitches = Rc::new(RefCell::new(Vec::new()));
+        let log = pitches.clone();
+        game.hooks_mut().on_lock = Some(Box::new(move |info| log.borrow_mut().push((info.pitch(5), info.pitch(1))) ));
+        let drop = |game: &mut crate::Game| {
+            game.press(Action::Drop);
+            game.tick(1.0);
+            game.release(Action::Drop);
+        };
+        drop(&mut game); // on the floor
+
+        game.grid.clear_board();
+        let gap = (0..game.grid().width()).find(|x| !piece_columns(&game).contains(x) ).unwrap();
+        for y in 2..game.grid().height() {
+            fill_row(&mut game, y, &[gap]);
+        }
+        drop(&mut game); // in the top rows
+        assert_eq!(*pitches.borrow(), [(0, 0), (4, 0)]);
+    }
+}
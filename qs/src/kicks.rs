@@ -0,0 +1,53 @@
+use super::PieceKind;
+use crate::primitives::{Coord, Orientation};
+
+pub const NUM_KICKS: usize = 5;
+
+// srs offsets are written with y pointing up, the grid's y points down
+const fn kick(x: i16, y: i16) -> Coord {
+    Coord{x, y: -y}
+}
+
+const JLSTZ_KICKS: [[Coord; NUM_KICKS]; 8] = [
+    [kick(0, 0), kick(-1, 0), kick(-1, 1), kick(0, -2), kick(-1, -2)], // Spawn -> Right
+    [kick(0, 0), kick(1, 0), kick(1, -1), kick(0, 2), kick(1, 2)], // Right -> Spawn
+    [kick(0, 0), kick(1, 0), kick(1, -1), kick(0, 2), kick(1, 2)], // Right -> Two
+    [kick(0, 0), kick(-1, 0), kick(-1, 1), kick(0, -2), kick(-1, -2)], // Two -> Right
+    [kick(0, 0), kick(1, 0), kick(1, 1), kick(0, -2), kick(1, -2)], // Two -> Left
+    [kick(0, 0), kick(-1, 0), kick(-1, -1), kick(0, 2), kick(-1, 2)], // Left -> Two
+    [kick(0, 0), kick(-1, 0), kick(-1, -1), kick(0, 2), kick(-1, 2)], // Left -> Spawn
+    [kick(0, 0), kick(1, 0), kick(1, 1), kick(0, -2), kick(1, -2)], // Spawn -> Left
+];
+
+const I_KICKS: [[Coord; NUM_KICKS]; 8] = [
+    [kick(0, 0), kick(-2, 0), kick(1, 0), kick(-2, -1), kick(1, 2)], // Spawn -> Right
+    [kick(0, 0), kick(2, 0), kick(-1, 0), kick(2, 1), kick(-1, -2)], // Right -> Spawn
+    [kick(0, 0), kick(-1, 0), kick(2, 0), kick(-1, 2), kick(2, -1)], // Right -> Two
+    [kick(0, 0), kick(1, 0), kick(-2, 0), kick(1, -2), kick(-2, 1)], // Two -> Right
+    [kick(0, 0), kick(2, 0), kick(-1, 0), kick(2, 1), kick(-1, -2)], // Two -> Left
+    [kick(0, 0), kick(-2, 0), kick(1, 0), kick(-2, -1), kick(1, 2)], // Left -> Two
+    [kick(0, 0), kick(1, 0), kick(-2, 0), kick(1, -2), kick(-2, 1)], // Left -> Spawn
+    [kick(0, 0), kick(-1, 0), kick(2, 0), kick(-1, 2), kick(2, -1)], // Spawn -> Left
+];
+
+const NO_KICKS: [Coord; NUM_KICKS] = [kick(0, 0); NUM_KICKS];
+
+// returns the offsets to try in order when rotating the piece kind between the orientations
+pub fn kicks(kind: PieceKind, from: Orientation, to: Orientation) -> [Coord; NUM_KICKS] {
+    let i = match (from, to) {
+        (Orientation::Spawn, Orientation::Right) => 0,
+        (Orientation::Right, Orientation::Spawn) => 1,
+        (Orientation::Right, Orientation::Two) => 2,
+        (Orientation::Two, Orientation::Right) => 3,
+        (Orientation::Two, Orientation::Left) => 4,
+        (Orientation::Left, Orientation::Two) => 5,
+        (Orientation::Left, Orientation::Spawn) => 6,
+        (Orientation::Spawn, Orientation::Left) => 7,
+        _ => return NO_KICKS, // not a quarter turn
+    };
+    match kind {
+        PieceKind::I => I_KICKS[i],
+        PieceKind::O => NO_KICKS,
+        _ => JLSTZ_KICKS[i],
+    }
+}
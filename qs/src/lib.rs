@@ -0,0 +1,3837 @@
+// the game without a frontend, the board, the pieces and the game states driven by Game's press, release and tick
+// frontends map their inputs to Actions and draw from the accessors
+use rand::{Rng, SeedableRng};
+#[cfg(test)]
+use rand::rngs::StdRng;
+use rand::distributions::{Distribution, Standard};
+
+use nalgebra::{Vector2, Matrix2};
+
+use std::ops::Range;
+use std::collections::{HashSet, VecDeque};
+use std::sync::OnceLock;
+use std::rc::Rc;
+use std::cell::{RefCell, RefMut};
+
+pub mod timing;
+use timing::{millis_per_update, Timer, SECOND, Repeat, RiseTimer, KillTimer, PieceRate, DROP_ROW_DURATION, CLEAR_DURATION, SOFT_LOCK_FLASH_DURATION, HARD_LOCK_FLASH_DURATION, INVISIBLE_DELAY, INVISIBLE_FADE_DURATION, HOLD_SWAP_DURATION, SHAKE_DURATION, POPUP_DURATION, COUNTDOWN_DURATION, COUNTDOWN_STEPS, ROTATE_REPEAT_DELAY, ROTATE_REPEAT_INTERVAL};
+pub mod animation;
+use animation::{FrameTimer, FrameState};
+pub mod primitives;
+use primitives::{Coord, Pos, OutOfBounds, Direction, Rotation, Orientation, Collision, Color, Action};
+pub mod config;
+use config::{Config, Ghost, Handling, SoftDrop, Mode, RotationSystem, SpawnOrientation, SpawnColumn, Variant, LineGravity, ClearWave, TopOutRules};
+mod kicks;
+use kicks::kicks;
+mod advice;
+pub mod score;
+use score::{Clear, Score};
+pub mod replay;
+use replay::Replay;
+pub mod save;
+use save::{Save, SavedPiece};
+mod rng;
+use rng::Pcg32;
+mod error;
+pub use error::Error;
+mod held_keys;
+pub use held_keys::HeldKeys;
+mod palette;
+pub use palette::Palette;
+mod hooks;
+pub use hooks::{Hooks, LockInfo, ClearInfo, SpawnHook, LockHook, ClearHook, LevelUpHook, GameOverHook};
+use hooks::HookEvent;
+pub mod menu;
+use menu::Menu;
+pub mod piece_file;
+pub mod playback;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(test)]
+mod test_util;
+
+// a single cell of a piece, or of the stack once the piece is committed
+#[derive(Copy, Clone, Debug)]
+pub struct Bone {
+    pub color: Color,
+    pub coord: Coord,
+    pub kind: PieceKind, // the piece the bone came from, kept once committed to the grid
+    pub special: Option<Special>, // only spawned in item mode
+}
+
+// an item carried by a bone
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Special {
+    Bomb, // clearing a row with a bomb in it clears the bomb's whole column as well
+}
+
+// what ended a lost game, shown with the results
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TopOut {
+    BlockOut, // a new piece spawned over the stack
+    LockOut, // a piece locked without reaching the visible rows
+    Garbage, // rising garbage pushed the stack over the top
+}
+
+impl TopOut {
+    pub fn name(self) -> &'static str {
+        match self {
+            TopOut::BlockOut => "block out",
+            TopOut::LockOut => "lock out",
+            TopOut::Garbage => "top out",
+        }
+    }
+}
+
+impl Default for Bone {
+    fn default() -> Self {
+        Bone::new(Color::Black, Coord{x: 0, y: 0}, PieceKind::I)
+    }
+}
+
+impl Bone {
+    fn new(color: Color, coord: Coord, kind: PieceKind) -> Self {
+        Self {
+            color,
+            coord,
+            kind,
+            special: None,
+        }
+    }
+
+    fn clear_animate(&mut self, state: &FrameState) {
+        if let FrameState::Ready = state { 
+            self.color = self.color.next_color()
+        }
+    }
+}
+
+// number of piece kinds across all variants including garbage, stats are indexed by kind
+pub const NUM_PIECES: usize = 28;
+
+#[derive(Debug, Clone)]
+pub struct Tetrinome {
+    kind: PieceKind,
+    bones: Vec<Bone>, // any number of bones, 4 for tetrinomes and 5 for pentominoes
+    pivot: Option<usize>, // the bone it rotates around, None doesn't rotate
+    orientation: Orientation,
+}
+
+impl Tetrinome {
+    // drawn from the grid's seeded rng so the same seed spawns the same pieces
+    // without hidden rows the top of the piece spawns in the top row so all of it is visible and collides the same as on the rest of the board
+    // with hidden rows it spawns resting on the top visible row, as far into view as the hidden rows leave it
+    fn new<R: Rng>(pieces: &[Tetrinome], size: Size, spawn_orientation: SpawnOrientation, spawn_column: SpawnColumn, rng: &mut R) -> Self {
+        let mut new_piece: Self = rng.sample(Deal{pieces, orientation: spawn_orientation});
+        let (x_range, y) = new_piece.spawn_range(size);
+        let offset = match spawn_column {
+            SpawnColumn::Random => Coord::rand_x_offset(rng, x_range, y), // translate to random x in the middle of the grid
+            SpawnColumn::Center => Self::center_offset(x_range, y),
+        };
+        new_piece.trans_change(&offset);
+        new_piece
+    }
+
+    // a piece of a scripted sequence, in its spawn layout in the middle of the spawn range so the rng is left alone
+    fn scripted(pieces: &[Tetrinome], kind: PieceKind, size: Size) -> Self {
+        let mut new_piece = pieces.iter().find(|piece| piece.kind == kind ).cloned().unwrap_or_else(|| Self::from_piece(kind) ); // as the variant deals it
+        let (x_range, y) = new_piece.spawn_range(size);
+        new_piece.trans_change(&Self::center_offset(x_range, y));
+        new_piece
+    }
+
+    // the middle of the spawn range, the left of the two middles when there's an even number
+    fn center_offset(x_range: (i16, i16), y: i16) -> Coord {
+        Coord{x: (x_range.0 + x_range.1 - 1) / 2, y}
+    }
+
+    // the range of x offsets the piece can spawn at, the end exclusive, and the y offset it spawns at
+    fn spawn_range(&self, size: Size) -> ((i16, i16), i16) {
+        let extent = self.bones.iter().map(|bone| bone.coord.x + 1 ).max().unwrap_or(1); // custom layouts can be wider than their bone count
+        let span = (self.bones.len() as i16).max(extent);
+        let max_x = (size.width - span).max(1); // the range can't be empty on narrow boards
+        let min_y = self.bones.iter().map(|bone| bone.coord.y ).min().unwrap_or(0); // rotated layouts can reach above their origin
+        let max_y = self.bones.iter().map(|bone| bone.coord.y ).max().unwrap_or(0);
+        ((span.min(max_x - 1).max(0), max_x), (-1 - max_y).max(size.top() - min_y))
+    }
+
+    // add offset
+    fn shift(&self, offset: Coord) -> Vec<Coord> {
+        self.bones.iter().map(|bone| bone.coord + offset ).collect()
+    }
+
+    // replace offset
+    fn trans_to(&mut self, new_coords: Vec<Coord>) {
+        self.bones.iter_mut().zip(new_coords).map(|(bone, new_coord)| bone.coord = new_coord ).collect()
+    }
+
+    // set new offset based on adding offset
+    pub fn trans_change(&mut self, offset: &Coord) {
+        self.trans_to(self.shift(*offset));
+    }
+
+    // the piece flipped left to right, each bone's column swapped with axis minus the column
+    pub fn flipped(&self, axis: i16) -> Self {
+        let mut piece = self.clone();
+        for bone in piece.bones.iter_mut() {
+            bone.coord.x = axis - bone.coord.x;
+        }
+        piece
+    }
+
+    fn get_coords(&self) -> Vec<Coord> {
+        self.bones.iter().map(|bone| bone.coord ).collect()
+    }
+
+    // the piece as a save writes it, its letter, orientation and cells
+    fn saved(&self) -> SavedPiece {
+        SavedPiece {
+            letter: self.kind.letter(),
+            orientation: self.orientation,
+            cells: self.get_coords(),
+            bomb: self.bones.iter().position(|bone| bone.special == Some(Special::Bomb) ),
+        }
+    }
+
+    pub fn bones(&self) -> &[Bone] {
+        &self.bones
+    }
+
+    pub fn kind(&self) -> PieceKind {
+        self.kind
+    }
+
+    // the occupied cells in a fixed order so pieces covering the same cells compare equal regardless of rotation
+    fn sorted_cells(&self) -> Vec<(i16, i16)> {
+        let mut cells: Vec<(i16, i16)> = self.bones.iter().map(|bone| (bone.coord.x, bone.coord.y) ).collect();
+        cells.sort();
+        cells
+    }
+
+    // from_layout instantiates a new tetrinome using the provided layout
+    fn from_layout(layout: String, color: Color, kind: PieceKind) -> Self {
+        let width = layout.find('\n').unwrap_or(layout.len()) as i16 + 1; // width in units not indices, the newline included
+    
+        let mut pivot = None;
+        
+        let mut bones = Vec::new();
+        for (i, c) in layout.chars().enumerate() {
+            if c == 'x' || c == 'o' {
+                if c == 'o' {
+                    pivot = Some(bones.len());
+                }
+                bones.push(Bone::new(color, Pos::from(i).pos_to_coord(width), kind));
+            }
+        }
+        if pivot.is_none() && bones.len() > 1 {
+            pivot = Self::center_bone(&bones); // layouts without an `o` rotate around their middle
+        }
+        
+        Tetrinome {
+            bones,
+            pivot,
+            kind,
+            orientation: Orientation::Spawn,
+        }
+    }
+
+    // the bone nearest the average of the bones' coords, the first of them on a tie
+    fn center_bone(bones: &[Bone]) -> Option<usize> {
+        let n = bones.len() as i32;
+        let sum_x: i32 = bones.iter().map(|bone| i32::from(bone.coord.x) ).sum();
+        let sum_y: i32 = bones.iter().map(|bone| i32::from(bone.coord.y) ).sum();
+        // distances are scaled by the bone count so the average stays a whole number
+        let distance = |bone: &Bone| (i32::from(bone.coord.x) * n - sum_x).pow(2) + (i32::from(bone.coord.y) * n - sum_y).pow(2);
+        (0..bones.len()).min_by_key(|i| distance(&bones[*i]) )
+    }
+
+    // the row of the piece's first bone, it changes by the rows the piece moves down
+    fn row(&self) -> i16 {
+        self.bones.first().map_or(0, |bone| bone.coord.y )
+    }
+
+    fn get_width(&self) -> i16 {
+        let xs = self.bones.iter().map(|bone| bone.coord.x );
+        xs.clone().max().unwrap() - xs.min().unwrap() + 1 // TODO: is the clone necessary? moved value xs where first clone
+    }
+
+    fn from_piece(kind: PieceKind) -> Self {
+        match kind {
+            PieceKind::I => Tetrinome::from_layout(
+                [
+                    "----",
+                    "xoxx",
+                    "----",
+                    "----",
+                ].join("\n"),
+                Color::Green,
+                kind,
+            ),
+            PieceKind::L => Tetrinome::from_layout(
+                [
+                    "--x-",
+                    "xox-",
+                    "----",
+                    "----",
+                ].join("\n"),
+                Color::Yellow,
+                kind,
+            ),
+            PieceKind::J => Tetrinome::from_layout(
+                [
+                    "x---",
+                    "xox-",
+                    "----",
+                    "----",
+                ].join("\n"),
+                Color::Red,
+                kind,
+            ),
+            PieceKind::T => Tetrinome::from_layout(
+                [
+                    "--x-",
+                    "-xox",
+                    "----",
+                    "----"
+                ].join("\n"),
+                Color::Blue,
+                kind,
+            ),
+            PieceKind::Z => Tetrinome::from_layout(
+                [
+                    "xx--",
+                    "-ox-",
+                    "----",
+                    "----",
+                ].join("\n"),
+                Color::Pink,
+                kind,
+            ),
+            PieceKind::S => Tetrinome::from_layout(
+                [
+                    "--xx",
+                    "-xo-",
+                    "----",
+                    "----",
+                ].join("\n"),
+                Color::White,
+                kind,
+            ),
+            PieceKind::O => {
+                let mut piece = Tetrinome::from_layout(
+                    [
+                        "-xx-",
+                        "-xx-",
+                        "----",
+                        "----",
+                    ].join("\n"),
+                    Color::Aqua,
+                    kind,
+                );
+                piece.pivot = None; // stays put, only its orientation turns for the kicks, see with_o_rotation
+                piece
+            },
+            PieceKind::F5 => Tetrinome::from_layout(["-xx--", "xo---", "-x---"].join("\n"), Color::Green, kind),
+            PieceKind::I5 => Tetrinome::from_layout(["-----", "xxoxx", "-----"].join("\n"), Color::Yellow, kind),
+            PieceKind::L5 => Tetrinome::from_layout(["---x-", "xxox-", "-----"].join("\n"), Color::Red, kind),
+            PieceKind::N5 => Tetrinome::from_layout(["--xx-", "xxo--", "-----"].join("\n"), Color::Blue, kind),
+            PieceKind::P5 => Tetrinome::from_layout(["xx---", "ox---", "x----"].join("\n"), Color::Pink, kind),
+            PieceKind::T5 => Tetrinome::from_layout(["xxx--", "-o---", "-x---"].join("\n"), Color::White, kind),
+            PieceKind::U5 => Tetrinome::from_layout(["x-x--", "xox--", "-----"].join("\n"), Color::Aqua, kind),
+            PieceKind::V5 => Tetrinome::from_layout(["x----", "x----", "oxx--"].join("\n"), Color::Green, kind),
+            PieceKind::W5 => Tetrinome::from_layout(["x----", "xo---", "-xx--"].join("\n"), Color::Yellow, kind),
+            PieceKind::X5 => Tetrinome::from_layout(["-x---", "xox--", "-x---"].join("\n"), Color::Red, kind),
+            PieceKind::Y5 => Tetrinome::from_layout(["--x--", "xxox-", "-----"].join("\n"), Color::Blue, kind),
+            PieceKind::Z5 => Tetrinome::from_layout(["xx---", "-o---", "-xx--"].join("\n"), Color::Pink, kind),
+            PieceKind::Garbage => Tetrinome::from_layout("o".to_string(), Color::Gray, kind),
+            // only read from the piece file, see piece_set, a single bone stands in for them here
+            _ => Tetrinome::from_layout("o".to_string(), Color::Gray, kind),
+        }
+    }
+
+    // the piece as the config deals it, an O piece turns around its center bone like other pieces without a pivot with --o-rotation
+    fn with_o_rotation(mut self, o_rotation: bool) -> Self {
+        if o_rotation && self.kind == PieceKind::O {
+            self.pivot = Self::center_bone(&self.bones);
+        }
+        self
+    }
+
+    fn rotate(&mut self, rot: &Rotation) {
+        if let Some(pivot_i) = self.pivot { // if the tetrinome has a pivot
+            let pivot = self.bones[pivot_i];
+            let pivot_vec = Vector2::new(pivot.coord.x, pivot.coord.y);
+            for bone in self.bones.iter_mut() {
+                if let Rotation::None = rot { 
+                } else { // rotation not nothing
+                    let rot_cw_matrix: Matrix2<i16>;
+                    if let Rotation::CW = rot {
+                        rot_cw_matrix = Matrix2::new(0, -1, 
+                                                    1, 0);
+                    } else {
+                        rot_cw_matrix = Matrix2::new(0, 1, 
+                                                    -1, 0);
+                    }
+
+                    let coord_vec = Vector2::new(bone.coord.x, bone.coord.y);
+                    let pivot_offset = coord_vec - pivot_vec; // relative position from pivot
+                    let new_pivot_offset = rot_cw_matrix * pivot_offset;
+                    let new_coord = pivot_vec + new_pivot_offset;
+
+                    bone.coord = Coord{x: new_coord[0], y: new_coord[1]};
+                }
+            }
+        }
+        self.orientation = self.orientation.rotate(rot);
+    }
+}
+
+// a random piece of the set in the spawn orientation
+struct Deal<'a> {
+    pieces: &'a [Tetrinome],
+    orientation: SpawnOrientation,
+}
+
+// returns a random tetrinome in the spawn orientation but not translated (width needed to translate)
+impl Distribution<Tetrinome> for Deal<'_> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tetrinome {
+        let i = rng.gen_range(0, self.pieces.len());
+        let mut new_piece = self.pieces[i].clone();
+        if let SpawnOrientation::Random = self.orientation {
+            new_piece.rotate(&rng.gen::<Rotation>());
+        }
+        new_piece
+    }
+}
+
+// returns a random standard tetrinome with a random 1 step rotation in either direction but not translated (width needed to translate)
+// so pieces can be sampled without a Game, ie. `rand::random::<Tetrinome>()`
+impl Distribution<Tetrinome> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tetrinome {
+        Deal{pieces: standard_pieces(), orientation: SpawnOrientation::Random}.sample(rng)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)] // a byte per bone, the grid holds many of them
+pub enum PieceKind {
+    L,
+    J,
+    I,
+    T,
+    Z,
+    S,
+    O,
+    // pentominoes, only spawned in the pentomino variant
+    F5,
+    I5,
+    L5,
+    N5,
+    P5,
+    T5,
+    U5,
+    V5,
+    W5,
+    X5,
+    Y5,
+    Z5,
+    // the custom variant's pieces in the order the piece file lists them
+    Custom1,
+    Custom2,
+    Custom3,
+    Custom4,
+    Custom5,
+    Custom6,
+    Custom7,
+    Custom8,
+    Garbage, // a single bone pushed up from the bottom of the board, never spawned
+}
+
+pub const CUSTOM_KINDS: [PieceKind; 8] = [
+    PieceKind::Custom1, PieceKind::Custom2, PieceKind::Custom3, PieceKind::Custom4,
+    PieceKind::Custom5, PieceKind::Custom6, PieceKind::Custom7, PieceKind::Custom8,
+];
+
+impl PieceKind {
+    // a shape drawn over each bone so the kinds can be told apart without relying on color
+    pub fn pattern(self) -> [&'static str; PATTERN_SIZE] {
+        match self {
+            PieceKind::I => ["---", "xxx", "---"],
+            PieceKind::L => ["x--", "x--", "xxx"],
+            PieceKind::J => ["--x", "--x", "xxx"],
+            PieceKind::T => ["xxx", "-x-", "-x-"],
+            PieceKind::Z => ["xx-", "-x-", "-xx"],
+            PieceKind::S => ["-xx", "-x-", "xx-"],
+            PieceKind::O => ["xxx", "x-x", "xxx"],
+            PieceKind::F5 => ["-xx", "xx-", "-x-"],
+            PieceKind::I5 => ["-x-", "-x-", "-x-"],
+            PieceKind::L5 => ["x--", "x--", "xxx"],
+            PieceKind::N5 => ["-x-", "xx-", "x--"],
+            PieceKind::P5 => ["xx-", "xx-", "x--"],
+            PieceKind::T5 => ["xxx", "-x-", "-x-"],
+            PieceKind::U5 => ["x-x", "x-x", "xxx"],
+            PieceKind::V5 => ["x-x", "x-x", "-x-"],
+            PieceKind::W5 => ["x--", "xx-", "-xx"],
+            PieceKind::X5 => ["-x-", "xxx", "-x-"],
+            PieceKind::Y5 => ["x-x", "-x-", "-x-"],
+            PieceKind::Z5 => ["xx-", "-x-", "-xx"],
+            PieceKind::Custom1 => ["x-x", "x-x", "x-x"],
+            PieceKind::Custom2 => ["xxx", "---", "xxx"],
+            PieceKind::Custom3 => ["x--", "-x-", "--x"],
+            PieceKind::Custom4 => ["--x", "-x-", "x--"],
+            PieceKind::Custom5 => ["xxx", "x--", "x--"],
+            PieceKind::Custom6 => ["-x-", "x-x", "-x-"],
+            PieceKind::Custom7 => ["x-x", "---", "x-x"],
+            PieceKind::Custom8 => ["xx-", "xx-", "---"],
+            PieceKind::Garbage => ["x-x", "-x-", "x-x"],
+        }
+    }
+
+    // the letter the piece is known by, pentominoes share letters with the tetrinomes but never spawn alongside them
+    // custom pieces are numbered in the order the piece file lists them
+    pub fn letter(self) -> char {
+        match self {
+            PieceKind::L | PieceKind::L5 => 'L',
+            PieceKind::J => 'J',
+            PieceKind::I | PieceKind::I5 => 'I',
+            PieceKind::T | PieceKind::T5 => 'T',
+            PieceKind::Z | PieceKind::Z5 => 'Z',
+            PieceKind::S => 'S',
+            PieceKind::O => 'O',
+            PieceKind::F5 => 'F',
+            PieceKind::N5 => 'N',
+            PieceKind::P5 => 'P',
+            PieceKind::U5 => 'U',
+            PieceKind::V5 => 'V',
+            PieceKind::W5 => 'W',
+            PieceKind::X5 => 'X',
+            PieceKind::Y5 => 'Y',
+            PieceKind::Custom1 => '1',
+            PieceKind::Custom2 => '2',
+            PieceKind::Custom3 => '3',
+            PieceKind::Custom4 => '4',
+            PieceKind::Custom5 => '5',
+            PieceKind::Custom6 => '6',
+            PieceKind::Custom7 => '7',
+            PieceKind::Custom8 => '8',
+            PieceKind::Garbage => 'G',
+        }
+    }
+
+    // the kinds that spawn in the built in variants, the custom variant's are read from its piece file, see Config::piece_kinds
+    pub fn of_variant(variant: Variant) -> Vec<Self> {
+        match variant {
+            Variant::Custom => Vec::new(),
+            Variant::Standard => vec![PieceKind::I, PieceKind::O, PieceKind::L, PieceKind::T, PieceKind::Z, PieceKind::S, PieceKind::J],
+            Variant::Pentomino => vec![
+                PieceKind::F5, PieceKind::I5, PieceKind::L5, PieceKind::N5, PieceKind::P5, PieceKind::T5,
+                PieceKind::U5, PieceKind::V5, PieceKind::W5, PieceKind::X5, PieceKind::Y5, PieceKind::Z5,
+            ],
+        }
+    }
+
+    // reads a sequence of the letters of the kinds, ie. "IJLOSTZ", ignoring case and whitespace
+    pub fn sequence(text: &str, kinds: &[Self]) -> Result<Vec<Self>, String> {
+        text.chars().filter(|c| !c.is_whitespace() ).map(|c| {
+            kinds.iter().copied().find(|kind| kind.letter() == c.to_ascii_uppercase() )
+                .ok_or_else(|| format!("expected the pieces {}, found '{}'", kinds.iter().map(|kind| kind.letter() ).collect::<String>(), c) )
+        }).collect()
+    }
+}
+
+pub const PATTERN_SIZE: usize = 3;
+
+// how a piece was locked, a hard drop flashes brighter and for longer
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Lock {
+    Soft, // by gravity or the lock delay
+    Hard, // by an instant drop
+}
+
+impl Lock {
+    fn flash_duration(self) -> f64 {
+        match self {
+            Lock::Soft => SOFT_LOCK_FLASH_DURATION,
+            Lock::Hard => HARD_LOCK_FLASH_DURATION,
+        }
+    }
+
+    // opacity of the white drawn over the block at the start of the flash
+    fn flash_strength(self) -> f32 {
+        match self {
+            Lock::Soft => 0.35,
+            Lock::Hard => 0.8,
+        }
+    }
+}
+
+// what a block's frame timer is animating
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Effect {
+    Clear, // the line clear wave, the block is removed once it's done
+    Flash(Lock), // the block was just locked, it fades from white to its color
+}
+
+// a cell of the stack
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub bone: Bone,
+    frame_timer: Option<FrameTimer>,
+    effect: Effect, // only meaningful while there is a frame timer
+    fade: Option<FrameTimer>, // fading out after locking in invisible mode, kept once done, apart from the clear and flash animations
+}
+
+impl From<Bone> for Block {
+    fn from(some_bone: Bone) -> Self {
+        Self {
+            bone: some_bone,
+            frame_timer: None,
+            effect: Effect::Clear,
+            fade: None,
+        }
+    }
+}
+
+impl Block {
+    // whether the block is part of a row being cleared, a flashing block isn't
+    pub fn clearing(&self) -> bool {
+        self.frame_timer.is_some() && self.effect == Effect::Clear
+    }
+
+    // opacity of the block, 0 once it has faded out in invisible mode, a clearing block is always shown
+    pub fn visibility(&self) -> f32 {
+        match &self.fade {
+            Some(fade) if !self.clearing() => 1.0 - fade.progress(),
+            _ => 1.0,
+        }
+    }
+
+    // opacity of the white drawn over the block, None when it isn't flashing
+    pub fn flash_alpha(&self) -> Option<f32> {
+        match (&self.frame_timer, self.effect) {
+            (Some(frame_timer), Effect::Flash(lock)) => Some(lock.flash_strength() * (1.0 - frame_timer.progress())),
+            _ => None,
+        }
+    }
+}
+
+// the board's size in cells, from the config the grid was created with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Size {
+    pub width: i16,
+    pub height: i16, // the visible rows
+    pub hidden_rows: i16, // rows above the visible rows that pieces spawn into and the stack can grow into without being drawn
+}
+
+impl Size {
+    // halved in big mode where each cell is drawn 2x2 so collisions use the big footprint
+    pub fn new(config: &Config) -> Self {
+        let cell_scale = if config.big { 2 } else { 1 };
+        Self {
+            width: Grid::WIDTH / cell_scale,
+            height: Grid::HEIGHT / cell_scale,
+            hidden_rows: config.hidden_rows,
+        }
+    }
+
+    // the top row of the board, the hidden rows have negative rows so the visible rows start at 0
+    pub fn top(&self) -> i16 {
+        -self.hidden_rows
+    }
+
+    // the cells of the hidden rows and the visible rows
+    fn len(&self) -> usize {
+        (self.width * (self.hidden_rows + self.height)) as usize
+    }
+}
+
+// the stack, a cell per board coord with the rows waiting on their clear animation
+#[derive(Clone)]
+pub struct Blocks {
+    size: Size,
+    data: Vec<Option<Block>>,
+    rows_full: Vec<i16>,
+    gravity: LineGravity, // how the blocks above fall once rows are cleared
+    clear_wave: ClearWave, // the order the blocks of a full row start clearing in
+    clear_duration: f64, // milliseconds a full row animates before it clears, 0 clears it without animating
+    clear_scaling: f64, // share of the clear duration added for each row cleared at once past the first
+    garbage_gap: Option<i16>, // the column of the last garbage row's gap, None until garbage has been added
+}
+
+impl Blocks {
+    // the share of the clear animation the wave is spread over, the last block to start still cycles for the rest
+    const CLEAR_WAVE_SPREAD: f64 = 1.0 / 3.0;
+    const REVEAL_ROWS: i16 = 2; // rows above and below a clearing row that are shown again in invisible mode
+
+    fn new(size: Size, gravity: LineGravity, clear_wave: ClearWave, clear_duration: f64, clear_scaling: f64) -> Self {
+        Self {
+            size,
+            data: vec![None; size.len()],
+            rows_full: Vec::default(),
+            gravity,
+            clear_wave,
+            clear_duration,
+            clear_scaling,
+            garbage_gap: None,
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    // returns the range of indices in data covered by the row or None if the row is off the grid
+    fn row_range(&self, row: &i16) -> Option<Range<usize>> {
+        if *row < self.size.top() || *row >= self.size.height {
+            return None
+        }
+        let start = ((row - self.size.top()) * self.size.width) as usize;
+        Some(start..start + self.size.width as usize)
+    }
+
+    // returns the index in data of the coord or None if the coord is off the grid, the hidden rows come first
+    fn index(&self, coord: &Coord) -> Option<usize> {
+        if coord.x < 0 || coord.x >= self.size.width || coord.y < self.size.top() || coord.y >= self.size.height {
+            return None
+        }
+        Some(((coord.y - self.size.top()) * self.size.width + coord.x) as usize)
+    }
+
+    // the coord of the index in data, the hidden rows have negative rows
+    pub fn coord(&self, i: usize) -> Coord {
+        Coord{x: i as i16 % self.size.width, y: i as i16 / self.size.width + self.size.top()}
+    }
+
+    // the cells of the board row by row from the top of the hidden rows
+    pub fn data(&self) -> &[Option<Block>] {
+        &self.data
+    }
+
+    // the block at the coord, None when the cell is empty or off the grid
+    pub fn block(&self, coord: &Coord) -> Option<&Block> {
+        self.index(coord).and_then(|i| self.data[i].as_ref() )
+    }
+
+    // the number of blocks on the board from each piece kind, indexed by kind
+    pub fn kind_counts(&self) -> [u32; NUM_PIECES] {
+        let mut counts = [0; NUM_PIECES];
+        for block in self.data.iter().flatten() {
+            counts[block.bone.kind as usize] += 1;
+        }
+        counts
+    }
+
+    // rows from the floor to the top block of the column, 0 when the column is empty
+    pub fn column_height(&self, x: i16) -> i16 {
+        (self.size.top()..self.size.height).find(|y| self.filled(&Coord{x, y: *y}) ).map_or(0, |y| self.size.height - y)
+    }
+
+    // rows from the floor to the top of the tallest column
+    pub fn stack_height(&self) -> i16 {
+        (0..self.size.width).map(|x| self.column_height(x) ).max().unwrap_or(0)
+    }
+
+    // moves the stack up by the number of rows and fills the rows from the bottom with garbage, each with a gap
+    // a row's gap moves to a random column by the chance of mess, otherwise it's under the last row's so they line up
+    // returns true if blocks were pushed over the top of the hidden rows
+    fn push_garbage<R: Rng>(&mut self, n_rows: i16, mess: f64, rng: &mut R) -> bool {
+        let n_rows = n_rows.max(0).min(self.size.height);
+        let mut overflowed = false;
+        for row in self.size.top()..self.size.height { // top down so each block moves into a spot already vacated
+            for x in 0..self.size.width {
+                if let Some(i) = self.index(&Coord{x, y: row}) {
+                    if let Some(mut block) = self.data[i].take() {
+                        block.bone.coord.y -= n_rows;
+                        match self.index(&block.bone.coord) {
+                            Some(j) => self.data[j] = Some(block),
+                            None => overflowed = true, // blocks pushed over the top are lost
+                        }
+                    }
+                }
+            }
+        }
+        for full_row in self.rows_full.iter_mut() {
+            *full_row -= n_rows;
+        }
+        let top = self.size.top();
+        self.rows_full.retain(|row| *row >= top );
+
+        for y in self.size.height - n_rows..self.size.height {
+            let moves = match self.garbage_gap {
+                None => true,
+                Some(_) if mess >= 1.0 => true, // no chance is drawn so the rng is used as it was before mess could be set
+                Some(_) => mess > 0.0 && rng.gen::<f64>() < mess,
+            };
+            if moves {
+                self.garbage_gap = Some(rng.gen_range(0, self.size.width));
+            }
+            let gap = self.garbage_gap.unwrap_or(0);
+            for x in (0..self.size.width).filter(|x| *x != gap ) {
+                let coord = Coord{x, y};
+                if let Some(i) = self.index(&coord) {
+                    self.data[i] = Some(Bone::new(Color::Gray, coord, PieceKind::Garbage).into());
+                }
+            }
+        }
+        overflowed
+    }
+
+    // the board as text a row per line from the top of the hidden rows, a `-` per empty cell and the color's letter per block
+    // laid out like the piece layouts, for pasting a board into a bug report
+    pub fn to_ascii(&self) -> String {
+        self.data.chunks(self.size.width as usize)
+            .map(|row| row.iter().map(|cell| cell.as_ref().map_or('-', |block| block.bone.color.letter() ) ).collect::<String>() )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // the board written by to_ascii for a board of the size, the blocks are garbage in their colors and the line clear settings are the defaults
+    pub fn from_ascii(text: &str, size: Size) -> Result<Self, Error> {
+        let rows: Vec<&str> = text.lines().map(str::trim).filter(|row| !row.is_empty() ).collect();
+        let n_rows = (size.hidden_rows + size.height) as usize;
+        if rows.len() != n_rows {
+            return Err(Error::InvalidLayout(format!("expected {} rows, found {}", n_rows, rows.len())))
+        }
+        let mut blocks = Blocks::new(size, LineGravity::Naive, ClearWave::LeftToRight, CLEAR_DURATION, 0.0);
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != size.width as usize {
+                return Err(Error::InvalidLayout(format!("expected {} cells in row {}, found '{}'", size.width, y + 1, row)))
+            }
+            for (x, letter) in row.chars().enumerate() {
+                if letter == '-' {
+                    continue
+                }
+                let color = Color::from_letter(letter).ok_or_else(|| Error::InvalidLayout(format!("unknown color '{}' in row {}", letter, y + 1)) )?;
+                let i = y * size.width as usize + x;
+                blocks.data[i] = Some(Bone::new(color, blocks.coord(i), PieceKind::Garbage).into());
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.iter().all(Option::is_none)
+    }
+
+    // whether the coord is taken by a block or is a wall or the floor, above the hidden rows is open
+    fn filled(&self, coord: &Coord) -> bool {
+        if coord.x < 0 || coord.x >= self.size.width || coord.y >= self.size.height {
+            return true
+        }
+        match self.index(coord) {
+            Some(i) => self.data[i].is_some(),
+            None => false,
+        }
+    }
+
+    fn set_block(&mut self, coord: &Coord, bone: Bone) -> std::result::Result<(), OutOfBounds> {
+        let i = self.checked_index(coord)?;
+        self.data[i] = Some(bone.into());
+        Ok(())
+    }
+
+    fn get_block(&self, coord: &Coord) -> std::result::Result<Option<Block>, OutOfBounds> {
+        let i = self.checked_index(coord)?;
+        Ok(self.data[i].clone())
+    }
+
+    // a coord off the grid is an error rather than an index panic
+    fn checked_index(&self, coord: &Coord) -> std::result::Result<usize, OutOfBounds> {
+        self.index(coord).ok_or(OutOfBounds(*coord))
+    }
+
+    // clears the entire grid
+    fn clear(&mut self) {
+        self.data = vec![None; self.size.len()];
+        self.garbage_gap = None;
+    }
+
+    // returns whether the row is full
+    fn row_full(&self, row: &i16) -> bool {
+        if let Some(range) = self.row_range(row) {
+            for some_block in self.data[range].iter() {
+                if some_block.is_none() {
+                    return false
+                }
+            }
+            return true
+        }
+        false
+    }
+
+    // the columns of the bombs in the rows
+    fn bomb_columns(&self, rows: &[i16]) -> Vec<i16> {
+        let mut columns = Vec::new();
+        for row in rows.iter() {
+            for x in 0..self.size.width {
+                let bomb = self.index(&Coord{x, y: *row}).and_then(|i| self.data[i].as_ref() ).is_some_and(|block| block.bone.special == Some(Special::Bomb) );
+                if bomb && !columns.contains(&x) {
+                    columns.push(x);
+                }
+            }
+        }
+        columns
+    }
+
+    // removes the blocks in the column, the rows still animating a clear are left full so they clear as well
+    fn clear_column(&mut self, x: i16) {
+        for y in self.size.top()..self.size.height {
+            if self.rows_full.contains(&y) {
+                continue
+            }
+            if let Some(i) = self.index(&Coord{x, y}) {
+                self.data[i] = None;
+            }
+        }
+    }
+
+    // replaces each block in the row with None
+    fn clear_row(&mut self, row: &i16) {
+        if let Some(range) = self.row_range(row) {
+            for some_block in self.data[range].iter_mut() {
+                *some_block = None;
+            }
+        }
+    }
+
+    fn add_row_to_clear(&mut self, row: &i16) {
+        self.rows_full.push(*row);
+    }
+
+    // returns whether the row is ready to be cleared if all the animations in the row are done
+    fn row_ready(&mut self, row: &i16) -> bool {
+        let range = match self.row_range(row) {
+            Some(range) => range,
+            None => return true, // nothing off the grid to wait on
+        };
+
+        self.data[range].iter_mut().filter_map(|some_block| {
+            if let Some(block) = some_block {
+                if let (Some(frame_timer), Effect::Clear) = (&mut block.frame_timer, block.effect) {
+                    return Some(frame_timer.get_state())
+                }
+            }
+            None
+        })
+        .all(|frame_state| matches!(frame_state, FrameState::Done))
+    }
+
+    // initializes the FrameTimer which begins the clearing countdown, longer the more rows are cleared at once with clear scaling
+    // each row waits on its own blocks' timers in row_ready, so rows of different clears finishing at different times is fine
+    fn start_clear(&mut self, row: &i16, n_rows: usize) {
+        let range = match self.row_range(row) {
+            Some(range) => range,
+            None => return,
+        };
+        self.reveal(*row);
+
+        if self.clear_duration <= 0.0 {
+            self.add_row_to_clear(row); // nothing to wait on so row_ready is true at once
+            return
+        }
+
+        let duration = self.clear_duration * (1.0 + self.clear_scaling * n_rows.saturating_sub(1) as f64);
+        let n_frames = (duration / (millis_per_update() * 3.0)).round().max(1.0) as usize; // the frames after the first
+        let frame_duration = duration / n_frames as f64; // stretched so the clear lasts as long at any update rate
+        let spread = (n_frames as f64 * Self::CLEAR_WAVE_SPREAD).floor();
+        for block in self.data[range].iter_mut().flatten() {
+            if !block.clearing() { // replaces a lock flash, ie. on the piece that filled the row
+                // whole frames of delay taken off the cycling so every block finishes with the row
+                let delay_frames = (self.clear_wave.offset(block.bone.coord.x, *row, self.size.width) * spread).round() as usize;
+                block.bone.color = Color::get_color(0);
+                block.frame_timer = Some(FrameTimer::equal_sized(n_frames + 1 - delay_frames, frame_duration, delay_frames as f64 * frame_duration)); // wave effect
+                block.effect = Effect::Clear;
+            }
+        }
+
+        self.add_row_to_clear(row);
+    }
+    
+    // starts the flash of a block that was just locked
+    fn start_flash(&mut self, coord: &Coord, lock: Lock) {
+        if let Ok(i) = self.checked_index(coord) {
+            if let Some(block) = &mut self.data[i] {
+                block.frame_timer = Some(FrameTimer::lasting(lock.flash_duration(), millis_per_update(), 0.0));
+                block.effect = Effect::Flash(lock);
+            }
+        }
+    }
+
+    // starts the block fading out, for invisible mode
+    fn start_fade(&mut self, coord: &Coord) {
+        if let Ok(i) = self.checked_index(coord) {
+            if let Some(block) = &mut self.data[i] {
+                block.fade = Some(FrameTimer::lasting(INVISIBLE_FADE_DURATION, millis_per_update(), INVISIBLE_DELAY));
+            }
+        }
+    }
+
+    // shows the faded blocks near a row that started clearing again for a moment, only invisible mode's blocks fade
+    fn reveal(&mut self, row: i16) {
+        for y in (row - Self::REVEAL_ROWS)..=(row + Self::REVEAL_ROWS) {
+            for x in 0..self.size.width {
+                if self.get_block(&Coord{x, y}).ok().flatten().is_some_and(|block| block.fade.is_some() ) {
+                    self.start_fade(&Coord{x, y});
+                }
+            }
+        }
+    }
+
+    // steps the clear animation of the blocks in full rows and the flash of the blocks just locked
+    fn animate(&mut self, elapsed: f64) {
+        for block in self.data.iter_mut().flatten() {
+            if let Some(fade) = &mut block.fade {
+                fade.state(elapsed);
+            }
+            if let Some(frame_timer) = &mut block.frame_timer {  // if animatable
+                let state = frame_timer.state(elapsed);
+                match block.effect {
+                    Effect::Clear => block.bone.clear_animate(&state),
+                    Effect::Flash(_) => if let FrameState::Done = state {
+                        block.frame_timer = None; // settled to the piece color
+                    },
+                }
+            }
+        }
+    }
+
+    // clears the rows whose animation is done and drops the blocks above, returns the rows newly full from a cascade
+    fn finish_clear(&mut self) -> Vec<i16> {
+        let ready_rows: Vec<i16> = self.rows_full.clone().into_iter().filter(|row| self.row_ready(row) ).collect();
+        let bomb_columns = self.bomb_columns(&ready_rows);
+        // clear the ready rows
+        for ready_row in ready_rows.iter() {
+            self.clear_row(ready_row);
+        }
+        for x in bomb_columns {
+            self.clear_column(x);
+        }
+        if ready_rows.is_empty() {
+            return Vec::new()
+        }
+        if let LineGravity::Naive = self.gravity {
+            self.drop_rows(&ready_rows);
+            return Vec::new()
+        }
+
+        // the rows still animating stay in place and hold up the blocks resting on them
+        self.rows_full.retain(|row| !ready_rows.contains(row) );
+        self.settle();
+        let mut cascaded = Vec::new();
+        if let LineGravity::Cascade = self.gravity {
+            // the settled blocks can fill new rows, these animate and clear again until the board is stable
+            cascaded = (self.size.top()..self.size.height).filter(|row| self.row_full(row) && !self.rows_full.contains(row) ).collect();
+            for row in cascaded.iter() {
+                self.start_clear(row, cascaded.len());
+            }
+        }
+        cascaded
+    }
+
+    // naive gravity, every row above a cleared row drops down by one
+    // the top row goes first so dropping the rows above a cleared row doesn't move the ready rows below it
+    fn drop_rows(&mut self, ready_rows: &[i16]) {
+        let mut ready_rows = ready_rows.to_vec();
+        ready_rows.sort();
+        for ready_row in ready_rows.iter() {
+            for upper_row in (self.size.top()..*ready_row).rev() {
+                if self.drop_row_down(&upper_row) == 0 {
+                    break; // preliminary break if empty row found
+                }
+            }
+            // removed by value since rows can become ready out of the order they started clearing in
+            if let Some(i) = self.rows_full.iter().position(|row| row == ready_row) {
+                self.rows_full.remove(i);
+            }
+        }
+    }
+
+    // the indices of each group of orthogonally connected blocks, blocks that are animating a clear are left out
+    fn groups(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.data.len()];
+        let mut groups = Vec::new();
+        for start in 0..self.data.len() {
+            if visited[start] || !self.settleable(start) {
+                continue
+            }
+            visited[start] = true;
+            let mut group = Vec::new();
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                group.push(i);
+                let coord = self.coord(i);
+                for offset in [Coord{x: -1, y: 0}, Coord{x: 1, y: 0}, Coord{x: 0, y: -1}, Coord{x: 0, y: 1}].iter() {
+                    if let Some(neighbor) = self.index(&(coord + *offset)) {
+                        if !visited[neighbor] && self.settleable(neighbor) {
+                            visited[neighbor] = true;
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+            groups.push(group);
+        }
+        groups
+    }
+
+    // whether there is a block at the index that can fall when settling
+    fn settleable(&self, i: usize) -> bool {
+        match &self.data[i] {
+            Some(block) => !block.clearing(),
+            None => false,
+        }
+    }
+
+    // the number of rows the group can fall before landing on the floor or another block
+    fn fall_distance(&self, group: &[usize]) -> i16 {
+        let coords: Vec<Coord> = group.iter().map(|i| self.coord(*i) ).collect();
+        let mut distance = 0;
+        loop {
+            let blocked = coords.iter().any(|coord| {
+                let below = Coord{x: coord.x, y: coord.y + distance + 1};
+                match self.index(&below) {
+                    Some(i) => self.data[i].is_some() && !group.contains(&i),
+                    None => true, // the floor
+                }
+            });
+            if blocked {
+                break distance
+            }
+            distance += 1;
+        }
+    }
+
+    // sticky gravity, connected groups of blocks fall together until none can fall any further
+    fn settle(&mut self) {
+        loop {
+            let mut moved = false;
+            for group in self.groups() {
+                let distance = self.fall_distance(&group);
+                if distance == 0 {
+                    continue
+                }
+                let blocks: Vec<Block> = group.iter().filter_map(|i| self.data[*i].take() ).collect();
+                for mut block in blocks {
+                    block.bone.coord.y += distance;
+                    if let Some(i) = self.index(&block.bone.coord) {
+                        self.data[i] = Some(block);
+                    }
+                }
+                moved = true;
+            }
+            if !moved {
+                break
+            }
+        }
+    }
+
+    // returns the rows the piece inhabits
+    fn get_piece_rows(&self, piece: &Tetrinome) -> Vec<i16> {
+        let mut ys: Vec<i16> = piece.bones.iter().map(|bone| bone.coord.y).collect();
+        ys.sort();
+        ys.dedup();
+        ys.into_iter().collect()
+    }
+
+    // drops the given row down
+    fn drop_row_down(&mut self, row: &i16) -> i16 {
+        let (range, range_below) = match (self.row_range(row), self.row_range(&(row + 1))) {
+            (Some(range), Some(range_below)) => (range, range_below),
+            _ => return 0, // the bottom row has nowhere to drop to
+        };
+        let mut count = 0;
+        for (i, i_below) in range.zip(range_below) {
+            if let Some(mut block) = self.data[i].take() { // old spot is left empty
+                block.bone.coord.y += 1; // coord for drawing
+                self.data[i_below] = Some(block); // new spot
+                count+=1;
+            }
+        }
+        // dropping down the rows affects the rows about to be cleared as well so add to each full row above the cleared row
+        for full_row in self.rows_full.iter_mut() {
+            if row >= full_row {
+                *full_row+=1;
+            }
+        }
+        count
+    }
+
+    fn check_collision(&self, piece: &Tetrinome, dir: &Direction, rot: &Rotation) -> Collision {
+        for coord in piece.get_coords() {
+            // out of bounds
+            if coord.x < 0 {
+                return Collision::Left
+            } else if coord.x >= self.size.width {
+                return Collision::Right
+            }
+            if coord.y >= self.size.height {
+                return Collision::Under
+            } else if let Ok(None) | Err(_) = self.get_block(&coord) {
+                // empty block or above the hidden rows
+            } else {
+                let rot_dir: Direction = (*rot).into();
+                return match dir {
+                    Direction::Down => Collision::Under,
+                    Direction::Left => Collision::Left,
+                    Direction::Right => Collision::Right,
+                    Direction::None => match rot_dir {
+                        Direction::Left => Collision::Left,
+                        Direction::Right => Collision::Right,
+                        _ => Collision::None,
+                    }
+                }
+            }
+        }
+
+        Collision::None
+    }
+}
+
+// a copy of the dropped piece falling a row at a time after it has already been committed
+#[derive(Clone)]
+pub struct InstantDrop {
+    piece: Tetrinome,
+    frame_timer: FrameTimer,
+    trail: Vec<Tetrinome>, // the positions the piece has dropped through, at most the drop distance
+}
+
+impl InstantDrop {
+    pub const TRAIL_LENGTH: usize = 4; // number of the latest positions drawn, fading with age
+
+    pub fn piece(&self) -> &Tetrinome {
+        &self.piece
+    }
+
+    // the latest positions the piece has dropped through, oldest first
+    pub fn trail(&self) -> &[Tetrinome] {
+        &self.trail[self.trail.len().saturating_sub(Self::TRAIL_LENGTH)..]
+    }
+}
+
+// the piece swapped out by a hold, drawn moving from where it was on the board into the hold box
+// only drawn, the swap has already happened so gameplay never waits on it
+#[derive(Clone)]
+pub struct HoldSwap {
+    piece: Tetrinome,
+    frame_timer: FrameTimer,
+}
+
+impl HoldSwap {
+    fn new(piece: Tetrinome) -> Self {
+        Self {
+            piece,
+            frame_timer: FrameTimer::lasting(HOLD_SWAP_DURATION, millis_per_update(), 0.0),
+        }
+    }
+
+    // the piece where it was on the board when it was held
+    pub fn piece(&self) -> &Tetrinome {
+        &self.piece
+    }
+
+    // the fraction of the way to the hold box
+    pub fn progress(&self) -> f32 {
+        self.frame_timer.progress()
+    }
+}
+
+// text over the board that floats up as it fades out
+#[derive(Clone)]
+pub struct Popup {
+    lines: Vec<String>,
+    row: i16, // the board row the popup starts at
+    frame_timer: FrameTimer,
+}
+
+impl Popup {
+    fn new(lines: Vec<String>, row: i16) -> Self {
+        Self {
+            lines,
+            row,
+            frame_timer: FrameTimer::lasting(POPUP_DURATION, millis_per_update(), 0.0), // a frame per update
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn row(&self) -> i16 {
+        self.row
+    }
+
+    // the fraction of the popup's duration that has passed, it rises and fades out over the duration
+    pub fn progress(&self) -> f32 {
+        self.frame_timer.progress()
+    }
+}
+
+// the state of the grid before a piece is committed, restored when the placement is undone
+#[derive(Clone)]
+struct Snapshot {
+    blocks: Blocks,
+    curr_piece: Tetrinome,
+    spawned_piece: Tetrinome,
+    next_pieces: VecDeque<Tetrinome>,
+    sequence: Option<VecDeque<PieceKind>>,
+    held_pieces: VecDeque<Tetrinome>,
+    can_hold: bool,
+    stats: [u32; NUM_PIECES],
+    droughts: [u32; NUM_PIECES],
+    score: Score,
+    finesse_faults: u32,
+}
+
+// the board, the current, next and held pieces and everything scored from placing them
+#[derive(Clone)]
+pub struct Grid {
+    blocks: Blocks,
+    curr_piece: Tetrinome,
+    instant_drop: Option<InstantDrop>,
+    stats: [u32; NUM_PIECES], // number of pieces spawned of each kind, indexed by PieceKind
+    droughts: [u32; NUM_PIECES], // pieces spawned since the last of each kind, indexed by PieceKind
+    spawned: u32, // pieces spawned this game, not taken back by undo unlike the stats
+    rotation_system: RotationSystem,
+    pieces: Vec<Tetrinome>, // the pieces of the variant being played, each new piece is a copy of one
+    spawn_orientation: SpawnOrientation,
+    spawn_column: SpawnColumn,
+    mode: Mode,
+    history: VecDeque<Snapshot>, // most recent placement at the back, only recorded in practice mode
+    spawned_piece: Tetrinome, // the current piece as it was spawned, the start of the finesse search
+    inputs: u32, // moves and rotations made by the player on the current piece
+    finesse_faults: u32, // pieces placed using more inputs than necessary
+    last_move_rotated: bool, // whether the last successful move of the current piece was a rotation, for t-spins
+    score: Score,
+    start_level: u32, // the level the score starts at
+    popups: Vec<Popup>,
+    pub patterns: bool, // draw each bone's piece pattern for colorblind players
+    next_pieces: VecDeque<Tetrinome>, // the front becomes the current piece on the next spawn, at least one even with no preview
+    preview_count: usize, // next pieces shown in the sidebar
+    blind: bool, // hides the next piece
+    invisible: bool, // locked blocks fade out, collisions still use them
+    lock_resets: u32, // moves and rotations made by the current piece while resting, each restarts the lock delay up to the cap
+    reset_lock: bool, // set by a resting move, taken by the game's update to restart the lock delay
+    fall_reset: bool, // whether left and right moves restart the fall interval
+    reset_fall: bool, // set by a left or right move with fall_reset, taken by the game's update to restart the fall interval
+    shadow: Option<Tetrinome>, // where the current piece would land, None when the piece or the board has changed since it was found
+    pub ghost: Ghost,
+    ghost_alpha: f32, // opacity of the shadow piece and the instant drop trail
+    ghost_height: i16, // the shadow piece is hidden once the stack is this many rows tall, 0 always shows it
+    pub show_coords: bool, // debug overlay of each block's pos and coord
+    rng: Pcg32, // seeded from the config so the pieces and the garbage are reproducible, saved with the game
+    garbage_rows: i16, // rows of garbage added when the board starts
+    garbage_mess: f64, // chance each garbage row's gap moves to a new column
+    hard_dropping: bool, // set while an instant drop moves the piece down so its lock flashes harder
+    pub mono: bool, // bones are shaded by their row rather than drawn in their color
+    events: Vec<HookEvent>, // what happened since the game last fired its hooks
+    pub mirrored: bool, // the board is drawn flipped left to right with left and right swapped on the controls, the simulation is left as is
+    palette: Palette, // colors picked in game for piece kinds
+    held_pieces: VecDeque<Tetrinome>, // as they were spawned, so they come back at the top in their spawn orientation, the front comes back first
+    hold_depth: usize, // pieces held before holding brings one back
+    can_hold: bool, // cleared by a hold until the next piece spawns
+    hold_swap: Option<HoldSwap>, // the piece last held moving into the hold box
+    hold_enabled: bool, // whether the hold key does anything
+    hold_animation: bool, // whether holding starts a HoldSwap
+    juice: bool, // whether locks flash and shake the board
+    hard_drop_lock: bool, // pieces only lock when instant dropped, a landed piece rests until then
+    shake: Option<FrameTimer>, // the board shaking after a lock near the top, only drawn
+    pub show_advice: bool, // outline the best spot for the current piece
+    advice: Option<Tetrinome>, // the best spot for the current piece, None until it's been found for this piece
+    items: bool, // spawned pieces sometimes carry a bomb
+    sent_garbage: i16, // rows of garbage earned by clears for the opponent in versus, taken by the frontend
+    top_out_rules: TopOutRules, // which of block out and lock out end the game
+    top_out: Option<TopOut>, // what pushed the stack over the top, ending the game
+    sequence: Option<VecDeque<PieceKind>>, // the kinds left to deal in a scripted game, None deals from the rng
+    sequence_ended: bool, // a scripted game ran out of pieces to spawn, ending the game
+}
+
+impl Grid {
+    pub const WIDTH: i16 = 10;
+    pub const HEIGHT: i16 = 20; // the visible rows, the hidden rows are above them
+    const UNDO_DEPTH: usize = 32; // number of placements that can be undone
+    const SPAWN_ROWS: i16 = 4; // rows at the top kept clear of starting garbage
+    const DANGER_ROWS: i16 = 4; // a lock with the stack this close to the top shakes the board
+    const SHAKE_MAGNITUDE: f32 = 0.2; // cells the board is shaken by at the start of a shake
+    const BOMB_CHANCE: u32 = 12; // in item mode 1 in this many pieces carries a bomb
+
+    // the board size in cells
+    pub fn width(&self) -> i16 {
+        self.blocks.size.width
+    }
+
+    pub fn height(&self) -> i16 {
+        self.blocks.size.height
+    }
+
+    // rows above the visible rows that pieces spawn into and the stack can grow into without being drawn
+    pub fn hidden_rows(&self) -> i16 {
+        self.blocks.size.hidden_rows
+    }
+
+    // the top row of the board, the hidden rows have negative rows so the visible rows start at 0
+    pub fn top(&self) -> i16 {
+        self.blocks.size.top()
+    }
+
+    pub fn new(config: &Config) -> Self {
+        let size = Size::new(config);
+        let placeholder = Tetrinome::from_piece(PieceKind::O); // replaced by the first piece dealt below
+        let mut grid = Self {
+            blocks: Blocks::new(size, config.line_gravity, config.clear_wave, config.clear_duration, config.clear_scaling), // init to None (like null ptr)
+            spawned_piece: placeholder.clone(),
+            curr_piece: placeholder,
+            next_pieces: VecDeque::new(),
+            preview_count: config.preview_count,
+            instant_drop: None,
+            stats: [0; NUM_PIECES],
+            droughts: [0; NUM_PIECES],
+            spawned: 0,
+            rotation_system: config.rotation_system,
+            pieces: piece_set(config),
+            spawn_orientation: config.spawn_orientation,
+            spawn_column: config.spawn_column,
+            mode: config.mode,
+            history: VecDeque::with_capacity(Self::UNDO_DEPTH),
+            inputs: 0,
+            finesse_faults: 0,
+            last_move_rotated: false,
+            score: Score::new(config.blind, config.start_level, config.scoring),
+            start_level: config.start_level,
+            popups: Vec::new(),
+            patterns: config.patterns,
+            blind: config.blind,
+            invisible: config.invisible,
+            lock_resets: 0,
+            reset_lock: false,
+            fall_reset: config.fall_reset,
+            reset_fall: false,
+            shadow: None,
+            ghost: config.ghost,
+            ghost_alpha: config.ghost_alpha,
+            ghost_height: config.ghost_height,
+            show_coords: false,
+            rng: Pcg32::seed_from_u64(config.seed),
+            garbage_rows: config.garbage_rows.min(size.height - Self::SPAWN_ROWS), // leaves room to spawn
+            garbage_mess: config.garbage_mess,
+            hard_dropping: false,
+            mono: config.mono,
+            mirrored: config.mirror,
+            events: Vec::new(),
+            palette: Palette::default(),
+            held_pieces: VecDeque::with_capacity(config.hold_depth + 1),
+            hold_depth: config.hold_depth.max(1),
+            can_hold: true,
+            hold_swap: None,
+            hold_enabled: config.hold,
+            hold_animation: config.hold_animation,
+            juice: config.juice,
+            hard_drop_lock: config.hard_drop_lock,
+            shake: None,
+            show_advice: config.advice,
+            advice: None,
+            items: config.items,
+            sent_garbage: 0,
+            top_out_rules: config.top_out,
+            top_out: None,
+            sequence: if config.sequence.is_empty() { None } else { Some(config.sequence.iter().copied().collect()) },
+            sequence_ended: false,
+        };
+        grid.curr_piece = grid.deal().unwrap(); // a scripted sequence is never empty
+        for _ in 0..config.preview_count.max(1) {
+            let next_piece = grid.deal();
+            grid.next_pieces.extend(next_piece);
+        }
+        grid.add_start_garbage();
+        grid.start_piece();
+        grid
+    }
+
+    pub fn blocks(&self) -> &Blocks {
+        &self.blocks
+    }
+
+    pub fn curr_piece(&self) -> &Tetrinome {
+        &self.curr_piece
+    }
+
+    // the upcoming pieces shown in the preview, the next to spawn first
+    pub fn next_pieces(&self) -> impl Iterator<Item = &Tetrinome> {
+        self.next_pieces.iter().take(self.preview_count)
+    }
+
+    // the held pieces from the one the hold key brings back next
+    pub fn held_pieces(&self) -> impl Iterator<Item = &Tetrinome> {
+        self.held_pieces.iter()
+    }
+
+    pub fn hold_depth(&self) -> usize {
+        self.hold_depth
+    }
+
+    pub fn hold_swap(&self) -> Option<&HoldSwap> {
+        self.hold_swap.as_ref()
+    }
+
+    // the pieces the grid deals from, in the order of the variant's kinds
+    pub fn pieces(&self) -> &[Tetrinome] {
+        &self.pieces
+    }
+
+    // a hash of the board's occupancy and the current, held and next pieces, the same on every run so two games can be compared
+    // update by update to find where a replay or a versus opponent diverged, the animations aren't hashed
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        let mut add = |bytes: &[u8]| for byte in bytes.iter() {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME);
+        };
+        for block in self.blocks.data.iter() {
+            add(&[block.is_some() as u8]);
+        }
+        for bone in self.curr_piece.bones.iter() {
+            add(&bone.coord.x.to_le_bytes());
+            add(&bone.coord.y.to_le_bytes());
+        }
+        add(&[self.curr_piece.kind as u8]);
+        if self.held_pieces.is_empty() {
+            add(&[u8::MAX]);
+        }
+        for piece in self.held_pieces.iter() {
+            add(&[piece.kind as u8]);
+        }
+        for piece in self.next_pieces.iter() {
+            add(&[piece.kind as u8]);
+        }
+        hash
+    }
+
+    // the best spot for the current piece by the advice heuristics, found once per piece and after the board changes
+    pub fn advice(&mut self) -> Option<&Tetrinome> {
+        if !self.show_advice {
+            return None
+        }
+        if self.advice.is_none() {
+            self.advice = advice::best_placement(self, &self.spawned_piece);
+        }
+        self.advice.as_ref()
+    }
+
+    // cells the board is drawn offset by while shaking, alternating sides each frame as it decays and exactly 0 once it's over
+    pub fn shake_offset(&self) -> (f32, f32) {
+        match &self.shake {
+            Some(frame_timer) => {
+                let magnitude = Self::SHAKE_MAGNITUDE * (1.0 - frame_timer.progress());
+                let side = if frame_timer.frame() % 2 == 0 { 1.0 } else { -1.0 };
+                (side * magnitude, -side * magnitude / 2.0)
+            },
+            None => (0.0, 0.0),
+        }
+    }
+
+    pub fn instant_drop(&self) -> Option<&InstantDrop> {
+        self.instant_drop.as_ref()
+    }
+
+    // the popups still showing, oldest first
+    pub fn popups(&self) -> &[Popup] {
+        &self.popups
+    }
+
+    // number of pieces spawned of each kind, indexed by PieceKind
+    pub fn stats(&self) -> &[u32; NUM_PIECES] {
+        &self.stats
+    }
+
+    // pieces spawned since the last of the kind, None for kinds that aren't dealt
+    pub fn drought(&self, kind: PieceKind) -> Option<u32> {
+        self.pieces.iter().find(|piece| piece.kind == kind ).map(|_| self.droughts[kind as usize] )
+    }
+
+    // the dealt kind that hasn't spawned for the most pieces, the first of them in the set on a tie
+    pub fn longest_drought(&self) -> Option<(PieceKind, u32)> {
+        self.pieces.iter().map(|piece| (piece.kind, self.droughts[piece.kind as usize]) ).fold(None, |longest, (kind, drought)| match longest {
+            Some((_, most)) if most >= drought => longest,
+            _ => Some((kind, drought)),
+        })
+    }
+
+    // the coord as drawn, the other side of the board when it's mirrored, and the board's coord for a drawn one
+    pub fn mirror(&self, coord: Coord) -> Coord {
+        if self.mirrored { Coord{x: self.width() - 1 - coord.x, ..coord} } else { coord }
+    }
+
+    pub fn mirror_bones(&self, bones: &[Bone]) -> Vec<Bone> {
+        bones.iter().map(|bone| Bone{coord: self.mirror(bone.coord), ..*bone} ).collect()
+    }
+
+    // the piece as drawn, previews included so their shapes match the board's
+    pub fn mirror_piece(&self, piece: &Tetrinome) -> Tetrinome {
+        if self.mirrored { piece.flipped(self.width() - 1) } else { piece.clone() }
+    }
+
+    // the action the board takes for a control, left and right and the rotations are swapped on a mirrored board
+    fn mirror_action(&self, action: Action) -> Action {
+        match action {
+            Action::Left if self.mirrored => Action::Right,
+            Action::Right if self.mirrored => Action::Left,
+            Action::RotateCW if self.mirrored => Action::RotateCCW,
+            Action::RotateCCW if self.mirrored => Action::RotateCW,
+            _ => action,
+        }
+    }
+
+    pub fn rotation_system(&self) -> RotationSystem {
+        self.rotation_system
+    }
+
+    pub fn spawned(&self) -> u32 {
+        self.spawned
+    }
+
+    pub fn hold_enabled(&self) -> bool {
+        self.hold_enabled
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    pub fn finesse_faults(&self) -> u32 {
+        self.finesse_faults
+    }
+
+    pub fn blind(&self) -> bool {
+        self.blind
+    }
+
+    // why the game was lost, for the results, None while it's still going
+    pub fn game_over_reason(&self) -> Option<&'static str> {
+        match self.top_out {
+            Some(top_out) => Some(top_out.name()),
+            None if self.sequence_ended => Some("out of pieces"),
+            None => None,
+        }
+    }
+
+    pub fn ghost_alpha(&self) -> f32 {
+        self.ghost_alpha
+    }
+
+    // whether the shadow piece is drawn as far as the board goes, it's hidden under pressure once the stack reaches the ghost height
+    // Game::ghost_visible also takes the ghost key into account
+    pub fn ghost_visible(&self) -> bool {
+        self.ghost != Ghost::Off && self.ghost_alpha > 0.0 && (self.ghost_height <= 0 || self.blocks.stack_height() < self.ghost_height)
+    }
+
+    // where the current piece would land, found again only after the piece or the board has changed
+    pub fn shadow(&mut self) -> &Tetrinome {
+        let shadow = match self.shadow.take() {
+            Some(shadow) => shadow,
+            None => self.landed(&self.curr_piece),
+        };
+        self.shadow.get_or_insert(shadow)
+    }
+
+    fn add_start_garbage(&mut self) {
+        self.blocks.push_garbage(self.garbage_rows, self.garbage_mess, &mut self.rng); // leaves the top rows clear so nothing is lost
+    }
+
+    // fills an empty cell with a garbage block or empties a filled one, for setting up a board to test
+    fn toggle_block(&mut self, coord: &Coord) {
+        if let Ok(i) = self.blocks.checked_index(coord) {
+            self.blocks.data[i] = match self.blocks.data[i] {
+                Some(_) => None,
+                None => Some(Bone::new(Color::Gray, *coord, PieceKind::Garbage).into()),
+            };
+            self.shadow = None;
+            self.advice = None;
+        }
+    }
+
+    // garbage rows rise under the stack, from survival's floor or the opponent in versus, and the current piece is pushed up out of them
+    // returns true once the stack is pushed over the top
+    fn rise_garbage(&mut self, n_rows: i16) -> bool {
+        let overflowed = self.blocks.push_garbage(n_rows, self.garbage_mess, &mut self.rng);
+        while self.curr_piece.bones.iter().any(|bone| self.blocks.filled(&bone.coord) ) {
+            self.curr_piece.trans_change(&Coord{x: 0, y: -1});
+        }
+        self.shadow = None;
+        self.advice = None;
+        if overflowed {
+            self.top_out = Some(TopOut::Garbage);
+        }
+        overflowed
+    }
+
+    // the next piece for the queue, from the scripted sequence if there is one, None once the sequence has run out
+    fn deal(&mut self) -> Option<Tetrinome> {
+        match &mut self.sequence {
+            Some(sequence) => sequence.pop_front().map(|kind| Tetrinome::scripted(&self.pieces, kind, self.blocks.size) ),
+            None => Some(Tetrinome::new(&self.pieces, self.blocks.size, self.spawn_orientation, self.spawn_column, &mut self.rng)),
+        }
+    }
+
+    // replaces the current piece with the next piece and adds a new piece to the back of the queue
+    // a scripted game ends once the queue is empty, the current piece is left where it locked
+    fn spawn_piece(&mut self) {
+        if let Some(mut next_piece) = self.deal() {
+            if self.items && self.rng.gen_range(0, Self::BOMB_CHANCE) == 0 {
+                let i = self.rng.gen_range(0, next_piece.bones.len());
+                next_piece.bones[i].special = Some(Special::Bomb);
+            }
+            self.next_pieces.push_back(next_piece);
+        }
+        match self.next_pieces.pop_front() {
+            Some(next_piece) => {
+                self.curr_piece = next_piece;
+                self.start_piece();
+            },
+            None => self.sequence_ended = true,
+        }
+    }
+
+    // the letters of the next n pieces the grid will spawn, the current piece first, ie. to share a seed's pieces
+    // fewer once a scripted sequence runs out, survival and versus garbage can change the pieces after the first few
+    pub fn upcoming_pieces(&self, n: usize) -> String {
+        let mut grid = self.clone();
+        let mut letters = String::new();
+        while letters.len() < n && !grid.sequence_ended {
+            letters.push(grid.curr_piece.kind.letter());
+            grid.spawn_piece();
+        }
+        letters
+    }
+
+    // puts the current piece on hold and brings back the one held longest once the hold is full, or the next piece until then
+    // the piece is held as it was spawned and can't be held again until the next piece spawns
+    fn hold_piece(&mut self) {
+        if !self.hold_enabled || !self.can_hold || (self.held_pieces.len() < self.hold_depth && self.next_pieces.is_empty()) {
+            return // nothing to swap with once a scripted sequence is running out
+        }
+        let board_piece = self.curr_piece.clone();
+        self.held_pieces.push_back(self.spawned_piece.clone());
+        let returned = if self.held_pieces.len() > self.hold_depth { self.held_pieces.pop_front() } else { None };
+        match returned {
+            Some(held_piece) => {
+                self.curr_piece = held_piece;
+                self.reset_piece(); // already counted when it first spawned
+            },
+            None => self.spawn_piece(),
+        }
+        self.can_hold = false;
+        if self.hold_animation {
+            self.hold_swap = Some(HoldSwap::new(board_piece));
+        }
+    }
+
+    // the board, the score, the pieces dealt and the rng, enough for a new game of the same config to deal what this one would have
+    fn save(&self) -> Save {
+        Save {
+            rng: self.rng.state(),
+            points: self.score.points,
+            lines: self.score.lines,
+            level: self.score.level,
+            garbage_gap: self.blocks.garbage_gap,
+            can_hold: self.can_hold,
+            piece: self.spawned_piece.saved(),
+            next: self.next_pieces.iter().map(Tetrinome::saved).collect(),
+            held: self.held_pieces.iter().map(Tetrinome::saved).collect(),
+            sequence: self.sequence.as_ref().map(|kinds| kinds.iter().map(|kind| kind.letter() ).collect() ),
+            board: self.blocks.to_ascii(),
+        }
+    }
+
+    // picks up the saved game, the current piece back at its spawn, nothing is changed if the save doesn't fit the board or the pieces
+    fn load(&mut self, save: &Save) -> Result<(), Error> {
+        let kinds: Vec<PieceKind> = self.pieces.iter().map(Tetrinome::kind).collect();
+        let piece = |saved: &SavedPiece| -> Result<Tetrinome, Error> {
+            let kind = PieceKind::sequence(&saved.letter.to_string(), &kinds).map_err(Error::InvalidLayout)?[0];
+            let mut piece = Tetrinome::scripted(&self.pieces, kind, self.blocks.size); // in the variant's colors
+            if saved.cells.len() != piece.bones.len() {
+                return Err(Error::InvalidLayout(format!("expected {} cells for the piece '{}', found {}", piece.bones.len(), saved.letter, saved.cells.len())))
+            }
+            piece.trans_to(saved.cells.clone());
+            piece.orientation = saved.orientation;
+            if let Some(i) = saved.bomb {
+                piece.bones[i].special = Some(Special::Bomb);
+            }
+            Ok(piece)
+        };
+        let curr_piece = piece(&save.piece)?;
+        let next_pieces = save.next.iter().map(piece).collect::<Result<VecDeque<_>, _>>()?;
+        let held_pieces = save.held.iter().map(piece).collect::<Result<VecDeque<_>, _>>()?;
+        let sequence = match &save.sequence {
+            Some(letters) => Some(PieceKind::sequence(letters, &kinds).map_err(Error::InvalidLayout)?.into_iter().collect()),
+            None => None,
+        };
+        self.blocks.data = Blocks::from_ascii(&save.board, self.blocks.size)?.data;
+        self.blocks.garbage_gap = save.garbage_gap;
+        self.rng = Pcg32::from_state(save.rng.0, save.rng.1);
+        self.score.points = save.points;
+        self.score.lines = save.lines;
+        self.score.level = save.level;
+        self.curr_piece = curr_piece;
+        self.next_pieces = next_pieces;
+        self.held_pieces = held_pieces;
+        self.sequence = sequence;
+        self.reset_piece();
+        self.can_hold = save.can_hold;
+        Ok(())
+    }
+
+    // clears the board and the stats, keeping the current piece
+    fn restart(&mut self) {
+        self.blocks.clear();
+        self.add_start_garbage();
+        self.stats = [0; NUM_PIECES];
+        self.droughts = [0; NUM_PIECES];
+        self.spawned = 0;
+        self.history.clear();
+        self.finesse_faults = 0;
+        self.score = Score::new(self.blind, self.start_level, self.score.scoring());
+        self.popups.clear();
+        self.held_pieces.clear();
+        self.hold_swap = None;
+        self.shake = None;
+        self.sent_garbage = 0;
+        self.top_out = None;
+        self.start_piece();
+    }
+
+    // empties the board keeping the score, the stats and the current piece
+    fn clear_board(&mut self) {
+        self.blocks.clear();
+        self.shadow = None;
+        self.advice = None;
+    }
+
+    // records the state before a placement so it can be undone, dropping the oldest once the history is full
+    fn save_snapshot(&mut self) {
+        if let Mode::Practice = self.mode {
+            if self.history.len() == Self::UNDO_DEPTH {
+                self.history.pop_front();
+            }
+            self.history.push_back(Snapshot {
+                blocks: self.blocks.clone(),
+                curr_piece: self.curr_piece.clone(),
+                spawned_piece: self.spawned_piece.clone(),
+                next_pieces: self.next_pieces.clone(),
+                sequence: self.sequence.clone(),
+                held_pieces: self.held_pieces.clone(),
+                can_hold: self.can_hold,
+                stats: self.stats,
+                droughts: self.droughts,
+                score: self.score,
+                finesse_faults: self.finesse_faults,
+            });
+        }
+    }
+
+    // restores the state from before the last placement
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.history.pop_back() {
+            self.blocks = snapshot.blocks;
+            self.curr_piece = snapshot.curr_piece;
+            self.shadow = None;
+            self.advice = None;
+            self.spawned_piece = snapshot.spawned_piece;
+            self.next_pieces = snapshot.next_pieces;
+            self.sequence = snapshot.sequence;
+            self.held_pieces = snapshot.held_pieces;
+            self.can_hold = snapshot.can_hold;
+            self.hold_swap = None;
+            self.stats = snapshot.stats;
+            self.droughts = snapshot.droughts;
+            self.score = snapshot.score;
+            self.finesse_faults = snapshot.finesse_faults;
+            self.inputs = 0;
+            self.last_move_rotated = false;
+            self.lock_resets = 0;
+            self.instant_drop = None;
+        }
+    }
+
+    // counts the newly spawned current piece and resets the per piece finesse tracking
+    fn start_piece(&mut self) {
+        self.stats[self.curr_piece.kind as usize] += 1;
+        for drought in self.droughts.iter_mut() {
+            *drought += 1;
+        }
+        self.droughts[self.curr_piece.kind as usize] = 0;
+        self.spawned += 1;
+        self.can_hold = true;
+        self.reset_piece();
+        self.events.push(HookEvent::Spawn(self.curr_piece.clone()));
+    }
+
+    // resets the per piece finesse tracking and lock delay for a piece entering the board
+    fn reset_piece(&mut self) {
+        if self.top_out_rules.block_out && self.curr_piece.bones.iter().any(|bone| self.blocks.filled(&bone.coord) ) {
+            self.top_out = Some(TopOut::BlockOut); // no room for the piece to enter
+        }
+        self.spawned_piece = self.curr_piece.clone();
+        self.inputs = 0;
+        self.last_move_rotated = false;
+        self.lock_resets = 0;
+        self.reset_lock = false;
+        self.reset_fall = false;
+        self.shadow = None;
+        self.advice = None;
+    }
+
+    // commit the piece after a downwards collision 
+    fn commit_piece(&mut self) {
+        let lock = if self.hard_dropping { Lock::Hard } else { Lock::Soft };
+        for new_block in self.curr_piece.bones.iter_mut() {
+            if self.blocks.set_block(&new_block.coord, *new_block).is_ok() { // bones above the hidden rows are dropped
+                if self.juice {
+                    self.blocks.start_flash(&new_block.coord, lock);
+                }
+                if self.invisible {
+                    self.blocks.start_fade(&new_block.coord);
+                }
+            }
+        }
+        if self.top_out_rules.lock_out && self.curr_piece.bones.iter().all(|bone| bone.coord.y < 0 ) {
+            self.top_out = Some(TopOut::LockOut); // locked without reaching the visible rows
+        }
+        if self.juice && self.blocks.stack_height() > self.height() - Self::DANGER_ROWS {
+            self.shake = Some(FrameTimer::lasting(SHAKE_DURATION, millis_per_update(), 0.0));
+        }
+    }
+
+    // clears the rows that are done animating, rows filled by cascade gravity are scored as their own clear
+    fn finish_clear(&mut self) {
+        if !self.blocks.rows_full.is_empty() {
+            self.shadow = None; // the rows might clear and move the stack under the piece
+            self.advice = None;
+        }
+        let n_pending = self.blocks.rows_full.len();
+        let cascaded = self.blocks.finish_clear();
+        if let Some(top_row) = cascaded.iter().min() {
+            self.score_clear(Clear{lines: cascaded.len(), t_spin: false}, *top_row, true);
+        }
+
+        // only a board emptied by a clear counts, not one that starts empty
+        let n_cleared = n_pending + cascaded.len() - self.blocks.rows_full.len();
+        if n_cleared > 0 && self.blocks.is_empty() {
+            let points = self.score.add_perfect_clear();
+            self.push_popup(vec!["ALL CLEAR".to_string(), format!("+{}", points)], self.height() / 2);
+        }
+    }
+
+    // starts clearing the rows filled by the committed piece, returns the number of rows
+    fn clear_row_if(&mut self) -> usize {
+        let rows = self.blocks.get_piece_rows(&self.curr_piece); // in asc order
+
+        // only the piece's rows can have become full, rows already clearing are skipped so they aren't counted twice
+        let full: Vec<i16> = rows.into_iter().filter(|row| self.blocks.row_full(row) && !self.blocks.rows_full.contains(row) ).collect();
+        for row in full.iter() {
+            self.blocks.start_clear(row, full.len());
+        }
+        full.len()
+    }
+
+    // a t-spin is a T piece whose last move was a rotation with at least 3 of the 4 corners around its pivot filled
+    fn is_t_spin(&self) -> bool {
+        if !self.last_move_rotated {
+            return false
+        }
+        if let (PieceKind::T, Some(pivot_i)) = (self.curr_piece.kind, self.curr_piece.pivot) {
+            let pivot = self.curr_piece.bones[pivot_i].coord;
+            let corners = [Coord{x: -1, y: -1}, Coord{x: 1, y: -1}, Coord{x: -1, y: 1}, Coord{x: 1, y: 1}];
+            let n_filled = corners.iter().filter(|corner| self.blocks.filled(&(pivot + **corner)) ).count();
+            return n_filled >= 3
+        }
+        false
+    }
+
+    // scores the clear and shows its name and points at the top row of the clear
+    fn score_clear(&mut self, clear: Clear, top_row: i16, cascade: bool) {
+        if !clear.scores() {
+            return
+        }
+        let back_to_back = self.score.back_to_back(&clear);
+        let level = self.score.level;
+        let points = self.score.add_clear(&clear);
+        if clear.lines > 0 {
+            self.events.push(HookEvent::Clear(ClearInfo{lines: clear.lines, t_spin: clear.t_spin, back_to_back, points, cascade}));
+        }
+        if self.score.level > level {
+            self.events.push(HookEvent::LevelUp(self.score.level));
+        }
+        self.sent_garbage += clear.garbage();
+        let mut lines = vec![clear.name().to_string(), format!("+{}", points)];
+        if back_to_back {
+            lines.insert(0, "BACK-TO-BACK".to_string());
+        }
+        self.push_popup(lines, top_row);
+    }
+
+    // shows the text over the board starting at the row
+    // the frontend lays out popups that overlap
+    fn push_popup(&mut self, lines: Vec<String>, top_row: i16) {
+        self.popups.push(Popup::new(lines, top_row.max(0)));
+    }
+
+    // move_if is the actually called helper, taking a direction and determining whether or not to move
+    fn move_if(&mut self, dir: Direction, rot: Rotation) -> bool {
+        if let (RotationSystem::Srs, Rotation::CW) | (RotationSystem::Srs, Rotation::CCW) = (self.rotation_system, rot) {
+            self.rotate_srs(rot);
+            return false;
+        }
+
+        let mut new_piece = self.curr_piece.clone();
+        new_piece.trans_change(&dir.into()); // translate new piece based on direction
+        new_piece.rotate(&rot); // do rotation
+
+        let col = self.blocks.check_collision(&new_piece, &dir, &rot);
+        match col { // check collision for new piece
+            Collision::Under if self.hard_drop_lock && !self.hard_dropping => (), // rests on the stack, still free to move into gaps
+            Collision::Under => { 
+                let t_spin = self.is_t_spin();
+                self.save_snapshot();
+                self.judge_finesse();
+                self.commit_piece(); 
+                let lines = self.clear_row_if(); 
+                let top_row = self.blocks.get_piece_rows(&self.curr_piece)[0];
+                let height = (f32::from(self.height() - 1 - top_row) / f32::from((self.height() - 1).max(1))).clamp(0.0, 1.0); // the hidden rows count as the top
+                self.events.push(HookEvent::Lock(LockInfo{kind: self.curr_piece.kind, bones: self.curr_piece.bones.clone(), hard_drop: self.hard_dropping, t_spin, lines, height}));
+                self.score_clear(Clear{lines, t_spin}, top_row, false);
+                if self.blocks.clear_duration <= 0.0 {
+                    // instant clears, cascades included, are done before the next piece spawns rather than on the next update
+                    while !self.blocks.rows_full.is_empty() {
+                        self.finish_clear();
+                    }
+                }
+                self.spawn_piece(); 
+                return true;
+            }, // if collided underneath then commit
+            Collision::Left | Collision::Right  => {
+                if let Rotation::CCW | Rotation::CW = rot {
+                    if let Some(kicked_piece) = self.naive_kick(new_piece, col) {
+                        self.curr_piece = kicked_piece;
+                        self.shadow = None;
+                        self.last_move_rotated = true;
+                    }
+                }
+            }, // collided on the side, nothing happens
+            Collision::None => { // no collision, then move
+                self.curr_piece = new_piece;
+                self.shadow = None;
+                self.last_move_rotated = rot != Rotation::None;
+            },
+        }
+        false
+    }
+
+    // a move made by the player rather than gravity, counted towards the piece's finesse
+    fn player_move(&mut self, dir: Direction, rot: Rotation) {
+        if let Direction::Left | Direction::Right = dir {
+            self.inputs += 1;
+        } else if let Rotation::CW | Rotation::CCW = rot {
+            self.inputs += 1;
+        }
+
+        let was_resting = self.resting();
+        let cells = self.curr_piece.sorted_cells();
+        let locked = self.move_if(dir, rot);
+        if !locked && cells != self.curr_piece.sorted_cells() && (was_resting || self.resting()) {
+            self.lock_resets += 1;
+            self.reset_lock = true;
+        }
+        if let (true, false, Direction::Left) | (true, false, Direction::Right) = (self.fall_reset, locked, dir) {
+            self.reset_fall |= cells != self.curr_piece.sorted_cells(); // only a move that happened, not one into a wall
+        }
+    }
+
+    // shifts a rotated piece away from the side it collided with by half its width, None if it still collides
+    fn naive_kick(&self, mut new_piece: Tetrinome, col: Collision) -> Option<Tetrinome> {
+        let col_dir: Direction = col.into();
+        let new_dir = col_dir.opposite();
+        for _ in 0..new_piece.get_width()/2 {
+            new_piece.trans_change(&new_dir.into());
+        }
+        if let Collision::None = self.blocks.check_collision(&new_piece, &new_dir, &Rotation::None) {
+            return Some(new_piece)
+        }
+        None
+    }
+
+    // returns the piece rotated using the first srs kick offset that doesn't collide
+    fn srs_rotated(&self, piece: &Tetrinome, rot: Rotation) -> Option<Tetrinome> {
+        let mut rotated = piece.clone();
+        rotated.rotate(&rot);
+        for offset in kicks(rotated.kind, piece.orientation, rotated.orientation).iter() {
+            let mut new_piece = rotated.clone();
+            new_piece.trans_change(offset);
+            if let Collision::None = self.blocks.check_collision(&new_piece, &Direction::None, &rot) {
+                return Some(new_piece)
+            }
+        }
+        None
+    }
+
+    // rotates the current piece using the first srs kick offset that doesn't collide, otherwise leaves it in place
+    fn rotate_srs(&mut self, rot: Rotation) {
+        if let Some(new_piece) = self.srs_rotated(&self.curr_piece, rot) {
+            self.curr_piece = new_piece;
+            self.shadow = None;
+            self.last_move_rotated = true;
+        }
+    }
+
+    // returns the piece rotated by the selected rotation system, None if it can't rotate
+    fn rotated(&self, piece: &Tetrinome, rot: Rotation) -> Option<Tetrinome> {
+        match self.rotation_system {
+            RotationSystem::Srs => self.srs_rotated(piece, rot),
+            RotationSystem::Naive => {
+                let mut new_piece = piece.clone();
+                new_piece.rotate(&rot);
+                let col = self.blocks.check_collision(&new_piece, &Direction::None, &rot);
+                match col {
+                    Collision::None => Some(new_piece),
+                    Collision::Left | Collision::Right => self.naive_kick(new_piece, col),
+                    Collision::Under => None,
+                }
+            }
+        }
+    }
+
+    // returns the piece translated in the direction, None if it collides
+    fn shifted(&self, piece: &Tetrinome, dir: Direction) -> Option<Tetrinome> {
+        let mut new_piece = piece.clone();
+        new_piece.trans_change(&dir.into());
+        if let Collision::None = self.blocks.check_collision(&new_piece, &dir, &Rotation::None) {
+            return Some(new_piece)
+        }
+        None
+    }
+
+    // whether rows are still animating a clear, the next piece waits at the spawn until they're gone
+    fn clearing(&self) -> bool {
+        !self.blocks.rows_full.is_empty()
+    }
+
+    // whether the current piece is sitting on the stack or the floor
+    fn resting(&self) -> bool {
+        self.shifted(&self.curr_piece, Direction::Down).is_none()
+    }
+
+    // moves the current piece down to where it would land without locking it, an instant soft drop
+    fn sonic_drop(&mut self) {
+        if !self.resting() {
+            self.curr_piece = self.landed(&self.curr_piece);
+        }
+    }
+
+    // every spot the piece can land in by rotating, moving all the way along the row and dropping, each spot once
+    fn placements(&self, piece: &Tetrinome) -> Vec<Tetrinome> {
+        let mut placements = Vec::new();
+        let mut seen = HashSet::new();
+        let mut rotated = Some(piece.clone());
+        for _ in 0..4 {
+            let piece = match rotated {
+                Some(piece) => piece,
+                None => break,
+            };
+            let mut shifted = piece.clone();
+            while let Some(left) = self.shifted(&shifted, Direction::Left) {
+                shifted = left;
+            }
+            let mut candidate = Some(shifted);
+            while let Some(piece) = candidate {
+                let landed = self.landed(&piece);
+                if seen.insert(landed.sorted_cells()) {
+                    placements.push(landed);
+                }
+                candidate = self.shifted(&piece, Direction::Right);
+            }
+            rotated = self.rotated(&piece, Rotation::CW);
+        }
+        placements
+    }
+
+    // returns the piece moved down to where it would land
+    fn landed(&self, piece: &Tetrinome) -> Tetrinome {
+        let mut landed_piece = piece.clone();
+        for _ in 0..self.shadow_distance(piece) {
+            landed_piece.trans_change(&Direction::Down.into());
+        }
+        landed_piece
+    }
+
+    // breadth first search over the left, right and rotation moves from the start piece for the fewest inputs that land on the target
+    // None if the target can't be reached by dropping from the top, ie. it was tucked under an overhang
+    fn min_inputs(&self, start: &Tetrinome, target: &Tetrinome) -> Option<u32> {
+        let target_cells = target.sorted_cells();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start.sorted_cells());
+        queue.push_back((start.clone(), 0));
+
+        while let Some((piece, n_inputs)) = queue.pop_front() {
+            if self.landed(&piece).sorted_cells() == target_cells {
+                return Some(n_inputs)
+            }
+            let next_pieces = [
+                self.shifted(&piece, Direction::Left),
+                self.shifted(&piece, Direction::Right),
+                self.rotated(&piece, Rotation::CW),
+                self.rotated(&piece, Rotation::CCW),
+            ];
+            for next_piece in next_pieces.iter().flatten() {
+                if visited.insert(next_piece.sorted_cells()) {
+                    queue.push_back((next_piece.clone(), n_inputs + 1));
+                }
+            }
+        }
+        None
+    }
+
+    // counts a finesse fault if the current piece used more inputs than necessary to reach where it's being committed
+    fn judge_finesse(&mut self) {
+        if let Some(min_inputs) = self.min_inputs(&self.spawned_piece, &self.curr_piece) {
+            if self.inputs > min_inputs {
+                self.finesse_faults += 1;
+            }
+        }
+    }
+
+    fn shadow_distance(&self, piece: &Tetrinome) -> usize {
+        let mut shadow_piece = piece.clone();
+        let mut i = 0;
+        loop {
+            let col_dir = self.blocks.check_collision(&shadow_piece, &Direction::Down, &Rotation::None);
+            if let Collision::Under = col_dir { // check collision for new piece
+                if i != 0 { // panic subtract overflow
+                    break i - 1;
+                } else {
+                    break 0;
+                }
+            }
+            i += 1;
+            shadow_piece.trans_change(&Direction::Down.into()); // translate new piece based on direction
+        }
+    }
+
+    fn start_drop(&mut self, piece: Tetrinome) {
+        let n_frames = self.shadow_distance(&piece) + 1;
+        self.instant_drop = Some(InstantDrop {
+            piece: piece.clone(),
+            frame_timer: FrameTimer::equal_sized(n_frames, DROP_ROW_DURATION, 0.0),
+            trail: Vec::with_capacity(n_frames),
+        });
+    }
+
+    fn finish_drop(&mut self) {
+        let curr_piece = self.curr_piece.clone();
+        self.hard_dropping = true;
+        let mut rows = 0;
+        loop {
+            if self.move_if(Direction::Down, Rotation::None) { 
+                self.start_drop(curr_piece);
+                break; 
+            }
+            rows += 1;
+        }
+        self.hard_dropping = false;
+        self.score.add_hard_drop(rows);
+    }
+
+    // steps the instant drop, the hold swap, the shake, the popups and the blocks' clear and lock flash animations
+    fn animate(&mut self, elapsed: f64) {
+        self.blocks.animate(elapsed);
+        self.animate_drop(elapsed);
+        if let Some(hold_swap) = &mut self.hold_swap {
+            if let FrameState::Done = hold_swap.frame_timer.state(elapsed) {
+                self.hold_swap = None;
+            }
+        }
+        if let Some(shake) = &mut self.shake {
+            if let FrameState::Done = shake.state(elapsed) {
+                self.shake = None;
+            }
+        }
+        self.popups.retain(|popup| !matches!(popup.frame_timer.get_state(), FrameState::Done) );
+        for popup in self.popups.iter_mut() {
+            popup.frame_timer.state(elapsed);
+        }
+    }
+
+    fn animate_drop(&mut self, elapsed: f64) {
+        if let Some(instant_drop) = &mut self.instant_drop {
+            let state = instant_drop.frame_timer.state(elapsed);
+            
+            if let FrameState::Ready = state {
+                instant_drop.trail.push(instant_drop.piece.clone());
+                let piece = &mut instant_drop.piece;
+                piece.trans_change(&Direction::Down.into());
+            } else if let FrameState::Done = state {
+                self.instant_drop = None;
+            }
+        }
+    }
+}
+
+// what the game is currently doing, gameplay only runs while Playing
+#[derive(Clone)]
+pub enum GameState {
+    Loading(Box<GameState>), // waiting for the frontend's assets, holds the state to start in once they've loaded
+    LoadFailed(Box<GameState>, String), // an asset couldn't be loaded, the error is shown until it's confirmed and the game starts without it
+    Menu(Menu), // picking the mode and the starting level before the countdown
+    Countdown(FrameTimer), // one frame per countdown step
+    Playing,
+    ConfirmQuit(Box<GameState>), // paused on the quit overlay, holds the state to resume
+    Paused(Box<GameState>), // nothing is updated, so every animation picks up from the frame it stopped on
+    Victory, // the marathon level cap was reached, the results are shown until the player quits or goes back to the menu
+    GameOver, // the stack was pushed over the top, the results are shown until the player quits or goes back to the menu
+}
+
+impl GameState {
+    fn countdown() -> Self {
+        let step_duration = COUNTDOWN_DURATION / COUNTDOWN_STEPS as f64;
+        GameState::Countdown(FrameTimer::equal_sized(COUNTDOWN_STEPS + 1, step_duration, 0.0)) // the first frame is ready immediately
+    }
+}
+
+// the simulation a frontend drives with press, release and tick and draws from
+#[derive(Clone)]
+pub struct Game {
+    grid: Grid,
+    timing: Timer,
+    state: GameState,
+    config: Config,
+    handling: Handling,
+    held_rotation: Option<(Rotation, Repeat)>, // rotation key being held when rotate_repeat is on
+    held_shift: Option<(Direction, Repeat)>, // most recently pressed move key still held, auto-repeats after the das every arr
+    soft_drop_repeat: Option<Repeat>, // moves the piece down at a soft drop rate while down is held, alongside the gravity
+    held_keys: HeldKeys, // the gameplay keys down as of the inputs applied so far
+    previewed_rotation: Option<Rotation>, // rotation key held with the rotation preview on, performed on release
+    pending_inputs: Vec<(Action, bool)>, // presses and releases since the last update, applied by tick so os key repeat can't move the piece more than once an update
+    input_buffer: VecDeque<Action>, // moves pressed before the piece can be controlled, applied on the first update it can be
+    play_time: f64, // milliseconds spent playing, shown on the results
+    rise: Option<RiseTimer>, // survival's rising floor
+    kill_timer: Option<KillTimer>, // the time the stack has been at the kill screen height, None without a kill screen
+    kill_screen: bool, // pieces fall to the stack at once and survival's rows rise their fastest until the game ends
+    piece_rate: PieceRate,
+    incoming_garbage: i16, // rows sent by the opponent in versus, rising on the next playing update
+    frame: u64, // updates since the game started, the inputs are recorded by the update they came before
+    steps: Option<u32>, // in step mode the updates asked for that haven't run yet, None runs every update
+    replay: Replay,
+    hooks: Rc<RefCell<Hooks>>, // shared with the game's clones, so a replay seeking back to a snapshot keeps them
+}
+
+impl Game {
+    const INPUT_BUFFER_SIZE: usize = 4;
+
+    // starts the countdown of a new game with the config's pieces and board size
+    pub fn new(config: Config) -> Self {
+        Game {
+            grid: Grid::new(&config),
+            timing: Timer::new(config.gravity_curve.clone()),
+            state: if config.menu { GameState::Menu(Menu::new(&config)) } else { GameState::countdown() },
+            handling: config.handling,
+            replay: Replay::new(config.seed),
+            hooks: Rc::default(),
+            rise: Self::rise_timer(config.mode),
+            kill_timer: Self::kill_timer(&config),
+            kill_screen: false,
+            incoming_garbage: 0,
+            piece_rate: PieceRate::new(config.piece_rate_window),
+            config,
+            held_rotation: None,
+            held_shift: None,
+            soft_drop_repeat: None,
+            held_keys: HeldKeys::default(),
+            previewed_rotation: None,
+            pending_inputs: Vec::new(),
+            input_buffer: VecDeque::with_capacity(Self::INPUT_BUFFER_SIZE),
+            play_time: 0.0,
+            frame: 0,
+            steps: None,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // fills or empties the cell in the editor, ignored outside it so it can't be used in a game
+    pub fn toggle_block(&mut self, coord: &Coord) {
+        if self.config.editor {
+            self.grid.toggle_block(coord);
+        }
+    }
+
+    // for the display toggles and the cached shadow
+    pub fn grid_mut(&mut self) -> &mut Grid {
+        &mut self.grid
+    }
+
+    // the game so far, picked up by load in a new game of the same config
+    pub fn save(&self) -> Save {
+        self.grid.save()
+    }
+
+    // picks up a saved game in a game that hasn't started, the save's board and pieces have to fit the config's
+    pub fn load(&mut self, save: &Save) -> Result<(), Error> {
+        self.grid.load(save)
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // where the held rotation key would put the current piece, kicks included, None without a held rotation or if it can't rotate
+    pub fn rotation_preview(&self) -> Option<Tetrinome> {
+        self.previewed_rotation.and_then(|rot| self.grid.rotated(&self.grid.curr_piece, rot) )
+    }
+
+    // the inputs so far, played back by pressing and releasing each before its frame's tick
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    // the updates played so far, the frame the next inputs are recorded by
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // the garbage rows earned by clears since the last call, for the frontend to send to the opponent in versus
+    pub fn take_sent_garbage(&mut self) -> i16 {
+        std::mem::replace(&mut self.grid.sent_garbage, 0)
+    }
+
+    // garbage rows from the opponent, they rise once the game is playing and can push the stack over the top
+    pub fn receive_garbage(&mut self, n_rows: i16) {
+        self.incoming_garbage += n_rows;
+    }
+
+    // ends the game as won, ie. when the opponent in versus is pushed over the top, the overlays and a finished game are left as they are
+    pub fn win(&mut self) {
+        if let GameState::Countdown(_) | GameState::Playing = self.state {
+            self.release_all();
+            self.state = GameState::Victory;
+        }
+    }
+
+    fn game_over(&mut self) {
+        self.state = GameState::GameOver;
+        self.grid.events.push(HookEvent::GameOver(self.grid.score));
+    }
+
+    // the callbacks fired on spawns, locks, clears, level ups and the game ending, see hooks.rs
+    pub fn hooks_mut(&mut self) -> RefMut<'_, Hooks> {
+        self.hooks.borrow_mut()
+    }
+
+    // passes what happened on the board during the update to the hooks
+    fn fire_hooks(&mut self) {
+        let events = std::mem::take(&mut self.grid.events);
+        let mut hooks = self.hooks.borrow_mut();
+        for event in events.iter() {
+            hooks.fire(event);
+        }
+    }
+
+    // the gameplay keys down and how long they've been held
+    pub fn held_keys(&self) -> &HeldKeys {
+        &self.held_keys
+    }
+
+    // pieces per second over the config's window of play, only counted while playing so pauses don't lower it
+    pub fn piece_rate(&self) -> f64 {
+        self.piece_rate.rate()
+    }
+
+    // survival's rising floor, None in the other modes
+    pub fn rise(&self) -> Option<&RiseTimer> {
+        self.rise.as_ref()
+    }
+
+    fn kill_timer(config: &Config) -> Option<KillTimer> {
+        if config.kill_height > 0 { Some(KillTimer::new(config.kill_delay)) } else { None }
+    }
+
+    pub fn kill(&self) -> Option<&KillTimer> {
+        self.kill_timer.as_ref()
+    }
+
+    pub fn kill_screen(&self) -> bool {
+        self.kill_screen
+    }
+
+    fn rise_timer(mode: Mode) -> Option<RiseTimer> {
+        if let Mode::Survival = mode { Some(RiseTimer::new()) } else { None }
+    }
+
+    pub fn handling(&self) -> &Handling {
+        &self.handling
+    }
+
+    // milliseconds spent playing
+    pub fn play_time(&self) -> f64 {
+        self.play_time
+    }
+
+    // milliseconds per row the piece falls at the current level, 0 once the kill screen drops pieces straight to the stack
+    pub fn gravity(&self) -> f64 {
+        if self.kill_screen { 0.0 } else { self.config.gravity_curve.rate(self.grid.score.level) }
+    }
+
+    // whether the shadow piece is drawn, with the ghost on demand only while the ghost key is held
+    pub fn ghost_visible(&self) -> bool {
+        self.grid.ghost_visible() && (self.grid.ghost != Ghost::OnDemand || self.held_keys.is_held(Action::Ghost))
+    }
+
+    // whether the game is frozen in step mode, advancing an update each time the step key is pressed
+    pub fn stepping(&self) -> bool {
+        self.steps.is_some()
+    }
+
+    // the fraction of the way the current piece is to the next row when falling smoothly, otherwise it snaps a row at a time
+    pub fn fall_progress(&self) -> f64 {
+        if self.config.smooth_fall && !self.grid.resting() {
+            return self.timing.fall_progress()
+        }
+        0.0
+    }
+
+    // queues a move or rotation, only the latest directional input is kept since earlier ones are stale
+    fn buffer_input(&mut self, action: Action) {
+        let is_directional = |action: &Action| !matches!(Direction::from(*action), Direction::None);
+        if is_directional(&action) {
+            self.input_buffer.retain(|buffered| !is_directional(buffered) );
+        } else if Rotation::from(action) == Rotation::None {
+            return // only moves and rotations are buffered, ie. not instant drops
+        }
+        if self.input_buffer.len() == Self::INPUT_BUFFER_SIZE {
+            self.input_buffer.pop_front();
+        }
+        self.input_buffer.push_back(action);
+    }
+
+    // keeps the presses since the last update for the next piece, releases still let go of the keys held before it
+    fn buffer_pending_inputs(&mut self) {
+        for (action, pressed) in std::mem::take(&mut self.pending_inputs) {
+            if pressed {
+                self.buffer_input(action);
+            } else {
+                self.apply_release(action);
+            }
+        }
+    }
+
+    // applies the buffered inputs to the current piece
+    fn drain_input_buffer(&mut self) {
+        while let Some(action) = self.input_buffer.pop_front() {
+            self.grid.player_move(action.into(), action.into());
+        }
+    }
+
+    // starts repeating the rotation while its key is held
+    fn hold_rotation(&mut self, rot: Rotation) {
+        if !self.config.rotate_repeat || rot == Rotation::None {
+            return
+        }
+        self.held_rotation = Some((rot, Repeat::new(ROTATE_REPEAT_DELAY, ROTATE_REPEAT_INTERVAL)));
+    }
+
+    // moves the piece and starts auto-repeating the move once the das has passed
+    // the most recently pressed direction wins, the opposite key's das is cancelled while the newer key is held
+    fn hold_shift(&mut self, dir: Direction) {
+        self.grid.player_move(dir, Rotation::None);
+        self.held_shift = Some((dir, Repeat::new(self.handling.das, self.handling.arr)));
+    }
+
+    // stops the move key's auto-repeat, falling back to charging the das of the opposite key if it's still held
+    fn release_shift(&mut self, dir: Direction) {
+        if self.held_shift.as_ref().map(|(held_dir, _)| *held_dir ) == Some(dir) {
+            let (das, arr) = (self.handling.das, self.handling.arr);
+            self.held_shift = self.held_keys.latest(&[Action::Left, Action::Right]).map(|other| (other.into(), Repeat::new(das, arr)) );
+        }
+    }
+
+    // moves the piece a row, or to where it would land with an instant soft drop, the fall speeds up until down is released
+    fn start_soft_drop(&mut self) {
+        let (row, was_resting) = (self.grid.curr_piece.row(), self.grid.resting());
+        match self.handling.soft_drop {
+            SoftDrop::Factor(_) => self.grid.player_move(Direction::Down, Rotation::None),
+            SoftDrop::Rate(rate) => {
+                self.grid.player_move(Direction::Down, Rotation::None);
+                self.soft_drop_repeat = Some(Repeat::new(SECOND / rate, SECOND / rate));
+            },
+            SoftDrop::Instant => self.grid.sonic_drop(),
+        }
+        if !was_resting {
+            self.score_soft_drop(row); // a resting piece locks instead, the next piece's row means nothing
+        }
+    }
+
+    // awards the soft drop points for the rows the current piece has fallen since it was at the row
+    fn score_soft_drop(&mut self, row: i16) {
+        let rows = (self.grid.curr_piece.row() - row).max(0) as u32;
+        self.grid.score.add_soft_drop(rows, self.config.soft_drop_points);
+    }
+
+    // forgets the held keys and the inputs waiting for the update, their releases can be missed while paused or restarting
+    fn release_all(&mut self) {
+        self.pending_inputs.clear();
+        self.held_rotation = None;
+        self.held_shift = None;
+        self.soft_drop_repeat = None;
+        self.held_keys.clear();
+        self.previewed_rotation = None;
+    }
+
+    // whether a marathon has cleared the lines for its level cap
+    fn won(&self) -> bool {
+        let capped = self.config.level_cap > 0;
+        capped && self.config.mode == Mode::Marathon && self.grid.score.completed(self.config.level_cap)
+    }
+
+    // the level the fall rate is taken from, gravity stops speeding up at the cap
+    fn capped_level(&self) -> u32 {
+        if self.config.level_cap > 0 {
+            return self.grid.score.level.min(self.config.level_cap)
+        }
+        self.grid.score.level
+    }
+
+    fn release_rotation(&mut self, rot: Rotation) {
+        if let Some((held_rot, _)) = &self.held_rotation {
+            if *held_rot == rot {
+                self.held_rotation = None;
+            }
+        }
+    }
+
+    // the simulation is driven by press, release and tick so it can run without a window, ie. replayed from a list of inputs
+    // gameplay inputs wait for the next tick, the overlays and display toggles respond at once
+    // returns true once the player has confirmed quitting
+    pub fn press(&mut self, action: Action) -> bool {
+        self.replay.record(self.frame, action, true);
+        if let GameState::ConfirmQuit(_) = self.state {
+            match action {
+                Action::Confirm => return true,
+                Action::Cancel | Action::Quit => self.resume(),
+                _ => (),
+            }
+            return false
+        }
+        if let Action::Quit = action {
+            let state = std::mem::replace(&mut self.state, GameState::Playing);
+            self.state = GameState::ConfirmQuit(Box::new(state));
+            self.release_all();
+            return false
+        }
+        if let GameState::Loading(_) = self.state {
+            return false
+        }
+        if let GameState::LoadFailed(..) = self.state {
+            if let Action::Confirm = action {
+                if let GameState::LoadFailed(state, _) = std::mem::replace(&mut self.state, GameState::Playing) {
+                    self.state = *state;
+                }
+            }
+            return false
+        }
+        if let GameState::Menu(menu) = &mut self.state {
+            if menu.press(action) {
+                let menu = menu.clone();
+                self.start(&menu);
+            }
+            return false
+        }
+        if let GameState::Paused(_) = self.state {
+            if let Action::Pause = action {
+                self.unpause();
+            }
+            return false
+        }
+        if let (Action::Pause, GameState::Countdown(_)) | (Action::Pause, GameState::Playing) = (action, &self.state) {
+            let state = std::mem::replace(&mut self.state, GameState::Playing);
+            self.state = GameState::Paused(Box::new(state));
+            self.release_all();
+            return false
+        }
+        match action {
+            Action::ToggleStep if self.config.debug => {
+                self.steps = if self.steps.is_some() { None } else { Some(0) };
+                self.release_all();
+                return false
+            },
+            Action::Cancel if self.steps.is_some() => {
+                self.steps = self.steps.map(|steps| steps + 1 );
+                return false
+            },
+            _ => (),
+        }
+        if let GameState::Countdown(_) = self.state {
+            self.buffer_input(action); // applied once the countdown finishes
+            return false
+        }
+        if let GameState::Victory | GameState::GameOver = self.state {
+            if let (Action::Confirm, true) = (action, self.config.menu) {
+                self.config.seed = self.grid.rng.gen(); // the next game gets new pieces, the same for a session started from the same seed
+                self.state = GameState::Menu(Menu::new(&self.config));
+            }
+            return false
+        }
+        match action {
+            Action::Restart if self.config.debug => {
+                self.grid.restart();
+                self.release_all();
+                self.input_buffer.clear();
+                self.play_time = 0.0;
+                self.piece_rate = PieceRate::new(self.config.piece_rate_window);
+                self.rise = Self::rise_timer(self.config.mode);
+                self.kill_timer = Self::kill_timer(&self.config);
+                self.kill_screen = false;
+                self.incoming_garbage = 0;
+                self.state = GameState::countdown();
+            },
+            Action::ClearBoard if self.config.debug => self.grid.clear_board(),
+            Action::TogglePatterns => self.grid.patterns = !self.grid.patterns,
+            Action::ToggleMono => self.grid.mono = !self.grid.mono,
+            Action::ToggleCoords => self.grid.show_coords = !self.grid.show_coords,
+            Action::ToggleAdvice => self.grid.show_advice = !self.grid.show_advice,
+            Action::ToggleGhost => self.grid.ghost = self.grid.ghost.next(),
+            Action::ToggleMirror => {
+                self.grid.mirrored = !self.grid.mirrored;
+                self.release_all(); // a key held through the flip would be released as the other direction
+            },
+            Action::SelectRecolor => self.grid.palette.select_next(&self.grid.pieces),
+            Action::Recolor => self.grid.palette.cycle(&self.grid.pieces),
+            Action::ResetColors => self.grid.palette.reset(),
+            Action::ToggleHiddenRows => (), // the game is the same either way
+            _ => {
+                // a press already waiting for the update is an os key repeat, das is the only auto-repeat
+                let action = self.grid.mirror_action(action);
+                if !self.pending_inputs.contains(&(action, true)) {
+                    self.pending_inputs.push((action, true));
+                }
+            },
+        }
+        false
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.replay.record(self.frame, action, false);
+        if let GameState::Playing = self.state {
+            self.pending_inputs.push((self.grid.mirror_action(action), false));
+        }
+    }
+
+    // releases every held key and every press waiting for the update, for when their releases won't arrive, ie. the window lost focus mid-press
+    // the releases are recorded like any other so a replay lets go of the keys at the same update
+    pub fn release_held(&mut self) {
+        let mut held: Vec<Action> = self.held_keys.actions().collect();
+        for (action, pressed) in self.pending_inputs.iter() {
+            if *pressed && !held.contains(action) {
+                held.push(*action);
+            }
+        }
+        for action in held {
+            self.release(self.grid.mirror_action(action)); // back to the control that was pressed, release swaps it again
+        }
+    }
+
+    // applies the presses and releases since the last update in the order they happened
+    fn apply_pending_inputs(&mut self) {
+        for (action, pressed) in std::mem::take(&mut self.pending_inputs) {
+            if pressed {
+                self.apply_press(action);
+            } else {
+                self.apply_release(action);
+            }
+        }
+    }
+
+    // moves and rotations ignore os key repeats of a key already held, auto-repeat is left to das and rotate_repeat
+    fn apply_press(&mut self, action: Action) {
+        let first_press = self.held_keys.press(action);
+        match action {
+            Action::Drop => self.grid.finish_drop(),
+            Action::Undo => self.grid.undo(),
+            Action::Hold => self.grid.hold_piece(),
+            Action::Ghost => (), // only held, read by ghost_visible
+            _ if !first_press => (),
+            Action::Left | Action::Right => self.hold_shift(action.into()),
+            Action::Down => self.start_soft_drop(),
+            Action::RotateCW | Action::RotateCCW if self.config.rotation_preview => self.previewed_rotation = Some(action.into()),
+            _ => {
+                self.grid.player_move(action.into(), action.into());
+                self.hold_rotation(action.into());
+            },
+        }
+    }
+
+    fn apply_release(&mut self, action: Action) {
+        self.held_keys.release(action);
+        self.release_rotation(action.into());
+        let rot = Rotation::from(action);
+        if rot != Rotation::None && self.previewed_rotation == Some(rot) {
+            self.previewed_rotation = None;
+            self.grid.player_move(Direction::None, rot); // the same rotation the preview showed
+        }
+        match action {
+            Action::Left | Action::Right => self.release_shift(action.into()),
+            Action::Down => self.soft_drop_repeat = None,
+            _ => (),
+        }
+    }
+
+    // starts a new game with the mode and level picked in the menu, they're kept for the next time the menu is shown
+    fn start(&mut self, menu: &Menu) {
+        let config = Config {
+            mode: menu.mode,
+            start_level: menu.level,
+            ..self.config.clone()
+        };
+        let hooks = self.hooks.clone(); // set before the menu, for every game started from it
+        *self = Game::new(config);
+        self.hooks = hooks;
+        self.state = GameState::countdown();
+    }
+
+    // closes the quit overlay, going back to the menu, the countdown or gameplay it paused
+    fn resume(&mut self) {
+        if let GameState::ConfirmQuit(state) = std::mem::replace(&mut self.state, GameState::Playing) {
+            self.state = *state;
+        }
+    }
+
+    // holds the game before its menu or countdown until the frontend reports its assets with loaded
+    pub fn start_loading(&mut self) {
+        let state = std::mem::replace(&mut self.state, GameState::Playing);
+        self.state = GameState::Loading(Box::new(state));
+    }
+
+    // starts the game once the frontend's assets have loaded, or shows the error of one that couldn't be
+    pub fn loaded(&mut self, result: std::result::Result<(), Error>) {
+        if let GameState::Loading(state) = std::mem::replace(&mut self.state, GameState::Playing) {
+            self.state = match result {
+                Ok(()) => *state,
+                Err(err) => GameState::LoadFailed(state, err.to_string()),
+            };
+        }
+    }
+
+    // goes back to the countdown or gameplay that was paused
+    fn unpause(&mut self) {
+        if let GameState::Paused(state) = std::mem::replace(&mut self.state, GameState::Playing) {
+            self.state = *state;
+        }
+    }
+
+    // advances the game by the elapsed milliseconds, firing the hooks for what happened once it has
+    pub fn tick(&mut self, elapsed: f64) {
+        self.update(elapsed);
+        self.fire_hooks();
+    }
+
+    fn update(&mut self, elapsed: f64) {
+        self.frame += 1;
+        if let GameState::Paused(_) = self.state {
+            return // the timers, animations included, only advance here so they freeze where they are
+        }
+        if let Some(steps) = &mut self.steps {
+            if *steps == 0 {
+                return // frozen until the next step
+            }
+            *steps -= 1;
+        }
+        if let GameState::Countdown(frame_timer) = &mut self.state {
+            if let FrameState::Done = frame_timer.state(elapsed) {
+                self.state = GameState::Playing;
+            }
+            return // gravity and the timers start once playing
+        }
+        if let GameState::Loading(_) | GameState::LoadFailed(..) | GameState::Menu(_) | GameState::ConfirmQuit(_) | GameState::Victory | GameState::GameOver = self.state {
+            return // waiting for the assets or the menu, paused until the overlay is closed, or finished
+        }
+        // while rows clear the next piece waits at the spawn, ie. the are, its moves are kept for when it can be controlled
+        let spawning = self.grid.clearing();
+        if spawning {
+            self.buffer_pending_inputs();
+        } else {
+            self.drain_input_buffer();
+            self.apply_pending_inputs();
+        }
+        self.held_keys.update(elapsed);
+        self.play_time += elapsed;
+        self.piece_rate.update(elapsed, self.play_time, self.grid.spawned);
+
+        if !spawning {
+            self.timing.update(elapsed);
+        }
+        
+        self.grid.animate(elapsed);
+        self.grid.finish_clear(); // checks whether there are lines to clear
+
+        // held keys don't auto-repeat while stepping, the piece only moves when a key is pressed
+        let repeating = self.steps.is_none() && !spawning;
+        if let (Some((rot, repeat)), true) = (&mut self.held_rotation, repeating) {
+            for _ in 0..repeat.update(elapsed) {
+                self.grid.player_move(Direction::None, *rot);
+            }
+        }
+
+        // an arr of 0 slides the piece until it hits something
+        if let (Some((dir, repeat)), true) = (&mut self.held_shift, repeating) {
+            let dir = *dir;
+            for _ in 0..repeat.update(elapsed) {
+                if self.grid.shifted(&self.grid.curr_piece, dir).is_none() {
+                    break
+                }
+                self.grid.player_move(dir, Rotation::None);
+            }
+        }
+
+        if let Some(rise) = &mut self.rise {
+            if rise.update(elapsed) {
+                if self.grid.rise_garbage(1) {
+                    self.game_over();
+                    return
+                }
+                self.grid.score.add_rise();
+            }
+        }
+        if self.incoming_garbage > 0 && self.grid.rise_garbage(std::mem::replace(&mut self.incoming_garbage, 0)) {
+            self.game_over();
+            return
+        }
+
+        // a stack that stays at the kill screen height starts the kill screen, one that spikes and drops back doesn't
+        if let (Some(kill_timer), false) = (&mut self.kill_timer, self.kill_screen) {
+            if kill_timer.update(elapsed, self.grid.blocks.stack_height() >= self.config.kill_height) {
+                self.kill_screen = true;
+                if let Some(rise) = &mut self.rise {
+                    rise.hurry();
+                }
+            }
+        }
+
+        // at high levels the piece can fall several rows per update, it stops once it lands and locks after resting
+        if self.won() {
+            self.state = GameState::Victory;
+            return
+        }
+        if spawning {
+            return // the piece neither falls nor locks until it's in play
+        }
+        if std::mem::replace(&mut self.grid.reset_fall, false) {
+            self.timing.reset_fall();
+        }
+        self.timing.set_fall_rate(self.capped_level());
+        let soft_dropping = self.held_keys.is_held(Action::Down);
+        let row = self.grid.curr_piece.row();
+        if soft_dropping {
+            match (self.handling.soft_drop, &mut self.soft_drop_repeat) {
+                (SoftDrop::Factor(factor), _) => self.timing.soft_drop(factor),
+                (SoftDrop::Rate(_), Some(repeat)) => for _ in 0..repeat.update(elapsed) {
+                    if self.grid.resting() {
+                        break
+                    }
+                    self.grid.move_if(Direction::Down, Rotation::None);
+                },
+                (SoftDrop::Rate(_), None) => (),
+                (SoftDrop::Instant, _) => self.grid.sonic_drop(),
+            }
+        }
+        for _ in 0..self.timing.fall() {
+            if self.grid.resting() {
+                break
+            }
+            self.grid.move_if(Direction::Down, Rotation::None);
+        }
+        if self.kill_screen {
+            self.grid.sonic_drop(); // 20G, the piece is on the stack as soon as it spawns and only the lock delay is left
+        }
+        if soft_dropping {
+            self.score_soft_drop(row); // the piece can't have locked yet, resting pieces lock below
+        }
+        // moving a resting piece restarts its lock delay, until the cap is passed and it locks as soon as it rests
+        if std::mem::replace(&mut self.grid.reset_lock, false) && self.grid.lock_resets <= self.handling.lock_resets {
+            self.timing.reset_rest();
+        }
+        if !self.grid.resting() {
+            self.timing.reset_rest();
+        } else if self.grid.lock_resets > self.handling.lock_resets || self.timing.rest(elapsed, self.handling.lock_delay) {
+            self.grid.move_if(Direction::Down, Rotation::None); // locks the piece
+        }
+        if self.grid.top_out.is_some() || self.grid.sequence_ended {
+            self.game_over();
+        }
+    }
+}
+
+// 64 bit fnv-1a, a hash that doesn't change between runs unlike the std hasher's random keys
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// the pieces of the config's variant, dealt from by its grid
+pub fn piece_set(config: &Config) -> Vec<Tetrinome> {
+    match config.variant {
+        Variant::Custom => config.custom_pieces.clone(),
+        variant => PieceKind::of_variant(variant).into_iter().map(|kind| Tetrinome::from_piece(kind).with_o_rotation(config.o_rotation) ).collect(),
+    }
+}
+
+// the standard pieces as the default config deals them, built on first use
+fn standard_pieces() -> &'static [Tetrinome] {
+    static STANDARD_PIECES: OnceLock<Vec<Tetrinome>> = OnceLock::new();
+    STANDARD_PIECES.get_or_init(|| piece_set(&Config{variant: Variant::Standard, o_rotation: false, ..Config::default()}) )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{playing, playing_with, fill_row, piece_columns};
+
+    #[test]
+    fn random_pieces_cover_every_kind() {
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[rand::random::<Tetrinome>().kind as usize] = true;
+        }
+        assert_eq!(seen, [true; 7]);
+    }
+
+    #[test]
+    fn restart_resets_the_stats_and_clear_board_keeps_them() {
+        let mut game = playing_with(Config{debug: true, ..Config::default()});
+        let floor = game.grid.height() - 1;
+        fill_row(&mut game, floor, &[0]);
+        let kind = game.grid.curr_piece.kind;
+        assert_eq!(game.grid.stats()[kind as usize], 1);
+
+        game.press(Action::ClearBoard);
+        assert!(game.grid.blocks.is_empty());
+        assert_eq!(game.grid.stats()[kind as usize], 1);
+
+        game.press(Action::Restart);
+        assert_eq!(game.grid.stats().iter().sum::<u32>(), 1); // only the piece the board restarts with
+        assert_eq!(game.grid.spawned(), 1);
+    }
+
+    #[test]
+    fn a_bomb_clears_its_column_with_the_line() {
+        let mut game = playing(&[PieceKind::I]);
+        let floor = game.grid.height() - 1;
+        let open = piece_columns(&game);
+        fill_row(&mut game, floor, &open);
+        game.grid.blocks.data.iter_mut().flatten().next_back().unwrap().bone.special = Some(Special::Bomb); // the right most column
+        let right = game.grid.width() - 1;
+        fill_row(&mut game, floor - 1, &(1..right).collect::<Vec<i16>>()); // a block above either end of the row
+        game.press(Action::Drop);
+        game.tick(1.0);
+
+        let filled = |x, y| game.grid.blocks.get_block(&Coord{x, y}).unwrap().is_some();
+        assert!(filled(0, floor)); // dropped into the cleared row
+        assert!(!filled(right, floor)); // cleared with the bomb's column
+        assert_eq!(game.grid.blocks.data.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn presses_between_updates_move_the_piece_once() {
+        let mut game = playing(&[PieceKind::T, PieceKind::T]);
+        let columns = piece_columns(&game);
+        game.press(Action::Left);
+        game.press(Action::Left); // an os key repeat
+        assert_eq!(piece_columns(&game), columns); // nothing moves until the update
+        game.tick(1.0);
+        assert_eq!(piece_columns(&game), columns.iter().map(|x| x - 1 ).collect::<Vec<i16>>());
+
+        game.release(Action::Left);
+        game.press(Action::Left);
+        game.tick(1.0);
+        assert_eq!(piece_columns(&game), columns.iter().map(|x| x - 2 ).collect::<Vec<i16>>());
+    }
+
+    #[test]
+    fn a_rise_under_a_block_at_the_top_tops_out() {
+        use timing::RISE_INTERVAL;
+        let mut game = playing_with(Config{mode: Mode::Survival, ..Config::default()});
+        let coord = Coord{x: 0, y: game.grid.top()};
+        game.grid.blocks.set_block(&coord, Bone::new(Color::Gray, coord, PieceKind::Garbage)).unwrap();
+        game.tick(RISE_INTERVAL - 1.0);
+        assert!(matches!(game.state, GameState::Playing));
+        game.tick(1.0);
+        assert!(matches!(game.state, GameState::GameOver));
+    }
+
+    #[test]
+    fn the_top_and_bottom_rows_are_on_the_grid_and_past_them_is_off() {
+        let size = Size{width: 4, height: 3, hidden_rows: 1};
+        let mut blocks = Blocks::new(size, LineGravity::Naive, ClearWave::LeftToRight, 0.0, 0.0);
+        assert_eq!(blocks.row_range(&-1), Some(0..4)); // the hidden row
+        assert_eq!(blocks.row_range(&2), Some(12..16)); // the floor
+        assert_eq!(blocks.row_range(&-2), None);
+        assert_eq!(blocks.row_range(&3), None);
+        assert_eq!(blocks.index(&Coord{x: 0, y: -1}), Some(0));
+        assert_eq!(blocks.index(&Coord{x: 3, y: 2}), Some(15));
+        assert_eq!(blocks.index(&Coord{x: 4, y: 0}), None); // not the start of the next row
+
+        for y in [-1, 2] {
+            for x in 0..4 {
+                blocks.set_block(&Coord{x, y}, Bone::default()).unwrap();
+            }
+            assert!(blocks.row_full(&y));
+            blocks.clear_row(&y);
+            assert!(blocks.is_empty());
+        }
+        assert!(!blocks.row_full(&3));
+        blocks.clear_row(&3); // off the grid, nothing to clear
+    }
+
+    #[test]
+    fn blocks_off_the_grid_are_errors() {
+        let size = Size{width: 4, height: 3, hidden_rows: 0};
+        let mut blocks = Blocks::new(size, LineGravity::Naive, ClearWave::LeftToRight, 0.0, 0.0);
+        for coord in [Coord{x: 0, y: 3}, Coord{x: 4, y: 0}, Coord{x: -1, y: 0}, Coord{x: 0, y: -1}, Coord{x: 3, y: i16::MAX}] {
+            assert!(blocks.set_block(&coord, Bone::default()).is_err());
+            assert!(blocks.get_block(&coord).is_err());
+        }
+        assert!(blocks.is_empty());
+        assert!(blocks.get_block(&Coord{x: 3, y: 2}).unwrap().is_none());
+    }
+
+    #[test]
+    fn naive_rotation_shifts_off_the_wall_by_half_the_width() {
+        let mut game = playing_with(Config{rotation_system: RotationSystem::Naive, ..Config::default()});
+        let rotate = |game: &mut Game| {
+            game.press(Action::RotateCW);
+            game.tick(1.0);
+            game.release(Action::RotateCW);
+        };
+        let mut upright = Tetrinome::from_piece(PieceKind::I);
+        upright.rotate(&Rotation::CW);
+        upright.trans_change(&Coord{x: 4, y: 5});
+        game.grid.curr_piece = upright;
+        rotate(&mut game);
+        assert_eq!(game.grid.curr_piece.sorted_cells(), [(3, 6), (4, 6), (5, 6), (6, 6)]); // turned in place
+
+        rotate(&mut game);
+        game.grid.curr_piece.trans_change(&Coord{x: -5, y: 0}); // upright against the left wall
+        let mut turned = game.grid.curr_piece.clone();
+        turned.rotate(&Rotation::CW);
+        assert_eq!(turned.sorted_cells()[0].0, -1);
+        turned.trans_change(&Coord{x: 2, y: 0}); // half the width of the I
+        rotate(&mut game);
+        assert_eq!(game.grid.curr_piece.sorted_cells(), turned.sorted_cells());
+        assert_eq!(game.grid.curr_piece.sorted_cells(), [(1, 6), (2, 6), (3, 6), (4, 6)]);
+    }
+
+    #[test]
+    fn flat_deals_are_the_spawn_layouts() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let deal = Deal{pieces: standard_pieces(), orientation: SpawnOrientation::Flat};
+        for _ in 0..50 {
+            let piece = deal.sample(&mut rng);
+            assert_eq!(piece.sorted_cells(), Tetrinome::from_piece(piece.kind).sorted_cells());
+            assert_eq!(piece.orientation, Orientation::Spawn);
+        }
+    }
+
+    #[test]
+    fn pieces_spawn_flat_without_the_random_rotation() {
+        let mut game = playing_with(Config{spawn_orientation: SpawnOrientation::Flat, seed: 3, ..Config::default()});
+        let shape = |piece: &Tetrinome| {
+            let cells = piece.sorted_cells();
+            let (left, top) = (cells.iter().map(|cell| cell.0 ).min().unwrap(), cells.iter().map(|cell| cell.1 ).min().unwrap());
+            cells.iter().map(|(x, y)| (x - left, y - top) ).collect::<Vec<_>>()
+        };
+        for _ in 0..20 {
+            let piece = game.grid.curr_piece.clone();
+            assert_eq!(shape(&piece), shape(&Tetrinome::from_piece(piece.kind)));
+            game.press(Action::Drop);
+            game.tick(1.0);
+            game.release(Action::Drop);
+            game.grid.clear_board(); // so the stack never tops out
+        }
+    }
+
+    #[test]
+    fn pentominoes_rotate_around_their_pivot() {
+        let mut piece = Tetrinome::from_piece(PieceKind::L5);
+        piece.rotate(&Rotation::CW);
+        assert_eq!(piece.sorted_cells(), [(2, -1), (2, 0), (2, 1), (2, 2), (3, 2)]);
+        for kind in PieceKind::of_variant(Variant::Pentomino) {
+            let spawn = Tetrinome::from_piece(kind);
+            let mut piece = spawn.clone();
+            for _ in 0..4 {
+                piece.rotate(&Rotation::CW);
+                assert_eq!(piece.bones.len(), 5);
+            }
+            assert_eq!(piece.sorted_cells(), spawn.sorted_cells(), "{:?}", kind);
+            piece.rotate(&Rotation::CW);
+            piece.rotate(&Rotation::CCW);
+            assert_eq!(piece.sorted_cells(), spawn.sorted_cells(), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn big_pieces_rest_over_a_one_cell_gap() {
+        let mut game = playing_with(Config{big: true, sequence: vec![PieceKind::O, PieceKind::O], ..Config::default()});
+        assert_eq!((game.grid.width(), game.grid.height()), (Grid::WIDTH / 2, Grid::HEIGHT / 2)); // each cell is drawn 2x2
+        let floor = game.grid.height() - 1;
+        fill_row(&mut game, floor, &[0]);
+        let left = piece_columns(&game).into_iter().min().unwrap();
+        game.grid.curr_piece.trans_change(&Coord{x: -left, y: 0});
+        game.press(Action::Drop);
+        game.tick(1.0);
+        assert!(game.grid.blocks.get_block(&Coord{x: 0, y: floor}).unwrap().is_none()); // the gap is left open
+        for x in 0..2 {
+            assert!(game.grid.blocks.get_block(&Coord{x, y: floor - 1}).unwrap().is_some()); // resting on the row
+        }
+    }
+
+    // a board from rows of to_ascii text, full rows clear at once
+    fn board(rows: &[&str], gravity: LineGravity) -> Blocks {
+        let size = Size{width: rows[0].len() as i16, height: rows.len() as i16, hidden_rows: 0};
+        let mut blocks = Blocks::from_ascii(&rows.join("\n"), size).unwrap();
+        blocks.gravity = gravity;
+        blocks.clear_duration = 0.0;
+        blocks
+    }
+
+    #[test]
+    fn sticky_groups_fall_whole() {
+        let mut blocks = board(&["-xx-", "-x--", "xxxx", "----", "---x"], LineGravity::Sticky);
+        blocks.start_clear(&2, 1);
+        assert!(blocks.finish_clear().is_empty());
+        assert_eq!(blocks.to_ascii(), ["----", "----", "----", "-xx-", "-x-x"].join("\n")); // the overhang falls with the block under it
+
+        let mut blocks = board(&["-xx-", "-x--", "xxxx", "----", "---x"], LineGravity::Naive);
+        blocks.start_clear(&2, 1);
+        blocks.finish_clear();
+        assert_eq!(blocks.to_ascii(), ["----", "-xx-", "-x--", "----", "---x"].join("\n"));
+    }
+
+    #[test]
+    fn cascades_clear_the_rows_the_falling_blocks_fill() {
+        let mut blocks = board(&["x---", "----", "xxxx", "-xxx"], LineGravity::Cascade);
+        blocks.start_clear(&2, 1);
+        assert_eq!(blocks.finish_clear(), [3]);
+        assert!(blocks.finish_clear().is_empty());
+        assert!(blocks.is_empty());
+    }
+
+    #[test]
+    fn spinning_on_the_floor_locks_past_the_reset_cap() {
+        let handling = Handling{lock_resets: 3, ..Handling::default()};
+        let mut game = playing_with(Config{handling, sequence: vec![PieceKind::T; 3], ..Config::default()});
+        for _ in 0..4 {
+            game.grid.sonic_drop(); // back on the floor after the kicks lift it
+            let cells = game.grid.curr_piece.sorted_cells();
+            game.press(Action::RotateCW);
+            game.tick(1.0);
+            game.release(Action::RotateCW);
+            assert_ne!(game.grid.curr_piece.sorted_cells(), cells);
+            assert_eq!(game.grid.spawned(), 1);
+        }
+        game.grid.sonic_drop();
+        game.tick(1.0);
+        assert_eq!(game.grid.spawned(), 2); // locked once it rested after the spin past the cap, long before the lock delay
+    }
+
+    #[test]
+    fn the_cached_shadow_follows_the_piece() {
+        let mut game = playing(&[PieceKind::L, PieceKind::L]);
+        let floor = game.grid.height() - 1;
+        fill_row(&mut game, floor, &[0, 1, 2]);
+        for action in [Action::Left, Action::Left, Action::RotateCW, Action::Right, Action::Down, Action::RotateCCW] {
+            game.grid.shadow(); // cached before the move
+            game.press(action);
+            game.tick(1.0);
+            game.release(action);
+            let mut fresh = game.grid.curr_piece.clone();
+            fresh.trans_change(&Coord{x: 0, y: game.grid.shadow_distance(&fresh) as i16});
+            assert_eq!(game.grid.shadow().sorted_cells(), fresh.sorted_cells(), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn emptying_the_board_is_a_perfect_clear() {
+        let all_clear = |game: &Game| game.grid.popups().iter().any(|popup| popup.lines() == ["ALL CLEAR", "+2000"] );
+        for stack_rows in [4, 5] {
+            let mut game = playing(&[PieceKind::I, PieceKind::I]);
+            game.tick(1.0);
+            assert!(!all_clear(&game)); // the board starts empty
+            let mut upright = Tetrinome::from_piece(PieceKind::I);
+            upright.rotate(&Rotation::CW);
+            game.grid.curr_piece = upright;
+            let floor = game.grid.height() - 1;
+            for y in floor + 1 - stack_rows..=floor {
+                fill_row(&mut game, y, &[1]);
+            }
+            game.press(Action::Drop);
+            game.tick(1.0);
+            assert_eq!(game.grid.score.lines, 4);
+            assert_eq!(all_clear(&game), stack_rows == 4); // the fifth row is left on the board
+        }
+    }
+
+    #[test]
+    fn the_board_starts_with_the_garbage_rows() {
+        let config = Config{garbage_rows: 6, seed: 5, ..Config::default()};
+        let mut game = playing_with(config.clone());
+        let (width, height) = (game.grid.width(), game.grid.height());
+        for y in 0..height {
+            let filled = (0..width).filter(|x| game.grid.blocks.filled(&Coord{x: *x, y}) ).count() as i16;
+            assert_eq!(filled, if y >= height - 6 { width - 1 } else { 0 }, "row {}", y);
+        }
+        assert_eq!(Grid::new(&config).blocks.to_ascii(), game.grid.blocks.to_ascii()); // seeded
+        game.tick(1.0);
+        assert!(matches!(game.state, GameState::Playing));
+    }
+
+    #[test]
+    fn rows_clear_in_the_order_their_animations_finish() {
+        let mut blocks = board(&["x---", "xxxx", "xxxx", "-x--"], LineGravity::Naive);
+        blocks.clear_duration = 300.0;
+        blocks.clear_scaling = 1.0;
+        blocks.start_clear(&2, 3); // three times as long as the row above
+        blocks.start_clear(&1, 1);
+        while !blocks.row_ready(&1) {
+            blocks.animate(10.0);
+        }
+        assert!(!blocks.row_ready(&2));
+        blocks.finish_clear();
+        assert_eq!(blocks.rows_full, [2]);
+        assert!(blocks.filled(&Coord{x: 0, y: 1}) && blocks.row_full(&2)); // the block above dropped onto the row still clearing
+
+        while !blocks.row_ready(&2) {
+            blocks.animate(10.0);
+        }
+        blocks.finish_clear();
+        assert!(blocks.rows_full.is_empty());
+        assert_eq!(blocks.to_ascii(), ["----", "----", "x---", "-x--"].join("\n"));
+    }
+
+    #[test]
+    fn releasing_the_newer_direction_falls_back_to_the_held_one() {
+        let mut game = playing(&[PieceKind::T, PieceKind::T]);
+        let left = |game: &Game| piece_columns(game).into_iter().min().unwrap();
+        let start = left(&game);
+        game.press(Action::Left);
+        game.tick(1.0);
+        assert_eq!(left(&game), start - 1);
+        game.press(Action::Right);
+        game.tick(1.0);
+        assert_eq!(left(&game), start); // the newest press wins
+        game.tick(game.handling.das - 50.0);
+        game.release(Action::Right);
+        game.tick(1.0);
+        assert_eq!(left(&game), start); // left charges its das again from the release
+        game.tick(game.handling.das);
+        assert!(left(&game) < start);
+    }
+
+    #[test]
+    fn the_clear_wave_staggers_the_starts_and_ends_together() {
+        let frame = millis_per_update() * 3.0;
+        for (wave, starts) in [(ClearWave::LeftToRight, [1, 2, 3, 4, 5]), (ClearWave::CenterOut, [5, 3, 1, 3, 5])] {
+            let mut blocks = board(&["xxxxx"], LineGravity::Naive);
+            blocks.clear_wave = wave;
+            blocks.clear_duration = frame * 12.0; // the wave is spread over the first 4 frames
+            blocks.start_clear(&0, 1);
+            let timer = |blocks: &Blocks, x: usize| blocks.data[x].as_ref().unwrap().frame_timer.clone().unwrap();
+            let mut started = [0; 5];
+            for update in 1..=36 { // 3 updates a frame
+                blocks.animate(millis_per_update());
+                for (x, start) in started.iter_mut().enumerate() {
+                    if *start == 0 && timer(&blocks, x).frame() > 0 {
+                        *start = (update + 2) / 3;
+                    }
+                    assert_eq!(timer(&blocks, x).progress() == 1.0, update == 36); // every block finishes with the row
+                }
+            }
+            assert_eq!(started, starts);
+        }
+    }
+
+    #[test]
+    fn a_pause_freezes_the_clear_animation() {
+        let mut game = playing_with(Config{sequence: vec![PieceKind::I; 2], ..Config::default()});
+        game.grid.blocks.clear_duration = CLEAR_DURATION;
+        let floor = game.grid.height() - 1;
+        let open = piece_columns(&game);
+        fill_row(&mut game, floor, &open);
+        game.press(Action::Drop);
+        game.tick(1.0);
+        game.release(Action::Drop);
+        let frame = |game: &Game| game.grid.blocks.block(&Coord{x: 0, y: floor}).unwrap().frame_timer.as_ref().unwrap().frame();
+        for _ in 0..(CLEAR_DURATION / 2.0 / millis_per_update()) as usize {
+            game.tick(millis_per_update());
+        }
+        let paused_at = frame(&game);
+        assert!(paused_at > 0);
+
+        game.press(Action::Pause);
+        for _ in 0..10 {
+            game.tick(CLEAR_DURATION);
+        }
+        assert_eq!(frame(&game), paused_at);
+        game.press(Action::Pause);
+        game.tick(1.0);
+        assert_eq!(frame(&game), paused_at); // picks up partway through the frame it stopped on
+        assert_eq!(game.grid.blocks.rows_full, [floor]);
+    }
+
+    #[test]
+    fn a_hard_drop_lands_before_the_gravity_of_its_update() {
+        let play = || {
+            let mut game = playing_with(Config{seed: 9, ..Config::default()});
+            let landing = game.grid.shadow().sorted_cells();
+            game.press(Action::Drop);
+            game.tick(game.gravity() * 3.0); // rows of gravity are due in the same update
+            (game, landing)
+        };
+        let (game, landing) = play();
+        let mut locked: Vec<(i16, i16)> = game.grid.blocks.data.iter().flatten().map(|block| (block.bone.coord.x, block.bone.coord.y) ).collect();
+        locked.sort();
+        assert_eq!(locked, landing);
+        assert_eq!(game.grid.spawned(), 2);
+        assert_eq!(play().0.grid.state_hash(), game.grid.state_hash());
+    }
+
+    #[test]
+    fn pieces_in_the_hidden_rows_move_into_view() {
+        let mut game = playing_with(Config{hidden_rows: 2, sequence: vec![PieceKind::I; 2], ..Config::default()});
+        assert_eq!(game.grid.top(), -2);
+        assert_eq!(game.grid.blocks.data.len(), (game.grid.width() * (game.grid.height() + 2)) as usize);
+        let mut upright = Tetrinome::from_piece(PieceKind::I);
+        upright.rotate(&Rotation::CW);
+        upright.trans_change(&Coord{x: 4, y: -2}); // the top two bones are above the visible rows
+        game.grid.curr_piece = upright;
+        let rows = |game: &Game| game.grid.curr_piece.bones.iter().map(|bone| bone.coord.y ).collect::<Vec<i16>>();
+        assert_eq!(rows(&game), [-2, -1, 0, 1]);
+        game.tick(game.gravity());
+        assert_eq!(rows(&game), [-1, 0, 1, 2]); // falls through the hidden rows like any other
+        for _ in 0..2 {
+            game.press(Action::Down);
+            game.tick(1.0);
+            game.release(Action::Down);
+        }
+        assert!(rows(&game).iter().all(|y| *y >= 0 ));
+    }
+
+    #[test]
+    fn boards_round_trip_through_ascii() {
+        let rows = ["------", "--g---", "-yyrr-", "xxxx-x"];
+        let size = Size{width: 6, height: 3, hidden_rows: 1};
+        let blocks = Blocks::from_ascii(&rows.join("\n"), size).unwrap();
+        assert_eq!(blocks.to_ascii(), rows.join("\n"));
+        assert!(blocks.filled(&Coord{x: 2, y: 0}) && !blocks.filled(&Coord{x: 4, y: 2}));
+        assert_eq!(blocks.block(&Coord{x: 1, y: 1}).unwrap().bone.color, Color::Yellow);
+
+        assert!(Blocks::from_ascii(&rows[1..].join("\n"), size).is_err()); // a row short
+        assert!(Blocks::from_ascii(&rows.join("\n").replace('g', "q"), size).is_err());
+    }
+
+    #[test]
+    fn keys_held_as_the_focus_is_lost_stop_repeating() {
+        let mut game = playing(&[PieceKind::T, PieceKind::T]);
+        let start = piece_columns(&game);
+        game.press(Action::Left);
+        game.tick(1.0);
+        game.release_held(); // the release goes to another window
+        game.tick(game.handling.das * 3.0);
+        assert_eq!(game.held_keys().actions().count(), 0);
+        assert_eq!(piece_columns(&game), start.iter().map(|x| x - 1 ).collect::<Vec<i16>>()); // only the move from the press
+    }
+
+    #[test]
+    fn a_seed_prints_the_pieces_it_deals() {
+        let config = Config{seed: 11, ..Config::default()};
+        let letters = Grid::new(&config).upcoming_pieces(12);
+        assert_eq!(letters.len(), 12);
+        assert_eq!(Grid::new(&config).upcoming_pieces(12), letters);
+
+        let dealt = |config: Config| {
+            let mut game = playing_with(config);
+            let mut dealt = String::new();
+            while dealt.len() < 12 && matches!(game.state, GameState::Playing) {
+                dealt.push(game.grid.curr_piece.kind.letter());
+                game.press(Action::Drop);
+                game.tick(1.0);
+                game.release(Action::Drop);
+                game.grid.clear_board();
+            }
+            (dealt, game)
+        };
+        assert_eq!(dealt(config.clone()).0, letters);
+        let sequence = PieceKind::sequence(&letters.to_lowercase(), &config.piece_kinds()).unwrap();
+        let (scripted, game) = dealt(Config{sequence, seed: 12, ..config});
+        assert_eq!(scripted, letters);
+        assert!(matches!(game.state, GameState::GameOver)); // out of pieces
+        assert!(PieceKind::sequence("IJQ", &PieceKind::of_variant(Variant::Standard)).is_err());
+    }
+
+    #[test]
+    fn soft_drop_points_only_change_the_score() {
+        for points in [1, 0] {
+            let mut game = playing_with(Config{soft_drop_points: points, sequence: vec![PieceKind::T; 2], ..Config::default()});
+            let row = game.grid.curr_piece.row();
+            game.press(Action::Down);
+            game.tick(1.0);
+            game.tick(game.gravity() / 4.0); // soft drop falls 20 times faster
+            let fallen = game.grid.curr_piece.row() - row;
+            assert!(fallen > 1);
+            assert_eq!(game.grid.score.points, fallen as u32 * points);
+        }
+    }
+
+    #[test]
+    fn identical_games_hash_the_same_every_update() {
+        let inputs = [Action::Left, Action::RotateCW, Action::Drop, Action::Right, Action::Hold, Action::Down, Action::Drop];
+        let play = |last: Action| {
+            let mut game = playing_with(Config{seed: 21, ..Config::default()});
+            let mut hashes = Vec::new();
+            for action in inputs.iter().copied().chain(std::iter::once(last)) {
+                game.press(action);
+                game.tick(millis_per_update());
+                game.release(action);
+                game.tick(millis_per_update());
+                hashes.push(game.grid.state_hash());
+            }
+            hashes
+        };
+        let hashes = play(Action::Drop);
+        assert_eq!(play(Action::Drop), hashes);
+        let diverged = play(Action::Left);
+        assert_eq!(diverged[..inputs.len()], hashes[..inputs.len()]);
+        assert_ne!(diverged.last(), hashes.last());
+    }
+
+    #[test]
+    fn soft_drop_repeats_at_its_rate_and_release_goes_back_to_gravity() {
+        let handling = Handling{soft_drop: SoftDrop::Rate(40.0), ..Handling::default()}; // a row every 25ms
+        let mut game = playing_with(Config{handling, sequence: vec![PieceKind::T; 2], ..Config::default()});
+        let row = game.grid.curr_piece.row();
+        game.press(Action::Down);
+        game.tick(1.0);
+        assert_eq!(game.grid.curr_piece.row(), row + 1);
+        game.tick(100.0);
+        assert_eq!(game.grid.curr_piece.row(), row + 5);
+        game.release(Action::Down);
+        game.tick(100.0);
+        assert_eq!(game.grid.curr_piece.row(), row + 5); // a row a second at level 1
+    }
+
+    #[test]
+    fn an_instant_soft_drop_lands_the_piece_without_locking_it() {
+        let handling = Handling{soft_drop: SoftDrop::Instant, ..Handling::default()};
+        let mut game = playing_with(Config{handling, sequence: vec![PieceKind::T; 2], ..Config::default()});
+        let landing = game.grid.shadow().row();
+        game.press(Action::Down);
+        game.tick(1.0);
+        assert_eq!(game.grid.curr_piece.row(), landing);
+        assert!(game.grid.blocks.data.iter().all(Option::is_none));
+        game.release(Action::Down);
+        game.press(Action::Left);
+        game.tick(1.0);
+        assert!(game.grid.blocks.data.iter().all(Option::is_none));
+        assert_eq!(game.grid.curr_piece.row(), landing);
+    }
+
+    #[test]
+    fn the_hold_key_does_nothing_with_hold_off() {
+        for hold in [true, false] {
+            let mut game = playing_with(Config{hold, sequence: vec![PieceKind::T, PieceKind::S, PieceKind::Z], ..Config::default()});
+            game.press(Action::Hold);
+            game.tick(1.0);
+            let kind = if hold { PieceKind::S } else { PieceKind::T };
+            assert_eq!(game.grid.curr_piece.kind, kind);
+            assert_eq!(game.grid.held_pieces().count(), hold as usize);
+        }
+    }
+
+    #[test]
+    fn moving_sideways_puts_off_the_fall_with_fall_reset() {
+        for fall_reset in [false, true] {
+            let mut game = playing_with(Config{fall_reset, sequence: vec![PieceKind::T; 2], ..Config::default()});
+            let row = game.grid.curr_piece.row();
+            for action in [Action::Left, Action::Right, Action::Left] {
+                game.tick(game.gravity() * 0.6);
+                game.press(action);
+                game.tick(1.0);
+                game.release(action);
+            }
+            let fallen = game.grid.curr_piece.row() - row;
+            assert_eq!(fallen, if fall_reset { 0 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn garbage_gaps_line_up_without_mess_and_scatter_with_it() {
+        for mess in [0.0, 1.0] {
+            let mut blocks = board(&["----"; 8], LineGravity::Naive);
+            blocks.push_garbage(8, mess, &mut StdRng::seed_from_u64(3));
+            let gaps: Vec<usize> = blocks.to_ascii().lines().map(|row| row.find('-').unwrap() ).collect();
+            assert_eq!(gaps.iter().all(|gap| *gap == gaps[0]), mess == 0.0, "{:?}", gaps);
+        }
+    }
+
+    #[test]
+    fn a_deep_hold_brings_back_the_piece_held_longest() {
+        use PieceKind::*;
+        let mut game = playing_with(Config{hold_depth: 3, sequence: vec![T, S, Z, O, I, L, J, T], ..Config::default()});
+        let tap = |game: &mut Game, action| {
+            game.press(action);
+            game.tick(1.0);
+            game.release(action);
+        };
+        for _ in 0..3 {
+            tap(&mut game, Action::Hold);
+            tap(&mut game, Action::Drop);
+        }
+        let held: Vec<PieceKind> = game.grid.held_pieces().map(Tetrinome::kind).collect();
+        assert_eq!(held, [T, Z, I]);
+        assert_eq!(game.grid.curr_piece.kind, J);
+        tap(&mut game, Action::Hold);
+        let held: Vec<PieceKind> = game.grid.held_pieces().map(Tetrinome::kind).collect();
+        assert_eq!(held, [Z, I, J]);
+        assert_eq!(game.grid.curr_piece.kind, T);
+        tap(&mut game, Action::Hold); // once per piece
+        assert_eq!(game.grid.curr_piece.kind, T);
+    }
+
+    #[test]
+    fn pieces_without_a_pivot_rotate_around_their_center() {
+        let mut piece = Tetrinome::from_layout("xxx".to_string(), Color::Gray, PieceKind::Garbage);
+        piece.rotate(&Rotation::CW);
+        assert_eq!(piece.sorted_cells(), [(1, -1), (1, 0), (1, 1)]);
+        piece.rotate(&Rotation::CCW);
+        assert_eq!(piece.sorted_cells(), [(0, 0), (1, 0), (2, 0)]);
+
+        for o_rotation in [false, true] {
+            let mut o = Tetrinome::from_piece(PieceKind::O).with_o_rotation(o_rotation);
+            let cells = o.sorted_cells();
+            o.rotate(&Rotation::CW);
+            assert_eq!(o.sorted_cells() == cells, !o_rotation);
+        }
+    }
+
+    #[test]
+    fn only_a_stack_that_stays_high_starts_the_kill_screen() {
+        let mut game = playing_with(Config{kill_height: 4, kill_delay: 1000.0, sequence: vec![PieceKind::T; 2], ..Config::default()});
+        let floor = game.grid.height() - 1;
+        let stack = |game: &mut Game| for y in floor - 3..=floor {
+            fill_row(game, y, &[0]);
+        };
+        stack(&mut game);
+        game.tick(600.0);
+        game.grid.blocks.clear(); // the spike drops back
+        game.tick(1.0);
+        stack(&mut game);
+        game.tick(600.0);
+        assert!(!game.kill_screen());
+        game.tick(500.0);
+        assert!(game.kill_screen());
+        assert_eq!(game.gravity(), 0.0);
+    }
+
+    #[test]
+    fn an_on_demand_ghost_shows_only_while_its_key_is_held() {
+        for (ghost, shown) in [(Ghost::Off, [false; 3]), (Ghost::Always, [true; 3]), (Ghost::OnDemand, [false, true, false])] {
+            let mut game = playing_with(Config{ghost, sequence: vec![PieceKind::T; 2], ..Config::default()});
+            let mut frames = Vec::new();
+            game.tick(1.0);
+            frames.push(game.ghost_visible());
+            game.press(Action::Ghost);
+            game.tick(1.0);
+            frames.push(game.ghost_visible());
+            game.release(Action::Ghost);
+            game.tick(1.0);
+            frames.push(game.ghost_visible());
+            assert_eq!(frames, shown, "{:?}", ghost);
+        }
+    }
+
+    #[test]
+    fn a_tetris_clears_for_longer_than_a_single_with_clear_scaling() {
+        let clear_time = |n_rows: usize, clear_scaling: f64| {
+            let mut blocks = board(&vec!["xxxx"; n_rows], LineGravity::Naive);
+            blocks.clear_duration = 300.0;
+            blocks.clear_scaling = clear_scaling;
+            let rows: Vec<i16> = (0..n_rows as i16).collect();
+            for row in rows.iter() {
+                blocks.start_clear(row, n_rows);
+            }
+            let mut elapsed = 0.0;
+            while !rows.iter().all(|row| blocks.row_ready(row) ) {
+                assert!(!rows.iter().any(|row| blocks.row_ready(row) ), "the rows of a clear finish together");
+                blocks.animate(10.0);
+                elapsed += 10.0;
+            }
+            blocks.finish_clear();
+            assert!(blocks.is_empty());
+            elapsed
+        };
+        assert_eq!(clear_time(4, 0.0), clear_time(1, 0.0));
+        let (single, tetris) = (clear_time(1, 0.5), clear_time(4, 0.5));
+        assert!(tetris > single * 2.0, "{} {}", tetris, single); // 2.5 times as long, less the rounding to whole frames
+    }
+
+    #[test]
+    fn a_mirrored_board_plays_the_controls_swapped() {
+        use PieceKind::*;
+        let play = |mirror: bool, actions: &[Action]| {
+            let mut game = playing_with(Config{mirror, seed: 4, sequence: vec![T, S, Z, L], ..Config::default()});
+            for action in actions {
+                game.press(*action);
+                game.tick(1.0);
+                game.release(*action);
+            }
+            game
+        };
+        let mirrored = play(true, &[Action::Left, Action::RotateCW, Action::Left, Action::Drop]);
+        let plain = play(false, &[Action::Right, Action::RotateCCW, Action::Right, Action::Drop]);
+        assert_eq!(mirrored.grid.state_hash(), plain.grid.state_hash());
+
+        let (start, moved) = (play(true, &[]), play(true, &[Action::Left]));
+        let shift = |cells: Vec<(i16, i16)>, dx: i16| cells.into_iter().map(|(x, y)| (x + dx, y) ).collect::<Vec<_>>();
+        assert_eq!(moved.grid.curr_piece.sorted_cells(), shift(start.grid.curr_piece.sorted_cells(), 1)); // right on the board
+        let drawn = |game: &Game| game.grid.mirror_piece(&game.grid.curr_piece).sorted_cells();
+        assert_eq!(drawn(&moved), shift(drawn(&start), -1)); // left on the screen
+    }
+
+    #[test]
+    fn pieces_spawn_wholly_in_view_without_hidden_rows() {
+        let size = Size{width: Grid::WIDTH, height: Grid::HEIGHT, hidden_rows: 0};
+        let mut rng = StdRng::seed_from_u64(5);
+        for kind in PieceKind::of_variant(Variant::Standard).into_iter().chain(PieceKind::of_variant(Variant::Pentomino)) {
+            let mut piece = Tetrinome::from_piece(kind);
+            for _ in 0..4 {
+                for spawn_column in [SpawnColumn::Random, SpawnColumn::Center] {
+                    let spawned = Tetrinome::new(std::slice::from_ref(&piece), size, SpawnOrientation::Flat, spawn_column, &mut rng);
+                    assert!(spawned.bones.iter().all(|bone| bone.coord.y >= 0 && (0..size.width).contains(&bone.coord.x) ), "{:?} {:?}", kind, spawned.orientation);
+                }
+                piece.rotate(&Rotation::CW);
+            }
+        }
+    }
+
+    #[test]
+    fn only_a_hard_drop_locks_with_hard_drop_lock() {
+        let mut game = playing_with(Config{hard_drop_lock: true, sequence: vec![PieceKind::T, PieceKind::S], ..Config::default()});
+        let floor = game.grid.height() - 1;
+        fill_row(&mut game, floor, &[0]);
+        let filled = |game: &Game| game.grid.blocks.data.iter().flatten().count();
+        let stack = filled(&game);
+        while !game.grid.resting() {
+            game.tick(game.gravity());
+        }
+        for _ in 0..10 {
+            game.tick(game.handling.lock_delay);
+        }
+        assert_eq!(filled(&game), stack);
+        assert_eq!(game.grid.curr_piece.kind, PieceKind::T);
+        game.press(Action::Drop);
+        game.tick(1.0);
+        assert_eq!(filled(&game), stack + 4);
+        assert_eq!(game.grid.curr_piece.kind, PieceKind::S);
+    }
+
+    fn garbage_at(game: &mut Game, coords: &[Coord]) {
+        for coord in coords {
+            game.grid.blocks.set_block(coord, Bone::new(Color::Gray, *coord, PieceKind::Garbage)).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_blocked_spawn_ends_the_game_only_with_block_out() {
+        for block_out in [true, false] {
+            let top_out = TopOutRules{block_out, lock_out: false};
+            let mut game = playing_with(Config{top_out, hidden_rows: 2, sequence: vec![PieceKind::O; 3], ..Config::default()});
+            let spawn = game.grid.curr_piece.get_coords(); // the next O spawns here too
+            for _ in 0..3 {
+                game.press(Action::Left);
+                game.tick(1.0);
+                game.release(Action::Left);
+            }
+            garbage_at(&mut game, &spawn);
+            game.press(Action::Drop);
+            game.tick(1.0);
+            assert_eq!(matches!(game.state, GameState::GameOver), block_out);
+            assert_eq!(game.grid.game_over_reason(), if block_out { Some("block out") } else { None });
+        }
+    }
+
+    #[test]
+    fn a_lock_above_the_board_ends_the_game_only_with_lock_out() {
+        for lock_out in [true, false] {
+            let top_out = TopOutRules{block_out: false, lock_out};
+            let mut game = playing_with(Config{top_out, hidden_rows: 2, sequence: vec![PieceKind::O; 3], ..Config::default()});
+            assert!(game.grid.curr_piece.bones.iter().all(|bone| bone.coord.y < 0 ));
+            let columns = piece_columns(&game);
+            let stack: Vec<Coord> = columns.iter().flat_map(|x| (0..game.grid.height()).map(move |y| Coord{x: *x, y}) ).collect();
+            garbage_at(&mut game, &stack); // up to the top of the board under the piece, no row full
+            game.press(Action::Drop);
+            game.tick(1.0);
+            assert_eq!(matches!(game.state, GameState::GameOver), lock_out);
+            assert_eq!(game.grid.game_over_reason(), if lock_out { Some("lock out") } else { None });
+        }
+    }
+
+    #[test]
+    fn a_failed_load_holds_the_game_on_the_error() {
+        let mut game = Game::new(Config{menu: true, ..Config::default()});
+        game.start_loading();
+        game.tick(1.0);
+        game.loaded(Err(Error::AssetLoad("missing.png".to_string())));
+        assert!(matches!(&game.state, GameState::LoadFailed(_, err) if err.contains("missing.png")));
+        let hash = game.grid.state_hash();
+        for action in [Action::Drop, Action::Left, Action::Pause] {
+            game.press(action);
+            game.tick(millis_per_update());
+            game.release(action);
+        }
+        assert!(matches!(game.state, GameState::LoadFailed(..)));
+        assert_eq!(game.grid.state_hash(), hash);
+        game.press(Action::Confirm); // goes on without the asset
+        assert!(matches!(game.state, GameState::Menu(_)));
+    }
+
+    #[test]
+    fn a_rotation_pressed_during_are_applies_to_the_newly_spawned_piece() {
+        let mut game = playing_with(Config{sequence: vec![PieceKind::I, PieceKind::T, PieceKind::T], ..Config::default()});
+        game.grid.blocks.clear_duration = CLEAR_DURATION;
+        let floor = game.grid.height() - 1;
+        let open = piece_columns(&game);
+        fill_row(&mut game, floor, &open);
+        game.press(Action::Drop);
+        game.tick(1.0);
+        game.release(Action::Drop);
+        assert!(game.grid.clearing());
+        let spawned = game.grid.curr_piece.clone();
+        assert_eq!(spawned.kind, PieceKind::T);
+
+        game.press(Action::RotateCW);
+        game.tick(millis_per_update());
+        game.release(Action::RotateCW);
+        game.tick(millis_per_update());
+        assert_eq!(game.grid.curr_piece.sorted_cells(), spawned.sorted_cells()); // waits at the spawn while the row clears
+        while game.grid.clearing() {
+            game.tick(millis_per_update());
+        }
+        let mut rotated = game.grid.clone();
+        rotated.player_move(Direction::None, Rotation::CW);
+        assert_ne!(rotated.curr_piece.orientation, spawned.orientation);
+        game.tick(millis_per_update());
+        assert_eq!(game.grid.curr_piece.orientation, rotated.curr_piece.orientation);
+        assert_eq!(game.grid.score.lines, 1);
+    }
+}
@@ -1,768 +1,1201 @@
-#![feature(checked_duration_since)] // non-panic'ing version for instant delay checking
-#![feature(duration_float)] // used to determine the number of frames given a frame time and total duration of an animation
-
 use quicksilver::{
     Result,
-    geom::{Rectangle, Vector}, // Now we need Transform
-    graphics,
-    input::{Key, ButtonState},
-    lifecycle::{State, Window, run, Event, Settings}
+    geom::{Rectangle, Vector},
+    graphics::{self, Image, View},
+    input::{Key, ButtonState, MouseButton},
+    lifecycle::{State, Window, run, Event, Settings, Asset},
+    sound::Sound,
 };
 
-use rand::{Rng};
-use rand::distributions::{Distribution, Standard};
-
-use nalgebra::{Vector2, Matrix2};
-
-mod timing;
-use timing::{get_elapsed, Timer, MILLIS_PER_UPDATE};
-mod animation;
-use animation::{FrameTimer, FrameState};
-mod primitives;
-use primitives::{Coord, Pos, Direction, Rotation, Collision, Color};
-
-#[derive(Copy, Clone, Debug)]
-struct Bone {
-    color: Color,
-    coord: Coord,
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use tetrust::{Error, Game, GameState, Grid, PieceKind, Block, Bone, Special, Tetrinome, InstantDrop, Popup, Palette, PATTERN_SIZE};
+use tetrust::timing::{set_updates_per_sec, millis_per_update, SECOND, COUNTDOWN_STEPS};
+use tetrust::primitives::{Coord, Color, Action};
+use tetrust::config::{Config, Background, GhostColor};
+use tetrust::playback::Playback;
+
+mod text;
+use text::{draw_text, text_width, GLYPH_HEIGHT};
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshot;
+
+// a player's game and what's kept for drawing it
+struct Board {
+    game: Game,
+    draw_calls: Cell<(u32, u32)>, // bone draws this frame, (batched, one per bone), for the debug overlay
+    background_image: Option<Asset<Image>>, // loaded from the config's image background, dropped if it fails to load
+    lock_sounds: Vec<Asset<Sound>>, // the lock sound at each pitch from low to high, empty without sound or once one fails to load
+    locked: Rc<Cell<Option<usize>>>, // the pitch of the update's lock, set by the lock hook and played once the update is done
+    playback: Option<Playback>, // steps through the config's replay, the keys control it instead of the game
+    layout: Layout, // where the board was last drawn, for the mouse and the screenshot
 }
 
-impl Default for Bone {
-    fn default() -> Self {
-        Bone::new(Color::Black.into(), Coord{x: 0, y: 0})
-    }
-}
-
-impl Bone {
-    fn new(color: Color, coord: Coord) -> Self {
+impl Board {
+    fn new(config: Config) -> Self {
+        let show_hidden_rows = config.show_hidden_rows;
+        let background_image = match &config.background {
+            Background::Image(path) => Some(Asset::new(Image::load(path.clone()))),
+            _ => None,
+        };
+        let (playback, mut game) = match &config.replay {
+            Some(replay) => {
+                let (playback, game) = Playback::new(&config, replay.clone());
+                (Some(playback), game)
+            },
+            None => (None, new_game(config)),
+        };
+        let n_sounds = match (game.config().sound, game.config().lock_pitch) {
+            (false, _) => 0,
+            (true, false) => 1,
+            (true, true) => LOCK_PITCHES,
+        };
+        let lock_sounds: Vec<_> = (0..n_sounds).map(|i| Asset::new(Sound::load(lock_sound_path(i))) ).collect();
+        let locked = Rc::new(Cell::new(None));
+        if n_sounds > 0 {
+            let pitch = locked.clone();
+            game.hooks_mut().on_lock = Some(Box::new(move |info| pitch.set(Some(info.pitch(n_sounds))) ));
+        }
+        if (background_image.is_some() || n_sounds > 0) && playback.is_none() {
+            game.start_loading(); // a replay doesn't wait on them, its inputs were recorded by the update they came before
+        }
         Self {
-            color,
-            coord
+            layout: Layout::new(game.grid(), show_hidden_rows),
+            game,
+            draw_calls: Cell::new((0, 0)),
+            background_image,
+            lock_sounds,
+            locked,
+            playback,
         }
     }
 
-    fn clear_animate(&mut self, state: &FrameState) {
-        if let FrameState::Ready = state { 
-            self.color = self.color.next_color()
+    // the colors of the board's cells as drawn, the stack, the shadow and the current piece, for a screenshot
+    #[cfg(not(target_arch = "wasm32"))]
+    fn screenshot_cells(&mut self) -> screenshot::Cells {
+        let shadow = if self.game.ghost_visible() { Some(self.game.grid_mut().shadow().clone()) } else { None };
+        let grid = self.game.grid();
+        let rgb = |bone: &Bone, alpha: f32| {
+            let color = if grid.mono { mono_color(bone.coord.y, grid.height()) } else { qs_color(bone.color) };
+            ((color.r * alpha * 255.0) as u8, (color.g * alpha * 255.0) as u8, (color.b * alpha * 255.0) as u8) // over black
+        };
+        let palette = grid.palette();
+        let mut cells: screenshot::Cells = grid.blocks().data().iter().flatten()
+            .map(|block| (if block.clearing() { block.bone } else { palette.recolor(&block.bone) }, block.visibility()) )
+            .map(|(bone, visibility)| (bone.coord, rgb(&bone, visibility)) )
+            .collect();
+        if let Some(shadow) = shadow {
+            let alpha = grid.ghost_alpha();
+            cells.extend(shadow.bones().iter().map(|bone| match self.game.config().ghost_color {
+                GhostColor::Dimmed => (bone.coord, rgb(&palette.recolor(bone), alpha * GHOST_BRIGHTNESS)),
+                GhostColor::Gray => (bone.coord, rgb(&Bone{color: Color::Gray, ..*bone}, alpha)),
+            }));
         }
+        cells.extend(palette.recolor_all(grid.curr_piece().bones()).iter().map(|bone| (bone.coord, rgb(bone, 1.0)) ));
+        cells.into_iter().map(|(coord, color)| (grid.mirror(coord), color) ).collect() // as drawn, the mono shades go by row so they're the same either way
     }
-}
 
-// number of tetries piece kinds
-const NUM_PIECES: usize = 7;
+    // passes a press to the replay viewer or the game, returns true once the window should close
+    fn press(&mut self, action: Action) -> bool {
+        let quit = match &mut self.playback {
+            Some(playback) => playback.press(action, &mut self.game),
+            None => self.game.press(action),
+        };
+        if quit {
+            self.save_record();
+        }
+        quit
+    }
 
-const TETRINOME_SIZE: usize = 4;
+    fn release(&mut self, action: Action) {
+        if self.playback.is_none() {
+            self.game.release(action);
+        }
+    }
 
-static mut PIECES: Option<[Tetrinome; NUM_PIECES]> = None;
+    // plays the next update of the replay or the game
+    fn update(&mut self, elapsed: f64) {
+        match &mut self.playback {
+            Some(playback) => playback.update(&mut self.game),
+            None => self.game.tick(elapsed),
+        }
+        self.play_lock_sound();
+    }
 
-#[derive(Debug, Clone)]
-struct Tetrinome {
-    kind: PieceKind,
-    bones: [Bone; TETRINOME_SIZE],
-    pivot: Option<usize>,
-}
+    // plays the lock sound at the pitch of the update's lock, a sound that can't be played leaves the game silent
+    fn play_lock_sound(&mut self) {
+        let sound = match self.locked.take().and_then(|pitch| self.lock_sounds.get_mut(pitch) ) {
+            Some(sound) => sound,
+            None => return,
+        };
+        if let Err(err) = sound.execute(|sound| sound.play() ) { // nothing plays until it has loaded
+            eprintln!("{}, playing without sound", err);
+            self.lock_sounds.clear();
+        }
+    }
 
-impl Tetrinome {
-    fn new(width: &i16) -> Self {
-        let mut new_piece = rand::random::<Self>();
-        new_piece.trans_change(&Coord::rand_x_offset((TETRINOME_SIZE as i16, width-TETRINOME_SIZE as i16), -1)); // translate to random x in the middle of the grid
-        new_piece
+    // writes the game's replay to the --record file and the game to the --save file
+    fn save_record(&self) {
+        let config = self.game.config();
+        let files = [(&config.record, self.game.replay().to_text()), (&config.save, self.game.save().to_text())];
+        for (path, text) in files.iter() {
+            if let Some(path) = path {
+                if let Err(err) = std::fs::write(path, text) {
+                    eprintln!("{}", Error::Io(err));
+                }
+            }
+        }
     }
 
-    // add offset
-    fn shift(&self, offset: Coord) -> Vec<Coord> {
-        self.bones.iter().map(|bone| bone.coord + offset ).collect()
+    // tells the game once the background image has loaded, or that it couldn't be, in which case the board is drawn black
+    fn poll_assets(&mut self) {
+        if let GameState::Loading(_) = self.game.state() {
+            let mut ready = true;
+            if let Some(image) = &mut self.background_image {
+                ready = false;
+                if image.execute(|_| { ready = true; Ok(()) }).is_err() { // loading is polled by execute, which only runs the closure once it's done
+                    let path = match &self.game.config().background {
+                        Background::Image(path) => path.clone(),
+                        _ => String::new(),
+                    };
+                    let err = Error::AssetLoad(path);
+                    eprintln!("{}, drawing a black background instead", err);
+                    self.background_image = None;
+                    self.game.loaded(Err(err));
+                    return
+                }
+            }
+            let mut failed = None;
+            for (i, sound) in self.lock_sounds.iter_mut().enumerate() {
+                let mut loaded = false;
+                if sound.execute(|_| { loaded = true; Ok(()) }).is_err() {
+                    failed = Some(i);
+                    break
+                }
+                ready &= loaded;
+            }
+            if let Some(i) = failed {
+                let err = Error::AssetLoad(lock_sound_path(i));
+                eprintln!("{}, playing without sound", err);
+                self.lock_sounds.clear();
+                self.game.loaded(Err(err));
+                return
+            }
+            if ready {
+                self.game.loaded(Ok(()));
+            }
+        }
     }
 
-    // replace offset
-    fn trans_to(&mut self, new_coords: Vec<Coord>) {
-        self.bones.iter_mut().zip(new_coords).map(|(bone, new_coord)| bone.coord = new_coord ).collect()
+    // draws the game and its overlays from the top left of the view, with the hidden rows above the board when they're shown
+    fn draw(&mut self, window: &mut Window, show_hidden_rows: bool) -> Result<()> {
+        self.layout = Layout::new(self.game.grid(), show_hidden_rows);
+        self.draw_game(window)?;
+        if self.game.config().debug {
+            let (batched, unbatched) = self.draw_calls.get();
+            draw_text(window, &format!("bone draws {}/{}", batched, unbatched), (SIDEBAR_MARGIN, SIDEBAR_MARGIN), READOUT_TEXT_SCALE, qs_color(Color::White));
+            if self.game.stepping() {
+                draw_text(window, "step, n advances", (SIDEBAR_MARGIN, SIDEBAR_MARGIN + READOUT_LINE_HEIGHT), READOUT_TEXT_SCALE, qs_color(Color::Yellow));
+            }
+            self.draw_ruleset(window);
+        }
+        self.draw_playback(window);
+        self.draw_loading(window);
+        self.draw_menu(window);
+        self.draw_countdown(window);
+        self.draw_results(window);
+        self.draw_paused(window);
+        self.draw_confirm_quit(window);
+        Ok(())
     }
 
-    // set new offset based on adding offset
-    fn trans_change(&mut self, offset: &Coord) {
-        self.trans_to(self.shift(*offset));
+    // the rotation system, handling and gravity in play, right aligned in the top right corner of the board for the debug overlay
+    // read from the game each frame so they're current whatever changes them
+    fn draw_ruleset(&self, window: &mut Window) {
+        let handling = self.game.handling();
+        let gravity = match self.game.gravity() {
+            rate if rate > 0.0 => format!("gravity {:.0}ms {:.3}G", rate, SECOND / 60.0 / rate), // G is rows per 60th of a second
+            _ => "gravity 20G".to_string(),
+        };
+        let lines = [
+            format!("rotation {}", self.game.grid().rotation_system().name()),
+            format!("das {} arr {} sd {}", handling.das, handling.arr, handling.soft_drop),
+            format!("lock {}/{}", handling.lock_delay, handling.lock_resets),
+            gravity,
+        ];
+        let right = self.layout.board_width() - SIDEBAR_MARGIN;
+        for (i, line) in lines.iter().enumerate() {
+            let x = right - text_width(line, READOUT_TEXT_SCALE);
+            draw_text(window, line, (x, SIDEBAR_MARGIN + i as i16 * READOUT_LINE_HEIGHT), READOUT_TEXT_SCALE, qs_color(Color::White));
+        }
     }
 
-    fn get_coords(&self) -> Vec<Coord> {
-        self.bones.iter().map(|bone| bone.coord ).collect()
+    fn draw_bones(&self, window: &mut Window, bones: &[Bone]) -> Result<()> { // bones is a slice of either a vec or an array
+        self.draw_bones_ex(window, bones, 0, 1.0, 1.0)
     }
 
-    // from_layout instantiates a new tetrinome using the provided layout
-    fn from_layout(layout: String, color: Color, kind: PieceKind) -> Self {
-        let width = layout.find('\n').unwrap() as i16 + 1; // width in units not indices
-    
-        let mut pivot = None;
-        
-        let mut bones: [Bone; TETRINOME_SIZE] = [Bone::default(); TETRINOME_SIZE];
-        let mut bone_i: usize = 0;
-        for (i, c) in layout.chars().enumerate() {
-            if c == 'x' || c == 'o' {
-                let bone = Bone::new(color, Pos::from(i).pos_to_coord(width)); 
-                bones[bone_i] = bone;
-                
-                if c == 'o' {
-                    pivot = Some(bone_i);
+    // draws the bones shifted down by y_offset pixels, used to draw a falling piece between cells, faded by alpha and darkened by brightness
+    fn draw_bones_ex(&self, window: &mut Window, bones: &[Bone], y_offset: i16, alpha: f32, brightness: f32) -> Result<()> {
+        let grid = self.game.grid();
+        let (x_offset, shake_y) = self.board_offset();
+        let y_offset = y_offset + shake_y;
+        // runs of same colored bones in a row are drawn as one rectangle to cut down on draw calls
+        let mut sorted: Vec<&Bone> = bones.iter().collect();
+        sorted.sort_by_key(|bone| (bone.coord.y, bone.coord.x) );
+        let mut n_draws = 0;
+        let mut i = 0;
+        while i < sorted.len() {
+            let start = sorted[i];
+            let mut run = 1;
+            while let Some(next) = sorted.get(i + run) {
+                if next.coord.y != start.coord.y || next.coord.x != start.coord.x + run as i16 || (next.color != start.color && !grid.mono) { // mono shades are the same across a row
+                    break
                 }
-                bone_i+=1;
+                run += 1;
             }
+            let rect: Rectangle = Rectangle::new(
+                (start.coord.x * self.layout.pixel_size + x_offset,  start.coord.y * self.layout.pixel_size + y_offset),
+                (run as i16 * self.layout.pixel_size, self.layout.pixel_size),
+            );
+            let color = if grid.mono { mono_color(start.coord.y, grid.height()) } else { qs_color(start.color) };
+            let color = graphics::Color{r: color.r * brightness, g: color.g * brightness, b: color.b * brightness, a: alpha};
+            window.draw(&rect, graphics::Background::Col(color));
+            n_draws += 1;
+            i += run;
         }
-        
-        Tetrinome {
-            bones,
-            pivot,
-            kind
-        }
-    }
-
-    fn get_width(&self) -> i16 {
-        let xs = self.bones.iter().map(|bone| bone.coord.x );
-        xs.clone().max().unwrap() - xs.min().unwrap() + 1 // TODO: is the clone necessary? moved value xs where first clone
-    }
-
-    fn from_piece(kind: PieceKind) -> Self {
-        match kind {
-            PieceKind::I => Tetrinome::from_layout(
-                [
-                    "----",
-                    "xoxx",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::Green,
-                kind,
-            ),
-            PieceKind::L => Tetrinome::from_layout(
-                [
-                    "--x-",
-                    "xox-",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::Yellow,
-                kind,
-            ),
-            PieceKind::J => Tetrinome::from_layout(
-                [
-                    "x---",
-                    "xox-",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::Red,
-                kind,
-            ),
-            PieceKind::T => Tetrinome::from_layout(
-                [
-                    "--x-",
-                    "-xox",
-                    "----",
-                    "----"
-                ].join("\n"),
-                Color::Blue,
-                kind,
-            ),
-            PieceKind::Z => Tetrinome::from_layout(
-                [
-                    "xx--",
-                    "-ox-",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::Pink,
-                kind,
-            ),
-            PieceKind::S => Tetrinome::from_layout(
-                [
-                    "--xx",
-                    "-xo-",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::White,
-                kind,
-            ),
-            PieceKind::O => Tetrinome::from_layout(
-                [
-                    "-xx-",
-                    "-xx-",
-                    "----",
-                    "----",
-                ].join("\n"),
-                Color::Aqua,
-                kind,
-            ),
-        }
-    }
-
-    fn rotate(&mut self, rot: &Rotation) {
-        if let Some(pivot_i) = self.pivot { // if the tetrinome has a pivot
-            let pivot = self.bones[pivot_i];
-            let pivot_vec = Vector2::new(pivot.coord.x, pivot.coord.y);
-            for bone in self.bones.iter_mut() {
-                if let Rotation::None = rot { 
-                } else { // rotation not nothing
-                    let rot_cw_matrix: Matrix2<i16>;
-                    if let Rotation::CW = rot {
-                        rot_cw_matrix = Matrix2::new(0, -1, 
-                                                    1, 0);
-                    } else {
-                        rot_cw_matrix = Matrix2::new(0, 1, 
-                                                    -1, 0);
-                    }
+        let (batched, unbatched) = self.draw_calls.get();
+        self.draw_calls.set((batched + n_draws, unbatched + bones.len() as u32));
 
-                    let coord_vec = Vector2::new(bone.coord.x, bone.coord.y);
-                    let pivot_offset = coord_vec - pivot_vec; // relative position from pivot
-                    let new_pivot_offset = rot_cw_matrix * pivot_offset;
-                    let new_coord = pivot_vec + new_pivot_offset;
-
-                    bone.coord = Coord{x: new_coord[0], y: new_coord[1]};
-                }
+        if grid.patterns {
+            for bone in bones.iter() {
+                draw_pattern(window, bone, (x_offset, y_offset), self.layout.pixel_size);
             }
         }
+        for bone in bones.iter().filter(|bone| bone.special == Some(Special::Bomb) ) {
+            draw_bomb(window, bone, (x_offset, y_offset), self.layout.pixel_size);
+        }
+        Ok(())
     }
-}
 
-// returns a random tetrinome with a random 1 step rotation in either direction but not translated (width needed to translate)
-impl Distribution<Tetrinome> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Tetrinome {
-        let i = rng.gen_range(0, NUM_PIECES) as usize;
-        
-        unsafe {
-            if let Some(pieces) = &PIECES {
-                let mut new_piece = pieces[i].clone();
-                new_piece.rotate(&rand::random::<Rotation>());
-                new_piece
-            } else {
-                panic!("piece array not initialized!")
-            }
+    // pixels the board's cells are drawn shifted by, the shake rounded so it's back at exactly 0 once it's over and the hidden rows when they're shown
+    fn board_offset(&self) -> (i16, i16) {
+        let (x, y) = self.game.grid().shake_offset();
+        let pixels = |cells: f32| (cells * self.layout.pixel_size as f32).round() as i16;
+        (pixels(x), pixels(y) + self.layout.top)
+    }
+
+    // fills the board behind the blocks with the configured color, gradient or dimmed image
+    fn draw_background(&mut self, window: &mut Window) {
+        let board_width = self.layout.board_width();
+        match &self.game.config().background {
+            Background::Solid(rgb) => draw_rgb(window, Rectangle::new((0, 0), (board_width, SCREEN_HEIGHT)), *rgb, 1.0),
+            Background::Gradient(top, bottom) => {
+                let step_height = f32::from(SCREEN_HEIGHT) / GRADIENT_STEPS as f32;
+                for i in 0..GRADIENT_STEPS {
+                    let t = i as f32 / (GRADIENT_STEPS - 1) as f32;
+                    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t) as u8;
+                    let rect = Rectangle::new((0.0, i as f32 * step_height), (f32::from(board_width), step_height.ceil()));
+                    draw_rgb(window, rect, (mix(top.0, bottom.0), mix(top.1, bottom.1), mix(top.2, bottom.2)), 1.0);
+                }
+            },
+            Background::Image(_) => if let Some(image) = &mut self.background_image {
+                let board = Rectangle::new((0, 0), (board_width, SCREEN_HEIGHT));
+                let loaded = image.execute(|image| { // stretched over the board, nothing is drawn until it has loaded
+                    window.draw(&board, graphics::Background::Img(image));
+                    Ok(())
+                });
+                match loaded {
+                    Ok(()) => draw_rgb(window, board, Background::BLACK, BACKGROUND_IMAGE_DIM),
+                    Err(_) => { // a missing or unreadable image leaves the board black
+                        if let Background::Image(path) = &self.game.config().background {
+                            eprintln!("{}, drawing a black background instead", Error::AssetLoad(path.clone()));
+                        }
+                        self.background_image = None;
+                    },
+                }
+            },
         }
     }
-}
 
-#[derive(Debug, Clone)]
-enum PieceKind {
-    L,
-    J,
-    I,
-    T,
-    Z,
-    S,
-    O,
-}
+    fn draw_grid(&self, window: &mut Window) -> Result<()> {
+        let grid = self.game.grid();
+        let blocks = grid.blocks();
+        let palette = grid.palette();
+        // pull out all bones from Option<Block>, clearing blocks keep the colors of their animation, at the coords they're drawn at
+        let bone = |block: &Block| grid.mirror_bones(&[if block.clearing() { block.bone } else { palette.recolor(&block.bone) }])[0];
+        let bones: Vec<Bone> = blocks.data().iter().flatten().filter(|block| block.visibility() >= 1.0 ).map(bone).collect();
 
-#[derive(Debug, Clone)]
-struct Block {
-    bone: Bone,
-    frame_timer: Option<FrameTimer>,
-}
+        self.draw_bones(window, &bones)?;
+        if self.game.config().connected {
+            // a block is joined to the neighbors drawn in its color, looked up on the board rather than searched for
+            let joined = |drawn: &Bone, coord: &Coord| blocks.block(&grid.mirror(*coord)).filter(|block| block.visibility() >= 1.0 ).is_some_and(|block| bone(block).color == drawn.color );
+            draw_edges(window, &bones, joined, self.board_offset(), self.layout.pixel_size, qs_color(Color::Black).with_alpha(EDGE_ALPHA));
+        }
+        // the blocks fading out in invisible mode are drawn one at a time since each has its own opacity
+        for block in blocks.data().iter().flatten().filter(|block| block.visibility() > 0.0 && block.visibility() < 1.0 ) {
+            self.draw_bones_ex(window, &[bone(block)], 0, block.visibility(), 1.0)?;
+        }
 
-impl From<Bone> for Block {
-    fn from(some_bone: Bone) -> Self {
-        Self {
-            bone: some_bone,
-            frame_timer: None,
+        // the blocks just locked are brightened with white that fades out
+        let white = qs_color(Color::White);
+        let (x_offset, y_offset) = self.board_offset();
+        for block in blocks.data().iter().flatten() {
+            if let Some(alpha) = block.flash_alpha() {
+                let coord = grid.mirror(block.bone.coord);
+                let rect = Rectangle::new(
+                    (coord.x * self.layout.pixel_size + x_offset, coord.y * self.layout.pixel_size + y_offset),
+                    (self.layout.pixel_size, self.layout.pixel_size),
+                );
+                window.draw(&rect, graphics::Background::Col(white.with_alpha(alpha)));
+            }
         }
+
+        Ok(())
     }
-}
 
-#[derive(Clone)]
-struct Blocks {
-    data: Vec<Option<Block>>,
-    rows_full: Vec<i16>,
-}
+    fn draw_curr_piece(&self, window: &mut Window) -> Result<()> {
+        // the piece is drawn part of the way to the next cell when falling smoothly, collisions still use its cell
+        let y_offset = (self.game.fall_progress() * self.layout.pixel_size as f64) as i16;
+        let grid = self.game.grid();
+        let bones = grid.mirror_bones(&grid.palette().recolor_all(grid.curr_piece().bones()));
+        self.draw_bones_ex(window, &bones, y_offset, 1.0, 1.0)?;
+        self.draw_piece_edges(window, &bones, y_offset, 1.0);
+        Ok(())
+    }
 
-impl Blocks {
-    fn new(len: usize) -> Self {
-        Self {
-            data: vec![None; len],
-            rows_full: Vec::default(),
+    // the edge around a piece drawn as one shape, with --connected
+    fn draw_piece_edges(&self, window: &mut Window, bones: &[Bone], y_offset: i16, alpha: f32) {
+        if self.game.config().connected {
+            let (x_offset, shake_y) = self.board_offset();
+            let joined = |_: &Bone, coord: &Coord| bones.iter().any(|bone| bone.coord.x == coord.x && bone.coord.y == coord.y );
+            draw_edges(window, bones, joined, (x_offset, y_offset + shake_y), self.layout.pixel_size, qs_color(Color::Black).with_alpha(EDGE_ALPHA * alpha));
         }
     }
 
-    fn set_block(&mut self, new_pos: Pos, bone: Bone) {
-        if new_pos.0 >= 0 { // make sure its on the grid
-            let i: usize = new_pos.into(); // convert to index type
-            self.data[i] = Some(bone.into());
+    fn draw_shadow(&mut self, window: &mut Window) -> Result<()> {
+        if !self.game.ghost_visible() {
+            return Ok(())
+        }
+        let shadow_piece = self.game.grid_mut().shadow().clone();
+        let shadow_piece = self.game.grid().mirror_piece(&shadow_piece);
+        let alpha = self.game.grid().ghost_alpha();
+        match self.game.config().ghost_color {
+            GhostColor::Dimmed => {
+                let bones = self.game.grid().palette().recolor_all(shadow_piece.bones());
+                self.draw_bones_ex(window, &bones, 0, alpha, GHOST_BRIGHTNESS)?;
+            },
+            GhostColor::Gray => {
+                // recolored so the runs of the shadow still batch into one draw a row
+                let bones: Vec<Bone> = shadow_piece.bones().iter().map(|bone| Bone{color: Color::Gray, ..*bone} ).collect();
+                self.draw_bones_ex(window, &bones, 0, alpha, 1.0)?;
+            },
         }
+        self.draw_piece_edges(window, shadow_piece.bones(), 0, alpha);
+        Ok(())
     }
 
-    fn get_block(&self, pos: Pos) -> Option<Block> {
-        if pos.0 >= 0 { // make sure its on the grid
-            let i: usize = pos.into(); // convert to index type
-            return self.data[i].clone()
-        }
-        None
+    // outlines the best spot for the current piece, a white frame around each cell so it can't be mistaken for the shadow
+    fn draw_advice(&mut self, window: &mut Window) {
+        let advice = match self.game.grid_mut().advice() {
+            Some(advice) => advice.clone(),
+            None => return,
+        };
+        let advice = self.game.grid().mirror_piece(&advice);
+        draw_outline(window, &advice, self.board_offset(), self.layout.pixel_size, qs_color(Color::White).with_alpha(0.8), self.game.config().connected);
     }
 
-    // clears the entire grid
-    fn clear(&mut self) {
-        self.data = vec![None.into(); Grid::SIZE as usize];
+    // faintly outlines where the held rotation key would put the current piece
+    fn draw_rotation_preview(&self, window: &mut Window) {
+        if let Some(preview) = self.game.rotation_preview() {
+            draw_outline(window, &self.game.grid().mirror_piece(&preview), self.board_offset(), self.layout.pixel_size, qs_color(Color::White).with_alpha(0.4), self.game.config().connected);
+        }
     }
 
-    // returns whether the row is full
-    fn row_full(&self, row: &i16) -> bool {
-        let start = (row * Grid::WIDTH) as usize;
-        let end = start + Grid::WIDTH as usize; 
-        for some_block in self.data[start..end].iter() {
-            if let None = some_block {
-                return false
+    fn draw_drop(&self, window: &mut Window) -> Result<()> {
+        let grid = self.game.grid();
+        if let Some(instant_drop) = grid.instant_drop() {
+            // the latest positions are drawn behind the piece, older ones fainter
+            for (i, piece) in instant_drop.trail().iter().enumerate() {
+                let alpha = grid.ghost_alpha() * (i + 1) as f32 / (InstantDrop::TRAIL_LENGTH + 1) as f32;
+                let bones = grid.mirror_bones(&grid.palette().recolor_all(piece.bones()));
+                self.draw_bones_ex(window, &bones, 0, alpha, 1.0)?;
             }
+            self.draw_bones(window, &grid.mirror_bones(instant_drop.piece().bones()))?;
         }
-        true
+        Ok(())
     }
 
-    // replaces each block in the row with None
-    fn clear_row(&mut self, row: &i16) {
-        let start = (row * Grid::WIDTH) as usize;
-        let end = start + Grid::WIDTH as usize; 
-        for some_block in self.data[start..end].iter_mut() {
-            if let None = some_block {
-            } else {
-                *some_block = None;
+    // writes each block's index into data and its coord over it, to check the index and coord conversions
+    fn draw_coords(&self, window: &mut Window) {
+        if !self.game.grid().show_coords {
+            return
+        }
+        let scale = (self.layout.pixel_size / PIXEL_SIZE).max(1); // 2 lines of text fit in a cell at the default pixel size
+        for (i, block) in self.game.grid().blocks().data().iter().enumerate() {
+            if block.is_some() {
+                let coord = self.game.grid().blocks().coord(i); // the hidden rows are above the window
+                let drawn = self.game.grid().mirror(coord);
+                let (x, y) = (drawn.x * self.layout.pixel_size + scale, drawn.y * self.layout.pixel_size + self.layout.top + scale);
+                draw_text(window, &i.to_string(), (x, y), scale, qs_color(Color::Black));
+                draw_text(window, &format!("{},{}", coord.x, coord.y), (x, y + (GLYPH_HEIGHT + 1) * scale), scale, qs_color(Color::Black));
             }
         }
     }
 
-    fn add_row_to_clear(&mut self, row: &i16) {
-        self.rows_full.push(*row);
+    // draws each piece kind in miniature alongside the number of times it has spawned and its blocks left on the board
+    fn draw_stats(&self, window: &mut Window) -> Result<()> {
+        let grid = self.game.grid();
+        let board_counts = grid.blocks().kind_counts();
+        let x = self.layout.board_width() + SIDEBAR_MARGIN;
+
+        // the minis shrink to fit larger piece sets in the space under the next piece
+        let pieces = grid.pieces();
+        let n_pieces = pieces.len().max(1) as i16;
+        let piece_height = pieces.iter().flat_map(|piece| piece.bones().iter().map(|bone| bone.coord.y + 1) ).max().unwrap_or(1);
+        let piece_width = pieces.iter().flat_map(|piece| piece.bones().iter().map(|bone| bone.coord.x + 1) ).max().unwrap_or(1);
+        let mini_size = (PIXEL_SIZE / 2).min(((SCREEN_HEIGHT - self.stats_top()) / n_pieces - SIDEBAR_MARGIN) / piece_height).max(1);
+        let row_height = mini_size * piece_height + SIDEBAR_MARGIN;
+        let mut y = SCREEN_HEIGHT - n_pieces * row_height;
+
+        for piece in pieces.iter() {
+            draw_mini_piece(window, piece, (x, y), mini_size, grid.palette());
+            let count = format!("{}/{}", grid.stats()[piece.kind() as usize], board_counts[piece.kind() as usize]);
+            let text_y = y + (mini_size * piece_height - GLYPH_HEIGHT * STATS_TEXT_SCALE) / 2; // vertically centered on the mini piece
+            let text_color = if grid.palette().selected() == Some(piece.kind()) { Color::Yellow } else { Color::White }; // the kind the recolor key changes
+            draw_text(window, &count, (x + piece_width * mini_size + SIDEBAR_MARGIN, text_y), STATS_TEXT_SCALE, qs_color(text_color));
+            y += row_height;
+        }
+        Ok(())
     }
 
-    // returns whether the row is ready to be cleared if all the animations in the row are done
-    fn row_ready(&mut self, row: &i16) -> bool {
-        let start = (row * Grid::WIDTH) as usize;
-        let end = start + Grid::WIDTH as usize;
-        
-        self.data[start..end].iter_mut().filter_map(|some_block| {
-            if let Some(block) = some_block {
-                if let Some(frame_timer) = &mut block.frame_timer {
-                    let frame_state = frame_timer.get_state();
-                    return Some(frame_state)                    
-                }
-            }
-            return None
-        })
-        .all(|frame_state| { if let FrameState::Done = frame_state { return true } return false })
+    // draws the next pieces under the readouts, or a marker that they are hidden in blind mode
+    fn draw_next(&self, window: &mut Window) {
+        let grid = self.game.grid();
+        if grid.blind() {
+            draw_readout(window, &self.layout, NEXT_READOUT_LINE, "blind");
+            return
+        }
+        if grid.next_pieces().next().is_none() {
+            return // no preview
+        }
+        draw_readout(window, &self.layout, NEXT_READOUT_LINE, "next");
+
+        let x = self.layout.board_width() + SIDEBAR_MARGIN;
+        let mut y = readout_y(NEXT_READOUT_LINE + 1);
+        for (i, piece) in grid.next_pieces().enumerate() {
+            draw_mini_piece(window, &at_origin(&grid.mirror_piece(piece)), (x, y), next_mini_size(i), grid.palette());
+            y += next_slot_height(i);
+        }
     }
 
-    // initializes the FrameTimer which begins the clearing countdown
-    fn start_clear(&mut self, row: &i16) {
-        let start = (row * Grid::WIDTH) as usize;
-        let end = start + Grid::WIDTH as usize;
-        
-        let mut i = 0;
-        for some_block in self.data[start..end].iter_mut() {
-            if let Some(block) = some_block {
-                if let None = &mut block.frame_timer {
-                    let frame_duration = MILLIS_PER_UPDATE * 3.0;
-                    let total_anim_time = 3000.0;
-                    let n_frames = total_anim_time / frame_duration;
-                    block.bone.color = Color::get_color(i as usize);
-                    block.frame_timer = Some(FrameTimer::equal_sized(n_frames as usize, frame_duration, 0.0)); // wave effect
-                    i += 1;
-                }
-            }
+    // top of the hold panel, under as many next pieces as are shown
+    fn hold_top(&self) -> i16 {
+        let grid = self.game.grid();
+        let n_next = if grid.blind() { 0 } else { grid.next_pieces().count() };
+        if n_next == 0 && !grid.blind() {
+            return readout_y(NEXT_READOUT_LINE) // takes the place of the preview
         }
+        readout_y(NEXT_READOUT_LINE + 1) + (0..n_next).map(next_slot_height).sum::<i16>() + SIDEBAR_MARGIN
+    }
 
-        self.add_row_to_clear(row);
+    // top of the caption, under the held pieces
+    fn caption_top(&self) -> i16 {
+        self.hold_top() + READOUT_LINE_HEIGHT + MINI_PIECE_HEIGHT * self.game.grid().hold_depth() as i16 + SIDEBAR_MARGIN
     }
-    
-    fn finish_clear(&mut self) {
-        let ready_rows: Vec<i16> = self.rows_full.clone().into_iter().filter(|row| self.row_ready(row) ).collect();
-        // clear the ready rows
-        for ready_row in ready_rows.iter() {
-            self.clear_row(ready_row);
-        }
-        for ready_row in ready_rows.iter() {
-            for upper_row in (0..*ready_row).rev() {
-                if self.drop_row_down(&upper_row) == 0 {
-                    break; // preliminary break if empty row found
-                }
-            }
-            self.rows_full.remove(0); // dequeu from front, doesn't cause deallocation, refer to: https://doc.rust-lang.org/std/vec/struct.Vec.html#guarantees
+
+    // top of the stats panel, under the caption if there is one
+    fn stats_top(&self) -> i16 {
+        match self.game.config().caption_text() {
+            Some(_) => self.caption_top() + READOUT_LINE_HEIGHT + SIDEBAR_MARGIN,
+            None => self.caption_top(),
         }
     }
 
-    // returns the rows the piece inhabits
-    fn get_piece_rows(&self, piece: &Tetrinome) -> Vec<i16> {
-        let mut ys: Vec<i16> = piece.bones.iter().map(|bone| bone.coord.y).collect();
-        ys.sort();
-        ys.dedup();
-        ys.into_iter().collect()
+    // draws the caption in the sidebar so it never covers the board
+    fn draw_caption(&self, window: &mut Window) {
+        if let Some(caption) = self.game.config().caption_text() {
+            let x = self.layout.board_width() + SIDEBAR_MARGIN;
+            draw_text(window, &caption, (x, self.caption_top()), READOUT_TEXT_SCALE, qs_color(Color::Yellow));
+        }
     }
 
-    // drops the given row down
-    fn drop_row_down(&mut self, row: &i16) -> i16 {
-        let mut start = (row * Grid::WIDTH) as usize;
-        let end = start + Grid::WIDTH as usize;
-        let mut count = 0;
-        for block in self.data.clone()[start..end].iter_mut() {
-            if let Some(block) = block {
-                block.bone.coord.y += 1; // coord for drawing
-                self.data[start] = None.into(); // old spot
-                self.data[start + Grid::WIDTH as usize] = Some(block.clone()); // new spot has clone
-                count+=1;
-            }
-            start+=1;
+    // draws the held pieces under the next piece, the one coming back first on top, the last held shrinking and sliding in from the board while a hold swap animates
+    fn draw_hold(&self, window: &mut Window) {
+        let grid = self.game.grid();
+        let x = self.layout.board_width() + SIDEBAR_MARGIN;
+        let hold_top = self.hold_top();
+        if !grid.hold_enabled() {
+            draw_text(window, "no hold", (x, hold_top), READOUT_TEXT_SCALE, qs_color(Color::Gray));
+            return
         }
-        // dropping down the rows affects the rows about to be cleared as well so add to each full row above the cleared row
-        for full_row in self.rows_full.iter_mut() {
-            if row >= full_row {
-                *full_row+=1;
+        draw_text(window, "hold", (x, hold_top), READOUT_TEXT_SCALE, qs_color(Color::White));
+        let box_pos = (x, hold_top + READOUT_LINE_HEIGHT);
+
+        let n_held = grid.held_pieces().count();
+        for (i, piece) in grid.held_pieces().enumerate() {
+            let slot_pos = (box_pos.0, box_pos.1 + i as i16 * MINI_PIECE_HEIGHT);
+            match grid.hold_swap() {
+                Some(hold_swap) if i + 1 == n_held => { // the last held is at the back
+                    let piece = &grid.mirror_piece(hold_swap.piece());
+                    let min_x = piece.bones().iter().map(|bone| bone.coord.x ).min().unwrap();
+                    let min_y = piece.bones().iter().map(|bone| bone.coord.y ).min().unwrap();
+                    let progress = hold_swap.progress();
+                    let lerp = |from: i16, to: i16| from + (f32::from(to - from) * progress) as i16;
+                    let pos = (lerp(min_x * self.layout.pixel_size, slot_pos.0), lerp(min_y * self.layout.pixel_size + self.layout.top, slot_pos.1));
+                    draw_mini_piece(window, &at_origin(piece), pos, lerp(self.layout.pixel_size, PIXEL_SIZE / 2), grid.palette());
+                },
+                _ => draw_mini_piece(window, &at_origin(&grid.mirror_piece(piece)), slot_pos, PIXEL_SIZE / 2, grid.palette()),
             }
         }
-        count
     }
 
-    fn check_collision(&self, piece: &Tetrinome, dir: &Direction, rot: &Rotation) -> Collision {
-        for coord in piece.get_coords() {
-            // out of bounds
-            if coord.x < 0 {
-                return Collision::Left
-            } else if coord.x >= Grid::WIDTH {
-                return Collision::Right
-            }
-            if coord.y >= Grid::HEIGHT {
-                return Collision::Under
-            } else if let None = self.get_block(coord.coord_to_pos(Grid::WIDTH)) {
-                // empty block
-            } else {
-                let rot_dir: Direction = (*rot).clone().into();
-                return match dir {
-                    Direction::Down => Collision::Under,
-                    Direction::Left => Collision::Left,
-                    Direction::Right => Collision::Right,
-                    Direction::None => match rot_dir {
-                        Direction::Left => Collision::Left,
-                        Direction::Right => Collision::Right,
-                        _ => Collision::None,
-                    }
-                }
-            }
+    // draws the board, the pieces on it and the sidebar
+    fn draw_game(&mut self, window: &mut Window) -> Result<()> {
+        self.draw_calls.set((0, 0));
+        self.draw_stats(window)?;
+        self.draw_next(window);
+        self.draw_hold(window);
+        self.draw_caption(window);
+        let score = *self.game.grid().score();
+        draw_readout(window, &self.layout, 0, &format!("score {}", score.points));
+        draw_readout(window, &self.layout, 1, &format!("lines {}", score.lines));
+        draw_readout(window, &self.layout, 2, &format!("level {}", score.level));
+        draw_readout(window, &self.layout, 3, &format!("finesse {}", self.game.grid().finesse_faults()));
+        draw_readout(window, &self.layout, 4, &format!("pieces {} {:.2}/s", self.game.grid().spawned(), self.game.piece_rate()));
+        if let Some(drought) = self.game.grid().drought(PieceKind::I) {
+            draw_readout(window, &self.layout, 5, &format!("I drought {}", drought));
         }
-
-        Collision::None
+        self.draw_background(window);
+        if self.layout.top > 0 { // where the shown hidden rows end and the board starts
+            let line = Rectangle::new((0, self.layout.top - 1), (self.layout.board_width(), 2));
+            window.draw(&line, graphics::Background::Col(qs_color(Color::Gray).with_alpha(0.5)));
+        }
+        self.draw_grid(window)?;
+        self.draw_shadow(window)?;
+        self.draw_advice(window);
+        self.draw_drop(window)?;
+        self.draw_curr_piece(window)?;
+        self.draw_rotation_preview(window);
+        self.draw_coords(window);
+        self.draw_rise(window);
+        self.draw_kill_screen(window);
+        draw_popups(window, self.game.grid().popups(), &self.layout);
+        Ok(())
     }
-}
 
-#[derive(Clone)]
-struct InstantDrop {
-    piece: Tetrinome,
-    frame_timer: FrameTimer,
-}
+    // highlights the cell under the mouse in the editor
+    fn draw_hover(&self, window: &mut Window, coord: &Coord) {
+        if self.game.config().editor {
+            let size = self.layout.pixel_size;
+            let rect = Rectangle::new((coord.x * size, coord.y * size + self.layout.top), (size, size));
+            window.draw(&rect, graphics::Background::Col(qs_color(Color::White).with_alpha(0.3)));
+        }
+    }
 
-#[derive(Clone)]
-struct Grid {
-    blocks: Blocks,
-    curr_piece: Tetrinome,
-    instant_drop: Option<InstantDrop>,
-}
+    // covers the board while the assets load, or says one couldn't be loaded until it's confirmed
+    fn draw_loading(&self, window: &mut Window) {
+        let lines: &[(&str, i16)] = match self.game.state() {
+            GameState::Loading(_) => &[("LOADING", PIXEL_SIZE / 6)],
+            GameState::LoadFailed(..) => &[("LOAD FAILED", PIXEL_SIZE / 6), ("see the log", PIXEL_SIZE / 10), ("enter to go on", PIXEL_SIZE / 10)],
+            _ => return,
+        };
+        let color = qs_color(Color::Black);
+        let board_size = (self.layout.board_width(), SCREEN_HEIGHT);
+        window.draw(&Rectangle::new((0, 0), board_size), graphics::Background::Col(color));
+
+        let mut y = SCREEN_HEIGHT / 3;
+        for (label, scale) in lines.iter() {
+            let x = (self.layout.board_width() - text_width(label, *scale)) / 2;
+            draw_text(window, label, (x, y), *scale, qs_color(Color::White));
+            y += (GLYPH_HEIGHT + 3) * scale;
+        }
+    }
 
-impl Grid {
-    const WIDTH: i16 = 10;
-    const HEIGHT: i16 = 20;
-    const SIZE: i16 = Self::WIDTH * Self::HEIGHT;
+    // dims the board and lists the menu's settings, the selected one highlighted
+    fn draw_menu(&self, window: &mut Window) {
+        if let GameState::Menu(menu) = self.game.state() {
+            let color = qs_color(Color::Black);
+            let board_size = (self.layout.board_width(), SCREEN_HEIGHT);
+            window.draw(&Rectangle::new((0, 0), board_size), graphics::Background::Col(color.with_alpha(0.75)));
+
+            let scale = PIXEL_SIZE / 10;
+            let mut y = SCREEN_HEIGHT / 3;
+            for (label, selected) in menu.lines().iter() {
+                let x = (self.layout.board_width() - text_width(label, scale)) / 2;
+                draw_text(window, label, (x, y), scale, qs_color(if *selected { Color::Yellow } else { Color::Gray }));
+                y += (GLYPH_HEIGHT + 3) * scale;
+            }
+            let hint = "enter to start";
+            let x = (self.layout.board_width() - text_width(hint, scale)) / 2;
+            draw_text(window, hint, (x, y + GLYPH_HEIGHT * scale), scale, qs_color(Color::White));
+        }
+    }
 
-    fn new() -> Self {
-        Self {
-            blocks: Blocks::new(Grid::WIDTH as usize * Grid::HEIGHT as usize), // init to None (like null ptr)
-            curr_piece: Tetrinome::new(&Grid::WIDTH),
-            instant_drop: None,
+    // draws the current countdown step large and centered on the board
+    fn draw_countdown(&self, window: &mut Window) {
+        if let GameState::Countdown(frame_timer) = self.game.state() {
+            let steps_left = COUNTDOWN_STEPS.saturating_sub(frame_timer.frame().max(1)); // the first step is shown before the first update
+            let label = if steps_left > 0 { steps_left.to_string() } else { "GO".to_string() };
+            let scale = PIXEL_SIZE / 2;
+            let x = (self.layout.board_width() - text_width(&label, scale)) / 2;
+            let y = (SCREEN_HEIGHT - GLYPH_HEIGHT * scale) / 2;
+            draw_text(window, &label, (x, y), scale, qs_color(Color::White));
         }
     }
 
-    // commit the piece after a downwards collision 
-    fn commit_piece(&mut self) {
-        for new_block in self.curr_piece.bones.iter_mut() {
-            let new_pos = new_block.coord.coord_to_pos(Grid::WIDTH); // convert into pos and then usize for indexing
+    // dims the board and shows the results once the marathon is won or survival is over
+    fn draw_results(&self, window: &mut Window) {
+        let title = match self.game.state() {
+            GameState::Victory => "YOU WIN",
+            GameState::GameOver => "GAME OVER",
+            _ => return,
+        };
+        let color = qs_color(Color::Black);
+        let board_size = (self.layout.board_width(), SCREEN_HEIGHT);
+        window.draw(&Rectangle::new((0, 0), board_size), graphics::Background::Col(color.with_alpha(0.75)));
+
+        let seconds = self.game.play_time() / SECOND;
+        let time = format!("{}:{:05.2}", (seconds / 60.0) as u32, seconds % 60.0);
+        let mut lines = vec![(title, PIXEL_SIZE / 6), ("TIME", PIXEL_SIZE / 10), (time.as_str(), PIXEL_SIZE / 10)];
+        if let (GameState::GameOver, Some(reason)) = (self.game.state(), self.game.grid().game_over_reason()) {
+            lines.insert(1, (reason, PIXEL_SIZE / 10));
+        }
+        let mut y = SCREEN_HEIGHT / 3;
+        for (label, scale) in lines.iter() {
+            let x = (self.layout.board_width() - text_width(label, *scale)) / 2;
+            draw_text(window, label, (x, y), *scale, qs_color(Color::Yellow));
+            y += (GLYPH_HEIGHT + 2) * scale;
+        }
+    }
 
-            self.blocks.set_block(new_pos, *new_block);
+    // fills a bar up the right edge of the board as survival's next garbage row gets closer
+    fn draw_rise(&self, window: &mut Window) {
+        if let Some(rise) = self.game.rise() {
+            let width = f32::from(PIXEL_SIZE / 8);
+            let height = rise.progress() as f32 * f32::from(SCREEN_HEIGHT);
+            let x = f32::from(self.layout.board_width()) - width;
+            let rect = Rectangle::new((x, f32::from(SCREEN_HEIGHT) - height), (width, height));
+            window.draw(&rect, graphics::Background::Col(qs_color(Color::Red).with_alpha(0.6)));
         }
     }
 
-    fn clear_row_if(&mut self) {
-        let rows = self.blocks.get_piece_rows(&self.curr_piece); // in asc order
+    // tints the board red once the kill screen starts, and counts down to it while the stack is high enough
+    fn draw_kill_screen(&self, window: &mut Window) {
+        let label = if self.game.kill_screen() {
+            let board_size = (self.layout.board_width(), SCREEN_HEIGHT);
+            window.draw(&Rectangle::new((0, 0), board_size), graphics::Background::Col(qs_color(Color::Red).with_alpha(0.2)));
+            "KILL SCREEN".to_string()
+        } else if let Some(remaining) = self.game.kill().and_then(|kill| kill.remaining() ) {
+            format!("kill screen in {:.1}", remaining / SECOND)
+        } else {
+            return
+        };
+        draw_text(window, &label, (SIDEBAR_MARGIN, SIDEBAR_MARGIN), READOUT_TEXT_SCALE, qs_color(Color::Red));
+    }
 
-        // iterate from top to bottom checking for full rows, once found clear it, and iterate from bottom up to drop blocks down
-        for row in 0..=rows[rows.len()-1] {
-            if self.blocks.row_full(&row) {
-                self.blocks.start_clear(&row);
+    // the replay's position at the bottom of the board
+    fn draw_playback(&self, window: &mut Window) {
+        if let Some(playback) = &self.playback {
+            let status = if playback.finished(&self.game) { "end" } else if playback.paused() { "paused" } else { "playing" };
+            let lines = [format!("replay {}", status), format!("frame {} piece {}", self.game.frame(), self.game.grid().spawned())];
+            let mut y = SCREEN_HEIGHT - SIDEBAR_MARGIN - lines.len() as i16 * READOUT_LINE_HEIGHT;
+            for line in lines.iter() {
+                draw_text(window, line, (SIDEBAR_MARGIN, y), READOUT_TEXT_SCALE, qs_color(Color::Yellow));
+                y += READOUT_LINE_HEIGHT;
             }
         }
     }
 
-    // move_if is the actually called helper, taking a direction and determining whether or not to move
-    fn move_if(&mut self, dir: Direction, rot: Rotation) -> bool {
-        let mut new_piece = self.curr_piece.clone();
-        new_piece.trans_change(&dir.clone().into()); // translate new piece based on direction
-        new_piece.rotate(&rot); // do rotation
-
-        let col = self.blocks.check_collision(&new_piece, &dir, &rot);
-        match col { // check collision for new piece
-            Collision::Under => { 
-                self.commit_piece(); 
-                self.clear_row_if(); 
-                self.curr_piece = Tetrinome::new(&Grid::WIDTH); 
-                return true;
-            }, // if collided underneath then commit
-            Collision::Left | Collision::Right  => {
-                if let Rotation::CCW | Rotation::CW = rot {
-                    let col_dir: Direction = col.into();
-                    let new_dir = col_dir.opposite();
-                    for _ in 0..new_piece.get_width()/2 {
-                        new_piece.trans_change(&new_dir.clone().into());
-                    }
-                    let new_col = self.blocks.check_collision(&new_piece, &new_dir, &Rotation::None);
-                    if let Collision::None = new_col {
-                        self.curr_piece = new_piece;
-                    }
-                }
-            }, // collided on the side, nothing happens
-            Collision::None => self.curr_piece = new_piece, // no collision, then move
+    // dims the board while the game is paused
+    fn draw_paused(&self, window: &mut Window) {
+        if let GameState::Paused(_) = self.game.state() {
+            let color = qs_color(Color::Black);
+            let board_size = (self.layout.board_width(), SCREEN_HEIGHT);
+            window.draw(&Rectangle::new((0, 0), board_size), graphics::Background::Col(color.with_alpha(0.75)));
+
+            let label = "PAUSED";
+            let scale = PIXEL_SIZE / 6;
+            let x = (self.layout.board_width() - text_width(label, scale)) / 2;
+            let y = (SCREEN_HEIGHT - GLYPH_HEIGHT * scale) / 2;
+            draw_text(window, label, (x, y), scale, qs_color(Color::White));
         }
-        false
     }
 
-    fn draw_bones(&self, window: &mut Window, bones: &[Bone]) -> Result<()> { // bones is a slice of either a vec or an array
-        for bone in bones.iter() {
-            let rect: Rectangle = Rectangle::new(
-                (bone.coord.x * get_pixel_size(),  bone.coord.y * get_pixel_size()),
-                (get_pixel_size(), get_pixel_size()),
-            );
-            window.draw(&rect, graphics::Background::Col(bone.color.into()));
-        }
-        // if let graphics::DrawMode::Fill(_) = draw_mode {
-        //     let mesh = &mut graphics::MeshBuilder::new();
-        //     for bone in bones.iter() {
-        //         let rect = mesh.rectangle(
-        //                 graphics::DrawMode::stroke(2.0), 
-        //                 graphics::Rect::new(
-        //                     ((bone.coord.x) * get_pixel_size()).into(),
-        //                     ((bone.coord.y) * get_pixel_size()).into(),
-        //                     get_pixel_size().into(),
-        //                     get_pixel_size().into(),
-        //                 ), 
-        //                 Color::Black.into(),
-        //             ).build(window)?;
-        //         graphics::draw(window, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
-        //     }
-        // }
-        Ok(())
+    // dims the screen and asks whether to quit
+    fn draw_confirm_quit(&self, window: &mut Window) {
+        if let GameState::ConfirmQuit(_) = self.game.state() {
+            let color = qs_color(Color::Black);
+            window.draw(&Rectangle::new((0, 0), SCREEN_SIZE), graphics::Background::Col(color.with_alpha(0.75)));
+
+            let lines = [("QUIT?", PIXEL_SIZE / 4), ("Y / N", PIXEL_SIZE / 8)];
+            let mut y = SCREEN_HEIGHT / 2 - GLYPH_HEIGHT * lines[0].1;
+            for (label, scale) in lines.iter() {
+                let x = (SCREEN_SIZE.x as i16 - text_width(label, *scale)) / 2;
+                draw_text(window, label, (x, y), *scale, qs_color(Color::White));
+                y += (GLYPH_HEIGHT + 2) * scale;
+            }
+        }
     }
+}
 
-    fn draw_grid(&mut self, window: &mut Window) -> Result<()> {
-        let blocks = &mut self.blocks.data; 
-        let bones: Vec<Bone> = blocks.iter_mut().filter_map(|block| { // pull out all bones from Option<Bone>
-                if let Some(block) = block {
-                    if let Some(frame_timer) = &mut block.frame_timer {  // if animatable
-                        block.bone.clear_animate(&frame_timer.state(get_elapsed()));
-                    }
-                    Some(block.bone)
-                } else {
-                    None
-                }
+// the game's action for the key, None for keys that do nothing
+fn action(key: Key) -> Option<Action> {
+    let action = match key {
+        Key::Left => Action::Left,
+        Key::Right => Action::Right,
+        Key::Down => Action::Down,
+        Key::Up | Key::X => Action::RotateCW,
+        Key::Z => Action::RotateCCW,
+        Key::Space => Action::Drop,
+        Key::LShift | Key::H => Action::Hold,
+        Key::Q | Key::Escape => Action::Quit,
+        Key::P => Action::Pause,
+        Key::Y | Key::Return => Action::Confirm,
+        Key::N => Action::Cancel,
+        Key::R => Action::Restart,
+        Key::B => Action::ClearBoard,
+        Key::Back => Action::Undo,
+        Key::C => Action::TogglePatterns,
+        Key::M => Action::ToggleMono,
+        Key::A => Action::ToggleAdvice,
+        Key::F4 => Action::ToggleCoords,
+        Key::F5 => Action::ToggleStep,
+        Key::F6 => Action::SelectRecolor,
+        Key::F7 => Action::Recolor,
+        Key::F8 => Action::ResetColors,
+        Key::G => Action::Ghost,
+        Key::F9 => Action::ToggleGhost,
+        Key::F10 => Action::ToggleMirror,
+        Key::F11 => Action::ToggleHiddenRows,
+        _ => return None,
+    };
+    Some(action)
+}
+
+// the keys of the two players in versus and the board each is for, None for keys every board takes, ie. quitting and pausing
+fn versus_action(key: Key) -> Option<(Option<usize>, Action)> {
+    let routed = match key {
+        Key::A => (Some(0), Action::Left),
+        Key::D => (Some(0), Action::Right),
+        Key::S => (Some(0), Action::Down),
+        Key::W => (Some(0), Action::RotateCW),
+        Key::E => (Some(0), Action::RotateCCW),
+        Key::Tab => (Some(0), Action::Drop),
+        Key::LShift => (Some(0), Action::Hold),
+        Key::Left => (Some(1), Action::Left),
+        Key::Right => (Some(1), Action::Right),
+        Key::Down => (Some(1), Action::Down),
+        Key::Up => (Some(1), Action::RotateCW),
+        Key::K => (Some(1), Action::RotateCCW),
+        Key::Space => (Some(1), Action::Drop),
+        Key::J => (Some(1), Action::Hold),
+        _ => {
+            let action = action(key)?;
+            match action {
+                Action::Quit | Action::Pause | Action::Confirm | Action::Cancel | Action::Restart | Action::ClearBoard | Action::TogglePatterns | Action::ToggleMono | Action::ToggleCoords | Action::ToggleStep
+                | Action::SelectRecolor | Action::Recolor | Action::ResetColors | Action::Ghost | Action::ToggleGhost | Action::ToggleMirror | Action::ToggleHiddenRows => (None, action),
+                _ => return None, // the single player's piece keys
             }
-        )
-        .collect();
+        },
+    };
+    Some(routed)
+}
 
-        // self.draw_bones(window, &bones, graphics::DrawMode::fill())?;
-        self.draw_bones(window, &bones)?;
+// the quicksilver frontend, a board per player side by side, maps keys to their actions
+struct Tetrust {
+    boards: Vec<Board>,
+    hover: Option<(usize, Coord)>, // the board and the cell under the mouse, highlighted in the editor
+    show_hidden_rows: bool, // draws the hidden rows above the boards, from --show-hidden-rows and toggled with F11
+}
 
-        Ok(())
+impl Tetrust {
+    // sends the garbage each player's clears earned to the others, the round ends once a player is pushed over the top
+    fn exchange_garbage(&mut self) {
+        if self.boards.len() < 2 {
+            return
+        }
+        let sent: Vec<i16> = self.boards.iter_mut().map(|board| board.game.take_sent_garbage() ).collect();
+        let total: i16 = sent.iter().sum();
+        for (board, own) in self.boards.iter_mut().zip(sent.iter()) {
+            if total > *own {
+                board.game.receive_garbage(total - own);
+            }
+        }
+        if self.boards.iter().any(|board| matches!(board.game.state(), GameState::GameOver)) {
+            for board in self.boards.iter_mut() {
+                board.game.win();
+            }
+        }
     }
 
-    fn draw_curr_piece(&mut self, window: &mut Window) -> Result<()> {
-        // self.draw_bones(window, &self.curr_piece.bones, graphics::DrawMode::fill())
-        self.draw_bones(window, &self.curr_piece.bones)
+    // saves the boards as they're drawn to a png without the sidebars, files can't be written on the web so it does nothing there
+    fn screenshot(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let boards: Vec<_> = self.boards.iter_mut().map(Board::screenshot_cells).collect();
+            let layout = self.boards[0].layout; // the players' boards are the same size
+            let (width, height, rgb) = screenshot::render(&boards, layout.columns, layout.rows, layout.pixel_size as usize);
+            screenshot::save(screenshot::encode_png(width, height, &rgb));
+        }
     }
+}
 
-    fn shadow_distance(&self, piece: &Tetrinome) -> usize {
-        let mut shadow_piece = piece.clone();
-        let mut i = 0;
-        loop {
-            let col_dir = self.blocks.check_collision(&shadow_piece, &Direction::Down, &Rotation::None);
-            match col_dir { // check collision for new piece
-                Collision::Under => {
-                    if i != 0 { // panic subtract overflow
-                        break i - 1;
-                    } else {
-                        break 0;
+impl State for Tetrust {
+    fn new() -> Result<Self> {
+        let config = CONFIG.get().cloned().unwrap_or_else(Config::from_args);
+
+        // create event handler instance, the players share the config so they get the same pieces
+        Ok(Self {
+            boards: (0..config.players).map(|_| Board::new(config.clone()) ).collect(),
+            hover: None,
+            show_hidden_rows: config.show_hidden_rows,
+        })
+    }
+
+    fn event(&mut self, event: &Event, window: &mut Window) -> Result<()> {
+        if let Event::Key(key, button_state) = event {
+            let routed = if self.boards.len() == 1 { action(*key).map(|action| (Some(0), action) ) } else { versus_action(*key) };
+            if let (Some((_, Action::ToggleHiddenRows)), ButtonState::Pressed) = (routed, button_state) {
+                self.show_hidden_rows = !self.show_hidden_rows; // only the layout changes, the game and the window size are the same either way
+            } else if let Some((player, action)) = routed {
+                let boards = self.boards.iter_mut().enumerate().filter(|(i, _)| player.is_none_or(|player| player == *i ) );
+                for (_, board) in boards {
+                    match button_state {
+                        ButtonState::Pressed if board.press(action) => window.close(), // quit confirmed
+                        ButtonState::Released => board.release(action),
+                        _ => (),
                     }
                 }
-                _ => ()
             }
-            i += 1;
-            shadow_piece.trans_change(&Direction::Down.into()); // translate new piece based on direction
         }
-    }
-
-    fn draw_shadow(&mut self, window: &mut Window) -> Result<()> {
-        let mut shadow_piece = self.curr_piece.clone();
-        for _ in 0..self.shadow_distance(&shadow_piece) {
-            shadow_piece.trans_change(&Direction::Down.into());
+        if let Event::Key(Key::F12, ButtonState::Pressed) = event {
+            self.screenshot();
+        }
+        if let Event::Unfocused = event {
+            // the releases of keys held as the window loses focus go to another window, the piece would keep shifting after refocusing
+            for board in self.boards.iter_mut() {
+                board.game.release_held();
+            }
+        }
+        match event {
+            Event::MouseMoved(pos) => self.hover = cell_at(*pos, self.boards.len(), &self.boards[0].layout),
+            Event::MouseButton(MouseButton::Left, ButtonState::Pressed) => if let Some((i, coord)) = &self.hover {
+                let coord = self.boards[*i].game.grid().mirror(*coord); // the cell drawn there
+                self.boards[*i].game.toggle_block(&coord); // only in the editor
+            },
+            _ => (),
         }
-        // self.draw_bones(window, &shadow_piece.bones, graphics::DrawMode::stroke(1.0))?;
-        self.draw_bones(window, &shadow_piece.bones)?;
         Ok(())
     }
 
-    fn start_drop(&mut self, piece: Tetrinome) {
-        let n_frames = self.shadow_distance(&piece) + 1;
-        self.instant_drop = Some(InstantDrop {
-            piece: piece.clone(),
-            frame_timer: FrameTimer::equal_sized(n_frames as usize, MILLIS_PER_UPDATE, 0.0),
-        });
+    // frames updated every millis_per_update()
+    fn update(&mut self, window: &mut Window) -> Result<()> {
+        for board in self.boards.iter_mut() {
+            board.poll_assets();
+            board.update(window.update_rate()); // quicksilver runs update at a fixed rate so the elapsed time is always the update rate
+        }
+        self.exchange_garbage();
+        Ok(())
     }
 
-    fn finish_drop(&mut self) {
-        let curr_piece = self.curr_piece.clone();
-        loop {
-            if self.move_if(Direction::Down, Rotation::None) { 
-                self.start_drop(curr_piece);
-                break; 
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        window.clear(qs_color(Color::Black))?;
+
+        // each board draws from the top left, the view moves it over to its place in the window
+        let n_boards = self.boards.len() as f32;
+        for (i, board) in self.boards.iter_mut().enumerate() {
+            let view = Rectangle::new((-(i as f32) * SCREEN_SIZE.x, 0.0), (n_boards * SCREEN_SIZE.x, SCREEN_SIZE.y));
+            window.set_view(View::new(view));
+            board.draw(window, self.show_hidden_rows)?;
+            if let Some((_, coord)) = self.hover.filter(|(hovered, _)| *hovered == i ) {
+                board.draw_hover(window, &coord);
             }
         }
+        // the mouse position is unprojected through the view, so it's left covering the whole window for cell_at
+        window.set_view(View::new(Rectangle::new((0.0, 0.0), (n_boards * SCREEN_SIZE.x, SCREEN_SIZE.y))));
+
+        Ok(())
     }
+}
 
-    fn animate_drop(&mut self) {
-        if let Some(instant_drop) = &mut self.instant_drop {
-            let state = instant_drop.frame_timer.state(get_elapsed());
-            
-            if let FrameState::Ready = state {
-                let piece = &mut instant_drop.piece;
-                // piece.bones.iter_mut().for_each(|bone| { if let Color::White = bone.color {bone.color = Color::White;} else { bone.color = Color::Black; } });
-                piece.trans_change(&Direction::Down.into());
-            } else if let FrameState::Done = state {
-                self.instant_drop = None;
-            }
+const SCREEN_HEIGHT: i16 = 500;
+const PIXEL_SIZE: i16 = SCREEN_HEIGHT / Grid::HEIGHT;
+const SIDEBAR_WIDTH: i16 = 6 * PIXEL_SIZE; // panel to the right of the grid
+const SCREEN_SIZE: Vector = Vector{x: (Grid::WIDTH * PIXEL_SIZE + SIDEBAR_WIDTH) as f32, y: SCREEN_HEIGHT as f32};
+const SIDEBAR_MARGIN: i16 = 8;
+const STATS_TEXT_SCALE: i16 = 3;
+const READOUT_TEXT_SCALE: i16 = 2;
+const POPUP_TEXT_SCALE: i16 = 2;
+const POPUP_LINE_HEIGHT: i16 = (GLYPH_HEIGHT + 2) * POPUP_TEXT_SCALE;
+const POPUP_RISE: f32 = 32.0; // pixels risen over the popup's duration
+const NEXT_READOUT_LINE: i16 = 6; // below the score, lines, level, finesse, pieces and drought readouts
+const READOUT_LINE_HEIGHT: i16 = (GLYPH_HEIGHT + 2) * READOUT_TEXT_SCALE;
+const MINI_PIECE_HEIGHT: i16 = 3 * PIXEL_SIZE / 2; // the next and held pieces are at most 3 minis tall
+// PIXEL_SIZE times the cell scale, smaller while the hidden rows are shown so they fit in the window's height with the board
+fn get_pixel_size(grid: &Grid, show_hidden_rows: bool) -> i16 {
+    SCREEN_HEIGHT / (grid.height() + shown_hidden_rows(grid, show_hidden_rows))
+}
+
+fn shown_hidden_rows(grid: &Grid, show_hidden_rows: bool) -> i16 {
+    if show_hidden_rows { grid.hidden_rows() } else { 0 }
+}
+
+// pixels from the top of the window to the top visible row
+fn board_top(grid: &Grid, show_hidden_rows: bool) -> i16 {
+    shown_hidden_rows(grid, show_hidden_rows) * get_pixel_size(grid, show_hidden_rows)
+}
+
+// where a board's cells are drawn in its part of the window
+#[derive(Clone, Copy)]
+struct Layout {
+    pixel_size: i16, // of a cell
+    top: i16, // pixels from the top of the window to the top visible row
+    columns: i16,
+    rows: i16, // the visible rows
+}
+
+impl Layout {
+    fn new(grid: &Grid, show_hidden_rows: bool) -> Self {
+        Self {
+            pixel_size: get_pixel_size(grid, show_hidden_rows),
+            top: board_top(grid, show_hidden_rows),
+            columns: grid.width(),
+            rows: grid.height(),
         }
     }
-    
-    fn draw_drop(&mut self, window: &mut Window) -> Result<()> {
-        if let Some(instant_drop) = &mut self.instant_drop {
-            let bones = instant_drop.piece.bones;
-            // self.draw_bones(window, &bones, graphics::DrawMode::fill())?;
-            self.draw_bones(window, &bones)?;
-        }
-        Ok(())
+
+    // pixels across the board, the sidebar starts after it
+    fn board_width(&self) -> i16 {
+        self.columns * self.pixel_size
     }
+}
 
-    fn draw(&mut self, window: &mut Window) -> Result<()> {
-        self.draw_grid(window)?;
-        self.draw_shadow(window)?;
-        self.animate_drop();
-        self.draw_drop(window)?;
-        self.draw_curr_piece(window)
+// the board and the visible cell under a point in the window, the inverse of drawing a coord at its pixel size, None over the sidebars
+fn cell_at(pos: Vector, n_boards: usize, layout: &Layout) -> Option<(usize, Coord)> {
+    let board = (pos.x / SCREEN_SIZE.x).floor();
+    if pos.x < 0.0 || pos.y < 0.0 || board >= n_boards as f32 {
+        return None
     }
+    let size = f32::from(layout.pixel_size);
+    let y = pos.y - f32::from(layout.top);
+    let coord = Coord{x: ((pos.x - board * SCREEN_SIZE.x) / size) as i16, y: (y / size) as i16};
+    if y < 0.0 || coord.x >= layout.columns || coord.y >= layout.rows {
+        return None
+    }
+    Some((board as usize, coord))
 }
 
-struct Game {
-    grid: Grid,
-    timing: Timer,
+fn qs_color(color: Color) -> graphics::Color {
+    let (r, g, b) = color.rgb();
+    graphics::Color::from_rgba(r, g, b, 1.0)
 }
 
-impl Game {
-    fn init(grid: Grid, timing: Timer) -> Self {
-        Game {
-            grid,
-            timing,
-        }
-    }
+fn draw_rgb(window: &mut Window, rect: Rectangle, (r, g, b): (u8, u8, u8), alpha: f32) {
+    window.draw(&rect, graphics::Background::Col(graphics::Color::from_rgba(r, g, b, alpha)));
 }
 
-impl State for Game {
-    fn new() -> Result<Self> {
-        let pieces: [Tetrinome; NUM_PIECES] = [
-            Tetrinome::from_piece(PieceKind::I),
-            Tetrinome::from_piece(PieceKind::O),
-            Tetrinome::from_piece(PieceKind::L),
-            Tetrinome::from_piece(PieceKind::T),
-            Tetrinome::from_piece(PieceKind::Z),
-            Tetrinome::from_piece(PieceKind::S),
-            Tetrinome::from_piece(PieceKind::J),
-        ];
-        unsafe {
-            PIECES = Some(pieces);
-        }
+const GRADIENT_STEPS: usize = 50; // bands the gradient background is drawn in
+const LOCK_PITCHES: usize = 5; // lock sounds from the floor to the top, static/lock_0.wav the lowest
+const BACKGROUND_IMAGE_DIM: f32 = 0.6; // opacity of the black drawn over a background image so it doesn't compete with the blocks
 
-        // // determine pixel size based on display height
-        // unsafe {
-        //     let display_height = event::EventsLoop::new().get_primary_monitor().get_dimensions().height;
-        //     PIXEL_SIZE = Some((display_height * 0.9) as i16 / Grid::HEIGHT);
-        // }
-        
-        let grid = Grid::new();
+const EDGE_ALPHA: f32 = 0.6; // of the black edge around shapes drawn with --connected
+const GHOST_BRIGHTNESS: f32 = 0.5; // of the piece's colors in the dimmed shadow piece
+const MONO_HUE: (f32, f32, f32) = (0.0, 0.68, 1.0); // aqua
+const MONO_DARKEST: f32 = 0.25; // brightness of the hue on the floor
+
+// the mono shade of the hue for the row, full brightness at the top darkening towards the floor
+fn mono_color(y: i16, rows: i16) -> graphics::Color {
+    let depth = f32::from(y.max(0).min(rows - 1)) / f32::from((rows - 1).max(1));
+    let brightness = 1.0 - (1.0 - MONO_DARKEST) * depth;
+    graphics::Color{r: MONO_HUE.0 * brightness, g: MONO_HUE.1 * brightness, b: MONO_HUE.2 * brightness, a: 1.0}
+}
 
-        // create event handler instance
-        let game = Self::init(grid, Timer::default());
-        Ok(game)
+// draws the piece kind's pattern in a darker shade over the bone, inset from its edges
+fn draw_pattern(window: &mut Window, bone: &Bone, offset: (i16, i16), size: i16) {
+    let unit = size / (PATTERN_SIZE as i16 + 2); // one unit of margin on each side
+    let inset = (size - unit * PATTERN_SIZE as i16) / 2;
+    let color = qs_color(Color::Black).with_alpha(0.5);
+    for (y, row) in bone.kind.pattern().iter().enumerate() {
+        for (x, unit_char) in row.chars().enumerate() {
+            if unit_char == 'x' {
+                let rect = Rectangle::new(
+                    (bone.coord.x * size + offset.0 + inset + x as i16 * unit, bone.coord.y * size + offset.1 + inset + y as i16 * unit),
+                    (unit, unit),
+                );
+                window.draw(&rect, graphics::Background::Col(color));
+            }
+        }
     }
+}
 
-    fn event(&mut self, event: &Event, _window: &mut Window) -> Result<()> {
-        if let Event::Key(key, button_state) = event {
-            if let ButtonState::Pressed = button_state {
-                let key = *key;
-                match key {
-                    Key::Space => self.grid.finish_drop(),
-                    Key::Q => self.grid.blocks.clear(),
-                    _ => {self.grid.move_if(key.into(), key.into());},
-                };
+// draws a frame around each of the piece's cells, or around the piece as a whole when connected
+fn draw_outline(window: &mut Window, piece: &Tetrinome, offset: (i16, i16), size: i16, color: graphics::Color, connected: bool) {
+    let joined = |_: &Bone, coord: &Coord| connected && piece.bones().iter().any(|bone| bone.coord.x == coord.x && bone.coord.y == coord.y );
+    draw_edges(window, piece.bones(), joined, offset, size, color);
+}
+
+// draws the sides of each bone that don't touch a bone it's joined to, joined is checked once per side so it should be a lookup
+fn draw_edges(window: &mut Window, bones: &[Bone], joined: impl Fn(&Bone, &Coord) -> bool, offset: (i16, i16), size: i16, color: graphics::Color) {
+    let thickness = (size / 8).max(1);
+    for bone in bones.iter() {
+        let (x, y) = (bone.coord.x * size + offset.0, bone.coord.y * size + offset.1);
+        let Coord{x: col, y: row} = bone.coord;
+        let edges = [
+            (Coord{x: col, y: row - 1}, (x, y), (size, thickness)),
+            (Coord{x: col, y: row + 1}, (x, y + size - thickness), (size, thickness)),
+            (Coord{x: col - 1, y: row}, (x, y), (thickness, size)),
+            (Coord{x: col + 1, y: row}, (x + size - thickness, y), (thickness, size)),
+        ];
+        for (neighbor, pos, edge_size) in edges.iter() {
+            if !joined(bone, neighbor) {
+                window.draw(&Rectangle::new(*pos, *edge_size), graphics::Background::Col(color));
             }
         }
-        Ok(())
-    } 
-
-    // frames updated every MILLIS_PER_UPDATE
-    fn update(&mut self, _window: &mut Window) -> Result<()> {
-        self.timing.update();
-        
-        self.grid.blocks.finish_clear(); // checks whether there are lines to clear
-
-        if self.timing.fall() {
-            #[cfg(not(target_arch="wasm32"))]
-            {
-                let old_time = self.timing.test;
-                use std::time::{Instant};
-                self.timing.test = Instant::now();
-                println!("{:?}", self.timing.test - old_time);
+    }
+}
+
+// draws a black square in the middle of the bone's cell
+fn draw_bomb(window: &mut Window, bone: &Bone, offset: (i16, i16), size: i16) {
+    let bomb_size = size / 2;
+    let inset = (size - bomb_size) / 2;
+    let rect = Rectangle::new(
+        (bone.coord.x * size + offset.0 + inset, bone.coord.y * size + offset.1 + inset),
+        (bomb_size, bomb_size),
+    );
+    window.draw(&rect, graphics::Background::Col(qs_color(Color::Black)));
+}
+
+// draws the popups centered over the board rising as they fade, each pushed below any earlier popup it would overlap
+fn draw_popups(window: &mut Window, popups: &[Popup], layout: &Layout) {
+    let mut drawn: Vec<(i16, i16)> = Vec::with_capacity(popups.len()); // top and height in pixels
+    for popup in popups.iter() {
+        let height = popup.lines().len() as i16 * POPUP_LINE_HEIGHT;
+        let mut top = popup.row() * layout.pixel_size + layout.top - (popup.progress() * POPUP_RISE) as i16;
+        for (drawn_top, drawn_height) in drawn.iter() {
+            if top < drawn_top + drawn_height && *drawn_top < top + height {
+                top = drawn_top + drawn_height;
             }
-            self.grid.move_if(Direction::Down, Rotation::None);
         }
+        drawn.push((top, height));
 
-        Ok(())
+        let color = qs_color(Color::White).with_alpha(1.0 - popup.progress());
+        for (i, line) in popup.lines().iter().enumerate() {
+            let x = (layout.board_width() - text_width(line, POPUP_TEXT_SCALE)) / 2;
+            draw_text(window, line, (x, top + i as i16 * POPUP_LINE_HEIGHT), POPUP_TEXT_SCALE, color);
+        }
     }
+}
 
-    fn draw(&mut self, window: &mut Window) -> Result<()> {
-        window.clear(Color::Black.into())?;
+// draws a line of text at the top of the sidebar, above the stats panel
+fn draw_readout(window: &mut Window, layout: &Layout, line: i16, text: &str) {
+    let x = layout.board_width() + SIDEBAR_MARGIN;
+    draw_text(window, text, (x, readout_y(line)), READOUT_TEXT_SCALE, qs_color(Color::White));
+}
 
-        self.grid.draw(window)?;
+// top of the readout line in pixels
+fn readout_y(line: i16) -> i16 {
+    SIDEBAR_MARGIN + line * READOUT_LINE_HEIGHT
+}
 
-        Ok(())
+// the first next piece is drawn at the mini size and the ones after it smaller so a long preview fits
+fn next_mini_size(i: usize) -> i16 {
+    if i == 0 { PIXEL_SIZE / 2 } else { PIXEL_SIZE / 3 }
+}
+
+// the space taken by a next piece in the preview, pieces are at most 3 minis tall
+fn next_slot_height(i: usize) -> i16 {
+    3 * next_mini_size(i) + SIDEBAR_MARGIN / 2
+}
+
+// the piece moved back to the origin from its spawn position or wherever it is on the board, for drawing in the sidebar
+fn at_origin(piece: &Tetrinome) -> Tetrinome {
+    let mut piece = piece.clone();
+    let min_x = piece.bones().iter().map(|bone| bone.coord.x ).min().unwrap();
+    let min_y = piece.bones().iter().map(|bone| bone.coord.y ).min().unwrap();
+    piece.trans_change(&Coord{x: -min_x, y: -min_y});
+    piece
+}
+
+// draws the piece's bones from its layout coords at a reduced size with the top left at pos
+fn draw_mini_piece(window: &mut Window, piece: &Tetrinome, pos: (i16, i16), mini_size: i16, palette: &Palette) {
+    for bone in piece.bones().iter() {
+        let rect = Rectangle::new(
+            (pos.0 + bone.coord.x * mini_size, pos.1 + bone.coord.y * mini_size),
+            (mini_size, mini_size),
+        );
+        window.draw(&rect, graphics::Background::Col(qs_color(palette.color(bone))));
     }
 }
 
-const SCREEN_HEIGHT: i16 = 500;
-const PIXEL_SIZE: i16 = SCREEN_HEIGHT as i16 / Grid::HEIGHT;
-const SCREEN_SIZE: Vector = Vector{x: (Grid::WIDTH * PIXEL_SIZE) as f32, y: SCREEN_HEIGHT as f32};
-// // prevents having to put "unsafe" anywhere where PIXEL_SIZE is needed
-// fn get_pixel_size() -> i16 {
-//     unsafe {
-//         if let Some(pixel_size) = PIXEL_SIZE {
-//             pixel_size
-//         } else {
-//             panic!("pixel size not initialized!");
-//         }
-//     }
-// }
-fn get_pixel_size() -> i16 {
-    PIXEL_SIZE
+// the lock sound at the pitch, in the static folder
+fn lock_sound_path(pitch: usize) -> String {
+    format!("lock_{}.wav", pitch)
+}
+
+// a new game of the config, picking up the --load save if there is one, a save that doesn't fit the config starts a new game
+fn new_game(config: Config) -> Game {
+    let save = config.load.clone();
+    let mut game = Game::new(config);
+    if let Some(save) = save {
+        if let Err(err) = game.load(&save) {
+            eprintln!("{}", err);
+            game = Game::new(game.config().clone());
+        }
+    }
+    game
 }
 
+// parsed before the window is created since the update rate is needed for its settings, read again by the game
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
 fn main() {
-    run::<Game>("Tetrust", SCREEN_SIZE, 
+    let config = Config::from_args();
+    set_updates_per_sec(config.updates_per_sec);
+    #[cfg(feature = "bench")]
+    {
+        if config.bench_placements > 0 {
+            tetrust::bench::run(&config);
+            return
+        }
+    }
+    if config.print_sequence > 0 {
+        let n = config.print_sequence;
+        println!("{}", Game::new(config).grid().upcoming_pieces(n));
+        return
+    }
+    let title = config.title.clone();
+    let screen_size = Vector{x: SCREEN_SIZE.x * config.players as f32, y: SCREEN_SIZE.y}; // the boards side by side
+    let _ = CONFIG.set(config); // only set here
+
+    run::<Tetrust>(&title, screen_size,
         Settings{
-            update_rate: MILLIS_PER_UPDATE,
+            update_rate: millis_per_update(),
             ..Settings::default()
         }
     );
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_rows_shrink_the_cells_to_fit() {
+        let game = Game::new(Config{hidden_rows: 5, ..Config::default()});
+        let hidden = Layout::new(game.grid(), false);
+        assert_eq!((hidden.pixel_size, hidden.top), (PIXEL_SIZE, 0));
+
+        let shown = Layout::new(game.grid(), true);
+        assert_eq!(shown.pixel_size, SCREEN_HEIGHT / 25);
+        assert_eq!(shown.top, 5 * shown.pixel_size);
+        assert!(shown.top + shown.rows * shown.pixel_size <= SCREEN_HEIGHT);
+    }
+
+    #[test]
+    fn f11_toggles_the_hidden_rows_for_every_board() {
+        assert_eq!(action(Key::F11), Some(Action::ToggleHiddenRows));
+        assert_eq!(versus_action(Key::F11), Some((None, Action::ToggleHiddenRows)));
+    }
+}
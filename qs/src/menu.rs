@@ -0,0 +1,72 @@
+// the menu shown before a game, the mode and the starting level picked in it are written to the config the game is played with
+use crate::config::{Config, Mode, MAX_START_LEVEL};
+use crate::primitives::Action;
+
+const MODES: [Mode; 3] = [Mode::Marathon, Mode::Practice, Mode::Survival];
+
+// the settings the menu changes, top to bottom
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuItem {
+    Mode,
+    Level,
+}
+
+#[derive(Clone, Debug)]
+pub struct Menu {
+    pub selected: MenuItem,
+    pub mode: Mode,
+    pub level: u32,
+}
+
+impl Menu {
+    pub const ITEMS: [MenuItem; 2] = [MenuItem::Mode, MenuItem::Level];
+
+    // starts on the config's settings so the flags pick the defaults
+    pub fn new(config: &Config) -> Self {
+        Self {
+            selected: MenuItem::Mode,
+            mode: config.mode,
+            level: config.start_level,
+        }
+    }
+
+    // up and down move the selection and left and right change the selected setting, returns true once the game should start
+    pub fn press(&mut self, action: Action) -> bool {
+        match action {
+            Action::RotateCW => self.select(-1), // the up key
+            Action::Down => self.select(1),
+            Action::Left => self.change(-1),
+            Action::Right => self.change(1),
+            Action::Confirm | Action::Drop => return true,
+            _ => (),
+        }
+        false
+    }
+
+    fn select(&mut self, step: isize) {
+        let i = Self::ITEMS.iter().position(|item| *item == self.selected ).unwrap_or(0) as isize;
+        self.selected = Self::ITEMS[(i + step).max(0).min(Self::ITEMS.len() as isize - 1) as usize];
+    }
+
+    // the mode wraps around, the level stops at either end
+    fn change(&mut self, step: isize) {
+        match self.selected {
+            MenuItem::Mode => {
+                let i = MODES.iter().position(|mode| *mode == self.mode ).unwrap_or(0) as isize;
+                self.mode = MODES[(i + step).rem_euclid(MODES.len() as isize) as usize];
+            },
+            MenuItem::Level => self.level = (self.level as isize + step).max(1).min(MAX_START_LEVEL as isize) as u32,
+        }
+    }
+
+    // the text of each item and whether it's selected, for the frontends to draw
+    pub fn lines(&self) -> Vec<(String, bool)> {
+        Self::ITEMS.iter().map(|item| {
+            let text = match item {
+                MenuItem::Mode => format!("mode {}", self.mode.name()),
+                MenuItem::Level => format!("level {}", self.level),
+            };
+            (text, *item == self.selected)
+        }).collect()
+    }
+}
@@ -0,0 +1,53 @@
+// colors picked in game for piece kinds, ie. to match a stream overlay, drawn instead of a bone's own color by its kind
+// so the current, next, held and locked pieces all change together, garbage and clearing blocks keep theirs
+use super::{Bone, Tetrinome, PieceKind, NUM_PIECES};
+use crate::primitives::Color;
+
+#[derive(Clone, Debug, Default)]
+pub struct Palette {
+    colors: [Option<Color>; NUM_PIECES], // indexed by PieceKind, None draws the kind's own color
+    selected: Option<PieceKind>, // the kind the recolor key changes, None until one is picked
+}
+
+impl Palette {
+    // the color the bone is drawn in
+    pub fn color(&self, bone: &Bone) -> Color {
+        self.colors[bone.kind as usize].unwrap_or(bone.color)
+    }
+
+    // the bone in the color it's drawn in
+    pub fn recolor(&self, bone: &Bone) -> Bone {
+        Bone{color: self.color(bone), ..*bone}
+    }
+
+    pub fn recolor_all(&self, bones: &[Bone]) -> Vec<Bone> {
+        bones.iter().map(|bone| self.recolor(bone) ).collect()
+    }
+
+    pub fn selected(&self) -> Option<PieceKind> {
+        self.selected
+    }
+
+    // picks the piece after the selected one, from the pieces that spawn
+    pub fn select_next(&mut self, pieces: &[Tetrinome]) {
+        let i = self.selected.and_then(|kind| pieces.iter().position(|piece| piece.kind == kind ) ).map_or(0, |i| i + 1);
+        self.selected = pieces.get(i % pieces.len().max(1)).map(Tetrinome::kind);
+    }
+
+    // moves the selected kind to the next color, skipping black which can't be seen on the board
+    pub fn cycle(&mut self, pieces: &[Tetrinome]) {
+        if let Some(kind) = self.selected {
+            let own = pieces.iter().find(|piece| piece.kind == kind ).and_then(|piece| piece.bones.first() ).map_or(Color::Gray, |bone| bone.color );
+            let mut color = self.colors[kind as usize].unwrap_or(own).next_color();
+            while let Color::Black = color {
+                color = color.next_color();
+            }
+            self.colors[kind as usize] = if color == own { None } else { Some(color) };
+        }
+    }
+
+    // back to every piece's own color
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
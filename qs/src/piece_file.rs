@@ -0,0 +1,129 @@
+// custom piece sets read from a text file for the custom variant, each piece a header line and its layout in the `x`/`o` format
+//
+// # lines starting with a hash are comments
+// standard           deals the standard seven pieces alongside the custom ones
+// piece plus r       a name and optionally the letter of its color from Blocks::to_ascii
+// -x-
+// xox                `o` is the bone the piece rotates around, pieces without one rotate around the bone nearest their middle
+// -x-
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+
+use super::{Tetrinome, PieceKind, Error, CUSTOM_KINDS};
+use crate::config::Variant;
+use crate::primitives::Color;
+
+const MAX_LAYOUT_SIZE: usize = 5; // rows and columns, so a piece fits the preview and the spawn rows
+const COLORS: [Color; 7] = [Color::Aqua, Color::Green, Color::Yellow, Color::Red, Color::Blue, Color::Pink, Color::White]; // by the order of the pieces without a color
+
+pub fn load(path: &str) -> Result<Vec<Tetrinome>, Error> {
+    let text = fs::read_to_string(path).map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path, err)) )?;
+    parse(&text).map_err(|err| match err {
+        Error::InvalidLayout(reason) => Error::InvalidLayout(format!("{} {}", path, reason)),
+        err => err,
+    })
+}
+
+// the pieces of the set in the order they're listed, the standard pieces first when they're included
+pub fn parse(text: &str) -> Result<Vec<Tetrinome>, Error> {
+    let mut pieces = Vec::new();
+    let mut names = HashSet::new();
+    let mut current: Option<(usize, String, Option<Color>, Vec<&str>)> = None; // the header's line, the name, the color and the rows so far
+    let mut custom = 0;
+
+    let lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()) ).filter(|(_, line)| !line.starts_with('#') );
+    for (line, content) in lines.chain(std::iter::once((0, ""))) { // a last blank line ends the last piece
+        let mut words = content.split_whitespace();
+        let header = match words.next() {
+            Some("standard") => Some(None),
+            Some("piece") => Some(Some(words)),
+            _ => None,
+        };
+        if header.is_none() && !content.is_empty() {
+            match &mut current {
+                Some((_, _, _, rows)) => rows.push(content),
+                None => return Err(invalid(line, format!("expected 'piece <name>' or 'standard', found '{}'", content))),
+            }
+            continue
+        }
+        if let Some((header_line, name, color, rows)) = current.take() {
+            if custom == CUSTOM_KINDS.len() {
+                return Err(invalid(header_line, format!("more than {} custom pieces", CUSTOM_KINDS.len())))
+            }
+            let color = color.unwrap_or(COLORS[custom % COLORS.len()]);
+            let piece = layout(&rows, color, CUSTOM_KINDS[custom]).map_err(|reason| invalid(header_line, format!("piece '{}' {}", name, reason)) )?;
+            pieces.push(piece);
+            custom += 1;
+        }
+        match header {
+            Some(None) => pieces.extend(PieceKind::of_variant(Variant::Standard).into_iter().map(Tetrinome::from_piece)),
+            Some(Some(mut words)) => {
+                let name = words.next().ok_or_else(|| invalid(line, "expected a name after 'piece'".to_string()) )?;
+                if !names.insert(name.to_string()) {
+                    return Err(invalid(line, format!("there's already a piece named '{}'", name)))
+                }
+                let color = match words.next() {
+                    Some(letter) => Some(letter.chars().next().and_then(Color::from_letter).filter(|_| letter.len() == 1 )
+                        .ok_or_else(|| invalid(line, format!("expected a color letter, found '{}'", letter)) )?),
+                    None => None,
+                };
+                current = Some((line, name.to_string(), color, Vec::new()));
+            },
+            None => (),
+        }
+    }
+    if pieces.is_empty() {
+        return Err(Error::InvalidLayout("has no pieces".to_string()))
+    }
+    Ok(pieces)
+}
+
+fn invalid(line: usize, reason: String) -> Error {
+    Error::InvalidLayout(format!("line {}: {}", line, reason))
+}
+
+// the piece for the rows of a layout, checked so it can spawn and be drawn like the built in pieces
+fn layout(rows: &[&str], color: Color, kind: PieceKind) -> Result<Tetrinome, String> {
+    let width = rows.first().map_or(0, |row| row.len() );
+    if rows.is_empty() {
+        return Err("has no layout".to_string())
+    }
+    if rows.len() > MAX_LAYOUT_SIZE || width > MAX_LAYOUT_SIZE {
+        return Err(format!("is larger than {} by {}", MAX_LAYOUT_SIZE, MAX_LAYOUT_SIZE))
+    }
+    if rows.iter().any(|row| row.len() != width ) {
+        return Err("has rows of different lengths".to_string())
+    }
+    if let Some(c) = rows.iter().flat_map(|row| row.chars() ).find(|c| !"xo-".contains(*c) ) {
+        return Err(format!("has '{}', expected x, o or -", c))
+    }
+    if rows.iter().flat_map(|row| row.chars() ).filter(|c| *c == 'o' ).count() > 1 {
+        return Err("has more than one o".to_string())
+    }
+    let piece = Tetrinome::from_layout(rows.join("\n"), color, kind);
+    if piece.bones.is_empty() {
+        return Err("has no bones".to_string())
+    }
+    if !connected(&piece) {
+        return Err("has bones that don't touch the rest".to_string())
+    }
+    Ok(piece)
+}
+
+// whether every bone can be reached from the first through bones sharing an edge
+fn connected(piece: &Tetrinome) -> bool {
+    let mut reached = vec![0];
+    let mut i = 0;
+    while i < reached.len() {
+        let coord = piece.bones[reached[i]].coord;
+        for (j, bone) in piece.bones.iter().enumerate() {
+            let touching = (bone.coord.x - coord.x).abs() + (bone.coord.y - coord.y).abs() == 1;
+            if touching && !reached.contains(&j) {
+                reached.push(j);
+            }
+        }
+        i += 1;
+    }
+    reached.len() == piece.bones.len()
+}
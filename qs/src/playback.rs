@@ -0,0 +1,163 @@
+// steps through a recorded game for analysis, the game is simulated again from the replay's seed and inputs so it plays out as it was recorded
+// seeking back restarts from the latest snapshot before the target rather than from the first update
+use crate::{Game, GameState};
+use crate::config::Config;
+use crate::primitives::Action;
+use crate::replay::Replay;
+use crate::timing::millis_per_update;
+
+const SNAPSHOT_PIECES: u32 = 10; // pieces between snapshots, more makes seeking back slower and fewer keeps more games around
+
+pub struct Playback {
+    replay: Replay,
+    next_input: usize, // the first of the replay's inputs that hasn't been applied
+    snapshots: Vec<(Game, usize)>, // the game every SNAPSHOT_PIECES pieces and its next input, the first from before the first update
+    piece_frames: Vec<u64>, // the frame each piece spawned by so far, by its index
+    paused: bool,
+}
+
+impl Playback {
+    // the game the replay is played in, with the replay's seed and without the menu so the inputs line up with the frames they were recorded on
+    pub fn new(config: &Config, replay: Replay) -> (Self, Game) {
+        let mut config = config.clone();
+        config.seed = replay.seed;
+        config.menu = false;
+        let game = Game::new(config);
+        let playback = Self {
+            replay,
+            next_input: 0,
+            snapshots: vec![(game.clone(), 0)],
+            piece_frames: vec![0],
+            paused: false,
+        };
+        (playback, game)
+    }
+
+    // the pause key stops and starts the playback, right and left step a piece forward and back and instant drop skips to the next line clear
+    // returns true once the viewer should close
+    pub fn press(&mut self, action: Action, game: &mut Game) -> bool {
+        match action {
+            Action::Quit => return true,
+            Action::Pause => self.paused = !self.paused,
+            Action::Right => {
+                let piece = game.grid().spawned();
+                self.advance_while(game, |game| game.grid().spawned() == piece );
+                self.paused = true;
+            },
+            Action::Left => {
+                let frame = self.piece_frames.iter().rev().find(|frame| **frame < game.frame() ).copied().unwrap_or(0);
+                self.seek(game, frame);
+                self.paused = true;
+            },
+            Action::Drop => {
+                let lines = game.grid().score().lines;
+                self.advance_while(game, |game| game.grid().score().lines == lines );
+                self.paused = true;
+            },
+            _ => (),
+        }
+        false
+    }
+
+    // plays an update unless paused
+    pub fn update(&mut self, game: &mut Game) {
+        if !self.paused && !self.finished(game) {
+            self.advance(game);
+        }
+    }
+
+    // puts the game at the frame, simulating it from the latest snapshot before the frame when it's behind the game
+    pub fn seek(&mut self, game: &mut Game, frame: u64) {
+        if frame < game.frame() {
+            let (snapshot, next_input) = self.snapshots.iter().rev().find(|(snapshot, _)| snapshot.frame() <= frame ).unwrap_or(&self.snapshots[0]);
+            *game = snapshot.clone();
+            self.next_input = *next_input;
+        }
+        self.advance_while(game, |game| game.frame() < frame );
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    // whether the recording has run out or the game is over, nothing after it was recorded
+    pub fn finished(&self, game: &Game) -> bool {
+        let over = matches!(game.state(), GameState::Victory | GameState::GameOver);
+        over || self.next_input >= self.replay.inputs.len()
+    }
+
+    fn advance_while(&mut self, game: &mut Game, condition: impl Fn(&Game) -> bool) {
+        while condition(game) && !self.finished(game) {
+            self.advance(game);
+        }
+    }
+
+    // applies the inputs recorded before the game's next update and plays it
+    fn advance(&mut self, game: &mut Game) {
+        while let Some(input) = self.replay.inputs.get(self.next_input).filter(|input| input.frame == game.frame() ) {
+            if input.pressed {
+                game.press(input.action); // a recorded quit only opens the confirmation, which the recording goes on to cancel or confirm
+            } else {
+                game.release(input.action);
+            }
+            self.next_input += 1;
+        }
+        game.tick(millis_per_update());
+
+        let spawned = game.grid().spawned();
+        if spawned as usize > self.piece_frames.len() {
+            self.piece_frames.push(game.frame());
+            let taken = self.snapshots.last().is_some_and(|(snapshot, _)| snapshot.frame() >= game.frame() );
+            if spawned.is_multiple_of(SNAPSHOT_PIECES) && !taken {
+                self.snapshots.push((game.clone(), self.next_input));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a game tapping the piece a few columns either way before dropping it, with the board's hash after each update
+    fn record(config: &Config) -> (Game, Vec<u64>) {
+        let mut game = Game::new(config.clone());
+        let mut hashes = Vec::new();
+        for frame in 0..560u64 {
+            let (piece, step) = (frame / 40, frame % 40);
+            let (direction, taps) = (if piece % 2 == 0 { Action::Left } else { Action::Right }, (piece / 2) % 5);
+            if step < taps * 2 {
+                if step % 2 == 0 {
+                    game.press(direction);
+                } else {
+                    game.release(direction);
+                }
+            } else if step == 30 {
+                game.press(Action::Drop);
+            } else if step == 31 {
+                game.release(Action::Drop);
+            }
+            game.tick(millis_per_update());
+            hashes.push(game.grid().state_hash());
+        }
+        (game, hashes)
+    }
+
+    #[test]
+    fn seeking_matches_a_fresh_simulation() {
+        let config = Config{menu: false, seed: 7, ..Config::default()};
+        let (recorded, hashes) = record(&config);
+        let (mut playback, mut game) = Playback::new(&config, recorded.replay().clone());
+
+        playback.seek(&mut game, 540);
+        assert_eq!(game.frame(), 540);
+        assert_eq!(game.grid().state_hash(), hashes[539]);
+        assert!(playback.snapshots.len() > 1);
+
+        for frame in [450, 250] { // back from the snapshot after ten pieces and from the start
+            playback.seek(&mut game, frame);
+            assert_eq!(game.frame(), frame);
+            assert_eq!(game.grid().state_hash(), hashes[frame as usize - 1]);
+        }
+    }
+}
@@ -1,24 +1,15 @@
+use std::fmt;
+use std::str::FromStr;
 use std::ops::{ Add, AddAssign };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use rand::distributions::{Distribution, Standard};
 
-use quicksilver::{
-    graphics::{Color as QSColor},
-    input::{Key}
-};
-
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Coord {
     pub x: i16,
     pub y: i16
 }
 
-impl Default for Coord {
-    fn default() -> Self {
-        Self{x:0, y:0}
-    }
-}
-
 impl From<Direction> for Coord {
     fn from(dir: Direction) -> Self {
         match dir {
@@ -55,13 +46,12 @@ impl Coord {
         Pos (self.x + self.y * width)
     }
 
-    pub fn rand_x_offset(x_range: (i16, i16), y: i16) -> Self {
-        let mut rng = thread_rng();
+    pub fn rand_x_offset<R: Rng>(rng: &mut R, x_range: (i16, i16), y: i16) -> Self {
         let i = rng.gen_range(x_range.0, x_range.1);
 
         Self {
-            x: i.into(),
-            y: y,
+            x: i,
+            y,
         }
     }
 }
@@ -69,9 +59,9 @@ impl Coord {
 #[derive(Copy, Clone, Debug)]
 pub struct Pos(pub i16); // grid_index refers to the index in the Board grid array
 
-impl Into<usize> for Pos {
-    fn into(self) -> usize {
-        self.0 as usize
+impl From<Pos> for usize {
+    fn from(pos: Pos) -> Self {
+        pos.0 as usize
     }
 }
 
@@ -84,13 +74,23 @@ impl From<usize> for Pos {
 impl Pos {
     pub fn pos_to_coord(&self, width: i16) -> Coord {
         Coord {
-            x: self.0 % width as i16,
-            y: self.0 / width as i16,
+            x: self.0 % width,
+            y: self.0 / width,
         }
     }
 }
 
+// returned when accessing a coord that isn't on the grid
 #[derive(Copy, Clone, Debug)]
+pub struct OutOfBounds(pub Coord);
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "coord {},{} is off the grid", (self.0).x, (self.0).y)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Direction {
     Down,
     Left, 
@@ -98,13 +98,13 @@ pub enum Direction {
     None
 }
 
-impl From<Key> for Direction {
-    fn from(key: Key) -> Self {
-        match key {
+impl From<Action> for Direction {
+    fn from(action: Action) -> Self {
+        match action {
             // move in a direction
-            Key::Down => Direction::Down,
-            Key::Left => Direction::Left,
-            Key::Right => Direction::Right,
+            Action::Down => Direction::Down,
+            Action::Left => Direction::Left,
+            Action::Right => Direction::Right,
             _ => Direction::None
         }
     }
@@ -142,7 +142,7 @@ impl Direction {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Rotation {
     CW,
     CCW,
@@ -161,18 +161,96 @@ impl Distribution<Rotation> for Standard {
     }
 }
 
-impl From<Key> for Rotation {
-    fn from(key: Key) -> Self {
-        match key {
-            // move in a direction
-            Key::Z => Rotation::CCW,
-            Key::X => Rotation::CW,
-            Key::Up => Rotation::CW,
+impl From<Action> for Rotation {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::RotateCCW => Rotation::CCW,
+            Action::RotateCW => Rotation::CW,
             _ => Rotation::None
         }
     }
 }
 
+// what an input asks the game to do, each frontend maps its own keys to these
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    Left,
+    Right,
+    Down,
+    RotateCW,
+    RotateCCW,
+    Drop, // instant drop
+    Hold, // swaps the current piece into the hold box, once per piece
+    Quit, // asks to confirm quitting, or cancels the confirmation
+    Pause, // pauses the game or resumes it
+    Confirm,
+    Cancel, // also advances one update in step mode
+    Restart, // only with debug on
+    ClearBoard, // empties the board keeping the score and stats, only with debug on
+    Undo, // only in practice mode
+    TogglePatterns,
+    ToggleMono,
+    ToggleCoords,
+    ToggleAdvice,
+    ToggleStep, // freezes the game so it advances an update at a time, only with debug on
+    SelectRecolor, // picks the next piece kind to recolor
+    Recolor, // changes the picked piece kind's color
+    ResetColors, // every piece kind back to its own color
+    Ghost, // shows the shadow piece while held, with the ghost on demand
+    ToggleGhost, // cycles the ghost off, always and on demand
+    ToggleMirror, // flips the board left to right and swaps the controls to match
+    ToggleHiddenRows, // draws the hidden rows above the board or stops, only the frontend's layout changes
+}
+
+impl Action {
+    const ALL: [Action; 26] = [
+        Action::Left, Action::Right, Action::Down, Action::RotateCW, Action::RotateCCW, Action::Drop, Action::Hold, Action::Quit, Action::Pause, Action::Confirm,
+        Action::Cancel, Action::Restart, Action::Undo, Action::TogglePatterns, Action::ToggleMono, Action::ToggleCoords, Action::ToggleAdvice,
+        Action::ClearBoard, Action::ToggleStep, Action::SelectRecolor, Action::Recolor, Action::ResetColors, Action::Ghost, Action::ToggleGhost, Action::ToggleMirror,
+        Action::ToggleHiddenRows,
+    ];
+
+    // the name written in replays
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Down => "down",
+            Action::RotateCW => "rotate_cw",
+            Action::RotateCCW => "rotate_ccw",
+            Action::Drop => "drop",
+            Action::Hold => "hold",
+            Action::Quit => "quit",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Restart => "restart",
+            Action::ClearBoard => "clear_board",
+            Action::Undo => "undo",
+            Action::TogglePatterns => "toggle_patterns",
+            Action::ToggleMono => "toggle_mono",
+            Action::ToggleCoords => "toggle_coords",
+            Action::ToggleAdvice => "toggle_advice",
+            Action::ToggleStep => "toggle_step",
+            Action::SelectRecolor => "select_recolor",
+            Action::Recolor => "recolor",
+            Action::ResetColors => "reset_colors",
+            Action::Ghost => "ghost",
+            Action::ToggleGhost => "toggle_ghost",
+            Action::ToggleMirror => "toggle_mirror",
+            Action::ToggleHiddenRows => "toggle_hidden_rows",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL.iter().cloned().find(|action| action.name() == s ).ok_or_else(|| format!("unknown action '{}'", s))
+    }
+}
+
 impl Rotation {
     pub fn to_dir(&self) -> Direction {
         match self {
@@ -183,9 +261,9 @@ impl Rotation {
     }
 }
 
-impl Into<Direction> for Rotation {
-    fn into(self) -> Direction {
-        match self {
+impl From<Rotation> for Direction {
+    fn from(rot: Rotation) -> Self {
+        match rot {
             Rotation::CW => Direction::Right,
             Rotation::CCW => Direction::Left,
             _ => Direction::None
@@ -193,6 +271,46 @@ impl Into<Direction> for Rotation {
     }
 }
 
+// the four rotation states of a tetrinome, Spawn being the layout it was created with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Orientation {
+    Spawn,
+    Right,
+    Two,
+    Left,
+}
+
+impl Orientation {
+    pub fn rotate(&self, rot: &Rotation) -> Self {
+        match (self, rot) {
+            (Orientation::Spawn, Rotation::CW) | (Orientation::Two, Rotation::CCW) => Orientation::Right,
+            (Orientation::Right, Rotation::CW) | (Orientation::Left, Rotation::CCW) => Orientation::Two,
+            (Orientation::Two, Rotation::CW) | (Orientation::Spawn, Rotation::CCW) => Orientation::Left,
+            (Orientation::Left, Rotation::CW) | (Orientation::Right, Rotation::CCW) => Orientation::Spawn,
+            (orientation, Rotation::None) => *orientation,
+        }
+    }
+
+    // the name written in saves
+    pub fn name(self) -> &'static str {
+        match self {
+            Orientation::Spawn => "spawn",
+            Orientation::Right => "right",
+            Orientation::Two => "two",
+            Orientation::Left => "left",
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Orientation::Spawn, Orientation::Right, Orientation::Two, Orientation::Left].iter().cloned()
+            .find(|orientation| orientation.name() == s ).ok_or_else(|| format!("unknown orientation '{}'", s))
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum Collision {
     Left,
@@ -225,7 +343,8 @@ impl From<Direction> for Collision {
     
 const NUM_COLORS: usize = 8;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
 pub enum Color {
     Black,
     Green,
@@ -235,12 +354,13 @@ pub enum Color {
     Pink,
     White,
     Aqua,
+    Gray, // garbage, not part of the clear animation's cycle
 }
 
 const COLORS: [Color; NUM_COLORS] = [Color::Black, Color::Green, Color::Yellow, Color::Red, Color::Blue, Color::Pink, Color::White, Color::Aqua];
 
 impl Color {
-    fn to_i(&self) -> usize {
+    fn to_i(self) -> usize {
         match self {
             Color::Black => 0,
             Color::Green => 1,
@@ -250,6 +370,7 @@ impl Color {
             Color::Pink => 5,
             Color::White => 6,
             Color::Aqua => 7,
+            Color::Gray => 0, // clears start from the first color
         }
     }
 
@@ -264,19 +385,38 @@ impl Color {
     pub fn next_color(&self) -> Color {
         Color::_next_color(self.to_i())
     }
-}
 
-impl Into<QSColor> for Color {
-    fn into(self) -> QSColor {
+    // the letter a block of the color is written as in a board's text
+    pub fn letter(self) -> char {
         match self {
-            Color::Black => QSColor::from_rgba(0, 0, 0, 1.0),
-            Color::Green => QSColor::from_rgba(0, 255, 34, 1.0),
-            Color::Yellow => QSColor::from_rgba(255, 255, 0, 1.0),
-            Color::Red => QSColor::from_rgba(255, 0, 0, 1.0),
-            Color::Blue => QSColor::from_rgba(0, 0, 255, 1.0),
-            Color::Pink => QSColor::from_rgba(255, 0, 255, 1.0),
-            Color::White => QSColor::from_rgba(255, 255, 255, 1.0),
-            Color::Aqua => QSColor::from_rgba(0, 173, 254, 1.0),
+            Color::Black => 'k',
+            Color::Green => 'g',
+            Color::Yellow => 'y',
+            Color::Red => 'r',
+            Color::Blue => 'b',
+            Color::Pink => 'p',
+            Color::White => 'w',
+            Color::Aqua => 'a',
+            Color::Gray => 'x',
         }
     }
-}
\ No newline at end of file
+
+    pub fn from_letter(letter: char) -> Option<Color> {
+        [Color::Black, Color::Green, Color::Yellow, Color::Red, Color::Blue, Color::Pink, Color::White, Color::Aqua, Color::Gray].iter().cloned().find(|color| color.letter() == letter )
+    }
+
+    // the red, green and blue of the color for frontends to draw with
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Black => (0, 0, 0),
+            Color::Green => (0, 255, 34),
+            Color::Yellow => (255, 255, 0),
+            Color::Red => (255, 0, 0),
+            Color::Blue => (0, 0, 255),
+            Color::Pink => (255, 0, 255),
+            Color::White => (255, 255, 255),
+            Color::Aqua => (0, 173, 254),
+            Color::Gray => (128, 128, 128),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// the inputs of a game by the update they came before, written as text so replays can be shared, diffed and edited by hand
+use std::fmt;
+
+use crate::primitives::Action;
+
+// a press or release of an action before the frame'th update
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Input {
+    pub frame: u64,
+    pub action: Action,
+    pub pressed: bool,
+}
+
+// a game's seed and its inputs in the order they happened, played back with the same config and update rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub inputs: Vec<Input>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, action: Action, pressed: bool) {
+        self.inputs.push(Input{frame, action, pressed});
+    }
+
+    // a `seed` line followed by a `frame:action` line per input, releases are written as `frame:-action`
+    pub fn to_text(&self) -> String {
+        let mut text = format!("seed {}\n", self.seed);
+        for input in self.inputs.iter() {
+            let release = if input.pressed { "" } else { "-" };
+            text.push_str(&format!("{}:{}{}\n", input.frame, release, input.action.name()));
+        }
+        text
+    }
+
+    // reads the text written by to_text, blank lines are skipped
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()) ).filter(|(_, line)| !line.is_empty() );
+
+        let (line, seed) = lines.next().ok_or(ReplayError{line: 1, reason: "expected 'seed <number>'".to_string()})?;
+        let seed = match seed.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["seed", seed] => seed.parse().map_err(|_| ReplayError{line, reason: format!("invalid seed '{}'", seed)})?,
+            _ => return Err(ReplayError{line, reason: "expected 'seed <number>'".to_string()}),
+        };
+
+        let mut replay = Replay::new(seed);
+        for (line, input) in lines {
+            let mut parts = input.splitn(2, ':');
+            let (frame, action) = match (parts.next(), parts.next()) {
+                (Some(frame), Some(action)) => (frame, action),
+                _ => return Err(ReplayError{line, reason: format!("expected 'frame:action', found '{}'", input)}),
+            };
+            let frame: u64 = frame.parse().map_err(|_| ReplayError{line, reason: format!("invalid frame '{}'", frame)})?;
+            if replay.inputs.last().is_some_and(|last| frame < last.frame ) {
+                return Err(ReplayError{line, reason: format!("frame {} is before the frame of the input above it", frame)})
+            }
+            let (pressed, action) = match action.strip_prefix('-') {
+                Some(action) => (false, action),
+                None => (true, action),
+            };
+            let action = action.parse().map_err(|reason| ReplayError{line, reason})?;
+            replay.record(frame, action, pressed);
+        }
+        Ok(replay)
+    }
+}
+
+// the line of a replay that couldn't be read and why
+#[derive(Debug)]
+pub struct ReplayError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "replay line {}: {}", self.line, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Game};
+
+    #[test]
+    fn a_recorded_game_round_trips() {
+        let mut game = Game::new(Config{menu: false, seed: 42, ..Config::default()});
+        for action in [Action::Left, Action::RotateCW, Action::Hold, Action::Drop] {
+            game.press(action);
+            game.tick(16.0);
+            game.release(action);
+            game.tick(16.0);
+        }
+        let replay = game.replay();
+        assert_eq!(replay.inputs.len(), 8);
+        assert_eq!(Replay::from_text(&replay.to_text()).unwrap(), *replay);
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        let error = |text| Replay::from_text(text).unwrap_err().to_string();
+        assert_eq!(error("seed x"), "replay line 1: invalid seed 'x'");
+        assert_eq!(error("seed 1\n\n3:left\nleft"), "replay line 4: expected 'frame:action', found 'left'");
+        assert_eq!(error("seed 1\n3:left\n2:-left"), "replay line 3: frame 2 is before the frame of the input above it");
+        assert!(error("seed 1\n3:jump").starts_with("replay line 2: "));
+    }
+}
@@ -0,0 +1,81 @@
+// the game's random number generator, a pcg32 whose state can be read and restored so a saved game deals the same pieces
+// it stands in for StdRng, whose state is hidden, the sequence for a seed is the same on every platform and version
+use rand::{RngCore, SeedableRng, Error};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const DEFAULT_STREAM: u64 = 0x0a02_bdbf_7bb3_c0a7; // gives the reference implementation's default increment
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64, // odd, picks one of the 2^63 sequences the multiplier steps through
+}
+
+impl Pcg32 {
+    // seeded the way the reference implementation's pcg32_srandom is, so the stream picks the sequence and the seed the start
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self{state: 0, increment: (stream << 1) | 1};
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    // the state and the increment, written to a save and read back by from_state
+    pub fn state(&self) -> (u64, u64) {
+        (self.state, self.increment)
+    }
+
+    // picks up where the rng the state was read from left off, an even increment is made odd like new does
+    pub fn from_state(state: u64, increment: u64) -> Self {
+        Self{state, increment: increment | 1}
+    }
+
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+    }
+}
+
+impl RngCore for Pcg32 {
+    // xsh rr, the high bits xorshifted down and rotated by the top 5 bits
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = u64::from(self.next_u32());
+        (u64::from(self.next_u32()) << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    type Seed = [u8; 16]; // the seed then the stream, little endian
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut seed_bytes = [0; 8];
+        let mut stream_bytes = [0; 8];
+        seed_bytes.copy_from_slice(&seed[..8]);
+        stream_bytes.copy_from_slice(&seed[8..]);
+        Self::new(u64::from_le_bytes(seed_bytes), u64::from_le_bytes(stream_bytes))
+    }
+
+    // the config's seed on the default stream
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed, DEFAULT_STREAM)
+    }
+}
@@ -0,0 +1,249 @@
+// a game saved partway through, written as text like a replay so saves can be shared, diffed and edited by hand
+// the board, the pieces already dealt and the rng's state are kept so the loaded game deals the pieces the saved one would have
+// the current piece starts again from its spawn, the stats, the undo history, the animations and the board's bombs aren't kept
+use std::fmt;
+
+use crate::primitives::{Coord, Orientation};
+
+// a piece in the layout it was dealt in, rebuilt from the loading grid's piece set by its letter
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedPiece {
+    pub letter: char,
+    pub orientation: Orientation,
+    pub cells: Vec<Coord>, // in the order of the piece's bones so it rotates around the same pivot
+    pub bomb: Option<usize>, // the bone carrying a bomb in item mode
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Save {
+    pub rng: (u64, u64), // the state and the increment of the grid's rng
+    pub points: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub garbage_gap: Option<i16>, // the column of the last garbage row's gap, the next row's gap moves from it
+    pub can_hold: bool,
+    pub piece: SavedPiece, // the current piece as it spawned
+    pub next: Vec<SavedPiece>, // the front spawns next
+    pub held: Vec<SavedPiece>, // the front comes back first
+    pub sequence: Option<String>, // the letters left to deal in a scripted game
+    pub board: String, // as written by Blocks::to_ascii
+}
+
+impl SavedPiece {
+    // the letter, the orientation and a `x,y` per bone, a bone carrying a bomb is followed by a `*`
+    fn to_text(&self) -> String {
+        let mut text = format!("{} {}", self.letter, self.orientation.name());
+        for (i, cell) in self.cells.iter().enumerate() {
+            let bomb = if self.bomb == Some(i) { "*" } else { "" };
+            text.push_str(&format!(" {},{}{}", cell.x, cell.y, bomb));
+        }
+        text
+    }
+
+    fn from_text(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let letter = match parts.next().map(|letter| letter.chars().collect::<Vec<_>>() ).as_deref() {
+            Some([letter]) => *letter,
+            _ => return Err(format!("expected a piece letter, found '{}'", text)),
+        };
+        let orientation = parts.next().ok_or_else(|| format!("expected an orientation after '{}'", letter) )?.parse()?;
+        let mut piece = SavedPiece{letter, orientation, cells: Vec::new(), bomb: None};
+        for cell in parts {
+            let (cell, bomb) = match cell.strip_suffix('*') {
+                Some(cell) => (cell, true),
+                None => (cell, false),
+            };
+            let coord = match cell.split(',').map(str::parse).collect::<Vec<_>>().as_slice() {
+                [Ok(x), Ok(y)] => Coord{x: *x, y: *y},
+                _ => return Err(format!("expected 'x,y', found '{}'", cell)),
+            };
+            if bomb {
+                piece.bomb = Some(piece.cells.len());
+            }
+            piece.cells.push(coord);
+        }
+        if piece.cells.is_empty() {
+            return Err(format!("expected the cells of the piece '{}'", letter))
+        }
+        Ok(piece)
+    }
+}
+
+impl Save {
+    // a line per setting and piece, ending with a `board` line followed by the board's rows
+    pub fn to_text(&self) -> String {
+        let mut text = format!("rng {} {}\n", self.rng.0, self.rng.1);
+        text.push_str(&format!("score {} {} {}\n", self.points, self.lines, self.level));
+        if let Some(gap) = self.garbage_gap {
+            text.push_str(&format!("gap {}\n", gap));
+        }
+        text.push_str(&format!("hold {}\n", self.can_hold));
+        text.push_str(&format!("piece {}\n", self.piece.to_text()));
+        for piece in self.next.iter() {
+            text.push_str(&format!("next {}\n", piece.to_text()));
+        }
+        for piece in self.held.iter() {
+            text.push_str(&format!("held {}\n", piece.to_text()));
+        }
+        if let Some(sequence) = &self.sequence {
+            text.push_str(&format!("sequence {}\n", sequence));
+        }
+        text.push_str("board\n");
+        text.push_str(&self.board);
+        text.push('\n');
+        text
+    }
+
+    // reads the text written by to_text, blank lines are skipped, the board is checked against the grid's size once it's loaded
+    pub fn from_text(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()) ).filter(|(_, line)| !line.is_empty() );
+        let (mut rng, mut score, mut garbage_gap, mut can_hold, mut piece, mut sequence) = (None, None, None, None, None, None);
+        let (mut next, mut held) = (Vec::new(), Vec::new());
+        let mut last_line = 0;
+        let mut board = None;
+        while let Some((line, text)) = lines.next() {
+            last_line = line;
+            let error = |reason: String| SaveError{line, reason};
+            let (key, value) = match text.split_once(' ') {
+                Some((key, value)) => (key, value.trim()),
+                None => (text, ""),
+            };
+            let numbers = || value.split_whitespace().map(str::parse).collect::<Result<Vec<u64>, _>>().map_err(|_| error(format!("invalid numbers '{}'", value)) );
+            match key {
+                "rng" => match numbers()?.as_slice() {
+                    [state, increment] => rng = Some((*state, *increment)),
+                    _ => return Err(error("expected 'rng <state> <increment>'".to_string())),
+                },
+                "score" => match numbers()?.as_slice() {
+                    [points, lines, level] if [points, lines, level].iter().all(|n| **n <= u64::from(u32::MAX) ) => score = Some((*points as u32, *lines as u32, *level as u32)),
+                    _ => return Err(error("expected 'score <points> <lines> <level>'".to_string())),
+                },
+                "gap" => garbage_gap = Some(value.parse().map_err(|_| error(format!("invalid gap '{}'", value)) )?),
+                "hold" => can_hold = Some(value.parse().map_err(|_| error(format!("expected 'hold true' or 'hold false', found '{}'", text)) )?),
+                "piece" => piece = Some(SavedPiece::from_text(value).map_err(error)?),
+                "next" => next.push(SavedPiece::from_text(value).map_err(error)?),
+                "held" => held.push(SavedPiece::from_text(value).map_err(error)?),
+                "sequence" => sequence = Some(value.to_string()),
+                "board" => {
+                    board = Some(lines.by_ref().map(|(_, row)| row ).collect::<Vec<_>>().join("\n"));
+                    break
+                },
+                _ => return Err(error(format!("unknown line '{}'", text))),
+            }
+        }
+        let missing = |what: &str| SaveError{line: last_line, reason: format!("missing the '{}' line", what)};
+        let (points, lines, level) = score.ok_or_else(|| missing("score") )?;
+        Ok(Save {
+            rng: rng.ok_or_else(|| missing("rng") )?,
+            points,
+            lines,
+            level,
+            garbage_gap,
+            can_hold: can_hold.ok_or_else(|| missing("hold") )?,
+            piece: piece.ok_or_else(|| missing("piece") )?,
+            next,
+            held,
+            sequence,
+            board: board.ok_or_else(|| missing("board") )?,
+        })
+    }
+}
+
+// the line of a save that couldn't be read and why
+#[derive(Debug)]
+pub struct SaveError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "save line {}: {}", self.line, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Game, GameState, PieceKind};
+    use crate::config::Variant;
+    use crate::primitives::Action;
+
+    // ticks the game past its countdown
+    fn started(mut game: Game) -> Game {
+        while !matches!(game.state(), GameState::Playing) {
+            game.tick(16.0);
+        }
+        game
+    }
+
+    // a game some pieces in, each dropped where it spawned
+    fn played(config: Config) -> Game {
+        let mut game = started(Game::new(Config{menu: false, clear_duration: 0.0, ..config}));
+        while game.grid().spawned() < 4 {
+            game.press(Action::Drop);
+            game.tick(16.0);
+            game.release(Action::Drop);
+            game.tick(16.0);
+        }
+        game
+    }
+
+    // the kinds and cells of the current piece and the next pieces
+    fn next_pieces(game: &mut Game, n: usize) -> Vec<(PieceKind, Vec<Coord>)> {
+        let mut pieces = Vec::new();
+        for _ in 0..n {
+            let piece = game.grid().curr_piece();
+            pieces.push((piece.kind(), piece.bones().iter().map(|bone| bone.coord ).collect()));
+            game.press(Action::Drop);
+            game.tick(16.0);
+            game.release(Action::Drop);
+        }
+        pieces
+    }
+
+    #[test]
+    fn a_saved_game_round_trips() {
+        let mut game = played(Config{seed: 42, items: true, preview_count: 3, ..Config::default()});
+        game.press(Action::Hold);
+        game.tick(16.0);
+        let save = game.save();
+        assert_eq!(save.held.len(), 1);
+        assert_eq!(save.next.len(), 3);
+        assert_eq!(Save::from_text(&save.to_text()).unwrap(), save);
+    }
+
+    #[test]
+    fn a_loaded_game_deals_the_pieces_the_saved_one_would_have() {
+        let config = Config{seed: 7, garbage_rows: 2, garbage_mess: 0.5, hidden_rows: 2, ..Config::default()};
+        let mut game = played(config.clone());
+        let save = Save::from_text(&game.save().to_text()).unwrap();
+        let mut loaded = Game::new(Config{menu: false, clear_duration: 0.0, seed: 1, ..config});
+        loaded.load(&save).unwrap();
+        let mut loaded = started(loaded);
+        assert_eq!(loaded.grid().state_hash(), game.grid().state_hash());
+        assert_eq!(next_pieces(&mut loaded, 6), next_pieces(&mut game, 6));
+        assert_eq!(loaded.grid().blocks().to_ascii(), game.grid().blocks().to_ascii());
+    }
+
+    #[test]
+    fn a_loaded_scripted_game_deals_the_letters_it_had_left() {
+        let config = Config{sequence: PieceKind::sequence("TSZOIJLT", &PieceKind::of_variant(Variant::Standard)).unwrap(), ..Config::default()};
+        let game = played(config.clone());
+        let save = Save::from_text(&game.save().to_text()).unwrap();
+        assert!(save.sequence.is_some());
+        let mut loaded = Game::new(config);
+        loaded.load(&save).unwrap();
+        assert_eq!(loaded.grid().upcoming_pieces(8), game.grid().upcoming_pieces(8));
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        let error = |text| Save::from_text(text).unwrap_err().to_string();
+        assert_eq!(error("rng 1"), "save line 1: expected 'rng <state> <increment>'");
+        assert_eq!(error("rng 1 2\nscore 0 0 1\nhold maybe"), "save line 3: expected 'hold true' or 'hold false', found 'hold maybe'");
+        assert_eq!(error("piece T spawn 1;2"), "save line 1: expected 'x,y', found '1;2'");
+        assert_eq!(error("rng 1 2\nscore 0 0 1\nhold true\nboard"), "save line 4: missing the 'piece' line");
+        assert!(error("rng 1 2\njump").starts_with("save line 2: "));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::config::Scoring;
+
+const LINES_PER_LEVEL: u32 = 10;
+const PERFECT_CLEAR_POINTS: u32 = 2000; // bonus for emptying the board, times the level
+const BACK_TO_BACK_PERCENT: u32 = 150; // guideline points for a difficult clear right after another
+const RISE_POINTS: u32 = 50; // survival's points for each garbage row survived, times the level
+const BLIND_BONUS_PERCENT: u32 = 50; // extra points for playing without the next preview
+
+// the rows cleared by a single placement
+#[derive(Copy, Clone, Debug)]
+pub struct Clear {
+    pub lines: usize,
+    pub t_spin: bool,
+}
+
+impl Clear {
+    // whether the placement is worth any points
+    pub fn scores(&self) -> bool {
+        self.lines > 0 || self.t_spin
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.t_spin, self.lines) {
+            (true, 0) => "T-SPIN",
+            (true, 1) => "T-SPIN SINGLE",
+            (true, 2) => "T-SPIN DOUBLE",
+            (true, _) => "T-SPIN TRIPLE",
+            (false, 0) => "",
+            (false, 1) => "SINGLE",
+            (false, 2) => "DOUBLE",
+            (false, 3) => "TRIPLE",
+            (false, _) => "TETRIS",
+        }
+    }
+
+    // tetrises and t-spins that clear lines, which chain into back to back clears
+    pub fn difficult(&self) -> bool {
+        self.lines >= 4 || (self.t_spin && self.lines > 0)
+    }
+
+    // rows of garbage sent to the opponent in versus, singles send nothing and t-spins send double
+    pub fn garbage(&self) -> i16 {
+        match (self.t_spin, self.lines) {
+            (true, lines) => 2 * lines as i16,
+            (false, 0) | (false, 1) => 0,
+            (false, 2) => 1,
+            (false, 3) => 2,
+            (false, _) => 4,
+        }
+    }
+
+}
+
+// the points a variant awards, the score calls into the rules for the config's scoring rather than a formula of its own
+// the level is the game's, which starts at 1
+pub trait ScoringRules {
+    fn line_clear_points(&self, lines: usize, level: u32, t_spin: bool, back_to_back: bool) -> u32;
+
+    fn soft_drop_points(&self, cells: u32) -> u32;
+
+    fn hard_drop_points(&self, cells: u32) -> u32;
+
+    fn perfect_clear_points(&self, level: u32) -> u32 {
+        PERFECT_CLEAR_POINTS * level
+    }
+}
+
+// t-spins, back to back bonuses, 1 point a row soft dropped and 2 hard dropped
+pub struct GuidelineScoring;
+
+impl ScoringRules for GuidelineScoring {
+    fn line_clear_points(&self, lines: usize, level: u32, t_spin: bool, back_to_back: bool) -> u32 {
+        let base = match (t_spin, lines) {
+            (true, 0) => 400,
+            (true, 1) => 800,
+            (true, 2) => 1200,
+            (true, _) => 1600,
+            (false, 0) => 0,
+            (false, 1) => 100,
+            (false, 2) => 300,
+            (false, 3) => 500,
+            (false, _) => 800,
+        };
+        let points = base * level;
+        if back_to_back { points * BACK_TO_BACK_PERCENT / 100 } else { points }
+    }
+
+    fn soft_drop_points(&self, cells: u32) -> u32 {
+        cells
+    }
+
+    fn hard_drop_points(&self, cells: u32) -> u32 {
+        2 * cells
+    }
+}
+
+// the nes points, which know nothing of t-spins, back to back clears, hard drops or perfect clears
+// the nes counts levels from 0, so the game's level 1 is its level 0 and multiplies by 1
+pub struct NesScoring;
+
+impl ScoringRules for NesScoring {
+    fn line_clear_points(&self, lines: usize, level: u32, _t_spin: bool, _back_to_back: bool) -> u32 {
+        let base = match lines {
+            0 => 0,
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            _ => 1200,
+        };
+        base * level
+    }
+
+    fn soft_drop_points(&self, cells: u32) -> u32 {
+        cells
+    }
+
+    fn hard_drop_points(&self, _cells: u32) -> u32 {
+        0
+    }
+
+    fn perfect_clear_points(&self, _level: u32) -> u32 {
+        0
+    }
+}
+
+pub fn rules(scoring: Scoring) -> &'static dyn ScoringRules {
+    match scoring {
+        Scoring::Guideline => &GuidelineScoring,
+        Scoring::Nes => &NesScoring,
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Score {
+    pub points: u32,
+    pub lines: u32,
+    pub level: u32,
+    blind: bool,
+    start_level: u32, // the level stays here until enough lines are cleared to pass it
+    scoring: Scoring,
+    difficult: bool, // the last clear of any lines was difficult, so a difficult clear next is back to back
+}
+
+impl Score {
+    pub fn new(blind: bool, start_level: u32, scoring: Scoring) -> Self {
+        Self {
+            points: 0,
+            lines: 0,
+            level: start_level,
+            blind,
+            start_level,
+            scoring,
+            difficult: false,
+        }
+    }
+
+    pub fn scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    // whether the clear would follow another difficult clear, t-spins without lines neither chain nor break the chain
+    pub fn back_to_back(&self, clear: &Clear) -> bool {
+        self.difficult && clear.difficult()
+    }
+
+    // whether the lines cleared reach the end of the level cap, ie. 150 lines for a cap of 15
+    pub fn completed(&self, level_cap: u32) -> bool {
+        self.lines >= level_cap * LINES_PER_LEVEL
+    }
+
+    // awards the bonus for a clear that empties the board, returning the points awarded
+    pub fn add_perfect_clear(&mut self) -> u32 {
+        let points = rules(self.scoring).perfect_clear_points(self.level);
+        self.points += points;
+        points
+    }
+
+    // awards the points for surviving a garbage row rising, returning the points awarded
+    pub fn add_rise(&mut self) -> u32 {
+        let points = RISE_POINTS * self.level;
+        self.points += points;
+        points
+    }
+
+    // awards the scoring's points for the rows soft dropped times the multiplier, not multiplied by the level, returning the points awarded
+    pub fn add_soft_drop(&mut self, rows: u32, multiplier: u32) -> u32 {
+        let points = rules(self.scoring).soft_drop_points(rows) * multiplier;
+        self.points += points;
+        points
+    }
+
+    // awards the points for the rows hard dropped, not multiplied by the level, returning the points awarded
+    pub fn add_hard_drop(&mut self, rows: u32) -> u32 {
+        let points = rules(self.scoring).hard_drop_points(rows);
+        self.points += points;
+        points
+    }
+
+    // awards the points for the clear at the current level, returning the points awarded
+    pub fn add_clear(&mut self, clear: &Clear) -> u32 {
+        let mut points = rules(self.scoring).line_clear_points(clear.lines, self.level, clear.t_spin, self.back_to_back(clear));
+        if clear.lines > 0 {
+            self.difficult = clear.difficult();
+        }
+        if self.blind {
+            points += points * BLIND_BONUS_PERCENT / 100;
+        }
+        self.points += points;
+        self.lines += clear.lines as u32;
+        self.level = self.start_level.max(1 + self.lines / LINES_PER_LEVEL);
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_tetris_at_level_5_scores_by_the_rules() {
+        let tetris = Clear{lines: 4, t_spin: false};
+        for (scoring, points, back_to_back) in [(Scoring::Guideline, 4000, 6000), (Scoring::Nes, 6000, 6000)] {
+            let mut score = Score::new(false, 5, scoring);
+            assert_eq!(score.add_clear(&tetris), points);
+            assert_eq!(score.add_clear(&tetris), back_to_back);
+            assert_eq!(score.add_hard_drop(10), if scoring == Scoring::Guideline { 20 } else { 0 });
+        }
+    }
+}
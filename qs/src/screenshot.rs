@@ -0,0 +1,174 @@
+// board screenshots for bug reports, the cells are drawn again into an image rather than read back from the window
+// and written as an uncompressed png so no image crate is needed
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tetrust::Error;
+use tetrust::primitives::Coord;
+
+const MAX_STORED_BLOCK: usize = 65535; // bytes in a deflate block written without compression
+
+pub type Cells = Vec<(Coord, (u8, u8, u8))>; // the rgb color of each cell drawn on a board
+
+// an rgb image of the boards side by side with a cell between them, each board the cells drawn on it over black
+pub fn render(boards: &[Cells], columns: i16, rows: i16, cell_size: usize) -> (usize, usize, Vec<u8>) {
+    let board_width = columns as usize * cell_size;
+    let width = (boards.len() * (board_width + cell_size)).saturating_sub(cell_size).max(1);
+    let height = rows as usize * cell_size;
+    let mut rgb = vec![0; width * height * 3];
+    for (i, cells) in boards.iter().enumerate() {
+        let left = i * (board_width + cell_size);
+        for (coord, color) in cells.iter().filter(|(coord, _)| coord.x >= 0 && coord.x < columns && coord.y >= 0 && coord.y < rows ) {
+            for y in coord.y as usize * cell_size..(coord.y as usize + 1) * cell_size {
+                for x in left + coord.x as usize * cell_size..left + (coord.x as usize + 1) * cell_size {
+                    let i = (y * width + x) * 3;
+                    rgb[i..i + 3].copy_from_slice(&[color.0, color.1, color.2]);
+                }
+            }
+        }
+    }
+    (width, height, rgb)
+}
+
+// writes the png to a file named by the time it was taken on its own thread so the game doesn't stutter
+pub fn save(png: Vec<u8>) {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = format!("tetrust-{}-{:03}.png", since_epoch.as_secs(), since_epoch.subsec_millis());
+    std::thread::spawn(move || {
+        match std::fs::write(&path, png) {
+            Ok(()) => eprintln!("saved a screenshot to {}", path),
+            Err(err) => eprintln!("{}", Error::Io(std::io::Error::new(err.kind(), format!("{}: {}", path, err)))),
+        }
+    });
+}
+
+// an 8 bit rgb png of the pixels row by row, the image data is deflated with stored blocks
+pub fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+    for row in rgb.chunks(width * 3) {
+        raw.push(0); // no filter
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01]; // deflate with a 32k window and no preset dictionary
+    let n_blocks = raw.len().div_ceil(MAX_STORED_BLOCK);
+    for (i, block) in raw.chunks(MAX_STORED_BLOCK).enumerate() {
+        let len = block.len() as u16;
+        zlib.push(if i + 1 == n_blocks { 1 } else { 0 }); // the last block is marked final, stored blocks are type 0
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits per channel, rgb, deflate, no filtering beyond each row's, not interlaced
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+// the length, type, data and the crc of the type and data
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes.iter() {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes.iter() {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the chunks of the png as (type, data) once each one's crc is checked
+    fn chunks(png: &[u8]) -> Vec<(&[u8], &[u8])> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes([rest[8 + len], rest[9 + len], rest[10 + len], rest[11 + len]]);
+            assert_eq!(crc, crc32(&rest[4..8 + len]));
+            chunks.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    // the bytes of the stored blocks of the zlib stream, once its adler is checked
+    fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(&zlib[..2], &[0x78, 0x01]);
+        let mut raw = Vec::new();
+        let mut i = 2;
+        loop {
+            let last = zlib[i] == 1;
+            let len = u16::from_le_bytes([zlib[i + 1], zlib[i + 2]]);
+            assert_eq!(!len, u16::from_le_bytes([zlib[i + 3], zlib[i + 4]]));
+            raw.extend_from_slice(&zlib[i + 5..i + 5 + len as usize]);
+            i += 5 + len as usize;
+            if last {
+                break
+            }
+        }
+        assert_eq!(&zlib[i..], &adler32(&raw).to_be_bytes());
+        raw
+    }
+
+    #[test]
+    fn checksums_match_the_reference_values() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9];
+        let png = encode_png(2, 2, &rgb);
+        let chunks = chunks(&png);
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| *kind ).collect();
+        assert_eq!(kinds, [&b"IHDR"[..], b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, &[0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(inflate_stored(chunks[1].1), [0, 255, 0, 0, 0, 255, 0, 0, 0, 0, 255, 9, 9, 9]);
+    }
+
+    #[test]
+    fn large_images_split_into_stored_blocks() {
+        let (width, height) = (200, 200); // 120200 bytes of rows, 2 blocks
+        let rgb: Vec<u8> = (0..width * height * 3).map(|i| (i % 251) as u8 ).collect();
+        let png = encode_png(width, height, &rgb);
+        let raw = inflate_stored(chunks(&png)[1].1);
+        assert_eq!(raw.len(), height * (width * 3 + 1));
+        for (row, pixels) in raw.chunks(width * 3 + 1).zip(rgb.chunks(width * 3)) {
+            assert_eq!(row[0], 0);
+            assert_eq!(&row[1..], pixels);
+        }
+    }
+}
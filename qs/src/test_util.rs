@@ -0,0 +1,27 @@
+// setups shared by the unit tests
+use super::{Bone, Config, Coord, Game, GameState, PieceKind};
+use crate::primitives::Color;
+
+// a game past its countdown dealing the pieces in order, full rows clear as soon as they fill
+pub fn playing(sequence: &[PieceKind]) -> Game {
+    playing_with(Config{sequence: sequence.to_vec(), ..Config::default()})
+}
+
+pub fn playing_with(config: Config) -> Game {
+    let mut game = Game::new(Config{menu: false, clear_duration: 0.0, ..config});
+    game.state = GameState::Playing;
+    game
+}
+
+// fills the row with garbage but for the columns left open
+pub fn fill_row(game: &mut Game, y: i16, open: &[i16]) {
+    for x in (0..game.grid.width()).filter(|x| !open.contains(x) ) {
+        let coord = Coord{x, y};
+        game.grid.blocks.set_block(&coord, Bone::new(Color::Gray, coord, PieceKind::Garbage)).unwrap();
+    }
+}
+
+// the columns the current piece covers, ie. to leave open below it
+pub fn piece_columns(game: &Game) -> Vec<i16> {
+    game.grid.curr_piece.bones.iter().map(|bone| bone.coord.x ).collect()
+}
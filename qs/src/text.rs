@@ -0,0 +1,85 @@
+use quicksilver::{
+    geom::Rectangle,
+    graphics,
+    lifecycle::Window,
+};
+
+// glyphs are drawn with rectangles from a layout like the tetrinome layouts so no font asset is needed
+pub const GLYPH_WIDTH: i16 = 3;
+pub const GLYPH_HEIGHT: i16 = 5;
+
+fn glyph(c: char) -> [&'static str; GLYPH_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        '0' => ["xxx", "x-x", "x-x", "x-x", "xxx"],
+        '1' => ["-x-", "xx-", "-x-", "-x-", "xxx"],
+        '2' => ["xxx", "--x", "xxx", "x--", "xxx"],
+        '3' => ["xxx", "--x", "xxx", "--x", "xxx"],
+        '4' => ["x-x", "x-x", "xxx", "--x", "--x"],
+        '5' => ["xxx", "x--", "xxx", "--x", "xxx"],
+        '6' => ["xxx", "x--", "xxx", "x-x", "xxx"],
+        '7' => ["xxx", "--x", "--x", "--x", "--x"],
+        '8' => ["xxx", "x-x", "xxx", "x-x", "xxx"],
+        '9' => ["xxx", "x-x", "xxx", "--x", "xxx"],
+        'A' => ["xxx", "x-x", "xxx", "x-x", "x-x"],
+        'B' => ["xx-", "x-x", "xx-", "x-x", "xx-"],
+        'C' => ["xxx", "x--", "x--", "x--", "xxx"],
+        'D' => ["xx-", "x-x", "x-x", "x-x", "xx-"],
+        'E' => ["xxx", "x--", "xx-", "x--", "xxx"],
+        'F' => ["xxx", "x--", "xx-", "x--", "x--"],
+        'G' => ["xxx", "x--", "x-x", "x-x", "xxx"],
+        'H' => ["x-x", "x-x", "xxx", "x-x", "x-x"],
+        'I' => ["xxx", "-x-", "-x-", "-x-", "xxx"],
+        'J' => ["--x", "--x", "--x", "x-x", "xxx"],
+        'K' => ["x-x", "x-x", "xx-", "x-x", "x-x"],
+        'L' => ["x--", "x--", "x--", "x--", "xxx"],
+        'M' => ["x-x", "xxx", "xxx", "x-x", "x-x"],
+        'N' => ["xx-", "x-x", "x-x", "x-x", "x-x"],
+        'O' => ["xxx", "x-x", "x-x", "x-x", "xxx"],
+        'P' => ["xxx", "x-x", "xxx", "x--", "x--"],
+        'Q' => ["xxx", "x-x", "x-x", "xxx", "--x"],
+        'R' => ["xx-", "x-x", "xx-", "x-x", "x-x"],
+        'S' => ["xxx", "x--", "xxx", "--x", "xxx"],
+        'T' => ["xxx", "-x-", "-x-", "-x-", "-x-"],
+        'U' => ["x-x", "x-x", "x-x", "x-x", "xxx"],
+        'V' => ["x-x", "x-x", "x-x", "x-x", "-x-"],
+        'W' => ["x-x", "x-x", "xxx", "xxx", "x-x"],
+        'X' => ["x-x", "x-x", "-x-", "x-x", "x-x"],
+        'Y' => ["x-x", "x-x", "-x-", "-x-", "-x-"],
+        'Z' => ["xxx", "--x", "-x-", "x--", "xxx"],
+        '-' => ["---", "---", "xxx", "---", "---"],
+        '+' => ["---", "-x-", "xxx", "-x-", "---"],
+        '.' => ["---", "---", "---", "---", "-x-"],
+        ':' => ["---", "-x-", "---", "-x-", "---"],
+        '/' => ["--x", "--x", "-x-", "x--", "x--"],
+        ',' => ["---", "---", "---", "-x-", "x--"],
+        '!' => ["-x-", "-x-", "-x-", "---", "-x-"],
+        _ => ["---", "---", "---", "---", "---"], // unknown characters are drawn as a space
+    }
+}
+
+// width in pixels of the text when drawn at the given scale
+pub fn text_width(text: &str, scale: i16) -> i16 {
+    let n_chars = text.chars().count() as i16;
+    if n_chars == 0 {
+        return 0
+    }
+    (n_chars * (GLYPH_WIDTH + 1) - 1) * scale // 1 unit gap between glyphs
+}
+
+// draws the text with its top left corner at pos, each glyph unit is scale pixels wide
+pub fn draw_text(window: &mut Window, text: &str, pos: (i16, i16), scale: i16, color: graphics::Color) {
+    for (i, c) in text.chars().enumerate() {
+        let x = pos.0 + i as i16 * (GLYPH_WIDTH + 1) * scale;
+        for (y, row) in glyph(c).iter().enumerate() {
+            for (dx, unit) in row.chars().enumerate() {
+                if unit == 'x' {
+                    let rect = Rectangle::new(
+                        (x + dx as i16 * scale, pos.1 + y as i16 * scale),
+                        (scale, scale),
+                    );
+                    window.draw(&rect, graphics::Background::Col(color));
+                }
+            }
+        }
+    }
+}
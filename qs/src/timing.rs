@@ -1,63 +1,389 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 pub const SECOND: f64 = 1000.0;
-pub const UPDATES_PER_SEC: f64 = 16.0;
-pub const MILLIS_PER_UPDATE: f64 = SECOND / UPDATES_PER_SEC;
+pub const DEFAULT_UPDATES_PER_SEC: f64 = 16.0;
+
+pub const DROP_ROW_DURATION: f64 = SECOND / DEFAULT_UPDATES_PER_SEC; // an instant drop falls a row this often whatever the update rate
+pub const CLEAR_DURATION: f64 = 3000.0; // the line clear animation
+pub const POPUP_DURATION: f64 = 1000.0;
+pub const SOFT_LOCK_FLASH_DURATION: f64 = 120.0; // white flash on a piece locked by gravity
+pub const HARD_LOCK_FLASH_DURATION: f64 = 250.0; // longer for an instant drop
+pub const INVISIBLE_DELAY: f64 = 1000.0; // a locked block stays visible this long in invisible mode, as do the blocks a clear reveals
+pub const INVISIBLE_FADE_DURATION: f64 = 2000.0; // then fades out over this long
+pub const HOLD_SWAP_DURATION: f64 = 150.0; // the held piece sliding from the board into the hold box
+pub const SHAKE_DURATION: f64 = 250.0; // the board shaking after a lock near the top
+
+pub const COUNTDOWN_DURATION: f64 = 3000.0; // the 3, 2, 1, GO before the first piece falls
+pub const COUNTDOWN_STEPS: usize = 4;
+
+pub const ROTATE_REPEAT_DELAY: f64 = 400.0; // held rotation waits this long before repeating
+pub const ROTATE_REPEAT_INTERVAL: f64 = 250.0; // slower than horizontal auto-repeat so the piece doesn't spin wildly
+
+pub const RISE_INTERVAL: f64 = 10000.0; // survival's first garbage row rises after this long
+pub const MIN_RISE_INTERVAL: f64 = 2000.0; // the interval stops shortening here
+const RISE_SPEEDUP: f64 = 0.9; // each rise comes this much sooner than the last
+
+pub const KILL_SCREEN_DELAY: f64 = 3000.0; // default time the stack stays at the kill screen height before it starts
+
+pub const PIECE_RATE_WINDOW: f64 = 5000.0; // default play time the pieces per second are taken over
+pub const LOCK_DELAY: f64 = 500.0; // default least time a landed piece rests before locking, so it can still slide at high gravity
 
-pub static mut ELAPSED: f64 = MILLIS_PER_UPDATE;
+// set at startup before the window is created, animation frames derive their length from it
+// the bits of the f64, 0 until it's set
+static MILLIS_PER_UPDATE: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_updates_per_sec(updates_per_sec: f64) {
+    MILLIS_PER_UPDATE.store((SECOND / updates_per_sec).to_bits(), Ordering::Relaxed);
+}
+
+pub fn millis_per_update() -> f64 {
+    match MILLIS_PER_UPDATE.load(Ordering::Relaxed) {
+        0 => SECOND / DEFAULT_UPDATES_PER_SEC,
+        bits => f64::from_bits(bits),
+    }
+}
+
+// milliseconds per row for each level from level 1, levels past the end of the table keep its last rate
+#[derive(Clone, Debug, PartialEq)]
+pub struct GravityCurve {
+    rates: Vec<f64>,
+}
+
+impl GravityCurve {
+    const LEVELS: u32 = 20; // levels the formula curves are tabled for
+    const NES_FPS: f64 = 60.0988;
+
+    // (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds per row
+    pub fn guideline() -> Self {
+        Self::from_fn(|level| (0.8 - level * 0.007).powf(level) * SECOND)
+    }
+
+    // the nes frames per row, level 1 plays as the nes level 0
+    pub fn nes() -> Self {
+        let frames = [48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1];
+        Self { rates: frames.iter().map(|frames| f64::from(*frames) * SECOND / Self::NES_FPS ).collect() }
+    }
+
+    // a second per row at level 1, 50 milliseconds faster each level
+    pub fn linear() -> Self {
+        Self::from_fn(|level| SECOND - level * 50.0)
+    }
+
+    // tables the rate for each level, the function takes the level counting from 0
+    fn from_fn(rate: impl Fn(f64) -> f64) -> Self {
+        Self { rates: (0..Self::LEVELS).map(|level| rate(f64::from(level)) ).collect() }
+    }
+
+    // a custom curve, the rates have to be positive and never get slower
+    pub fn from_rates(rates: Vec<f64>) -> Result<Self, String> {
+        if rates.is_empty() || rates.iter().any(|rate| rate.is_nan() || *rate <= 0.0) {
+            return Err("expected positive milliseconds per row".to_string())
+        }
+        if rates.windows(2).any(|pair| pair[1] > pair[0]) {
+            return Err("expected milliseconds per row that never increase with the level".to_string())
+        }
+        Ok(Self { rates })
+    }
 
-pub fn set_elapsed(elapsed: f64) {
-    unsafe {
-        ELAPSED = elapsed;
+    // milliseconds per row at the level, the last rate once the level is past the table
+    pub fn rate(&self, level: u32) -> f64 {
+        let i = (level.max(1) - 1) as usize;
+        self.rates[i.min(self.rates.len() - 1)]
     }
 }
 
-pub fn get_elapsed() -> f64 {
-    unsafe {
-        ELAPSED
+impl Default for GravityCurve {
+    fn default() -> Self {
+        Self::guideline()
     }
 }
 
-#[cfg(not(target_arch="wasm32"))]
-use std::time::{Instant};
+impl FromStr for GravityCurve {
+    type Err = String;
 
-#[derive(Debug)]
+    // a named curve or the comma separated milliseconds per row from level 1, ie. `1000,800,600`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "guideline" => Ok(Self::guideline()),
+            "nes" => Ok(Self::nes()),
+            "linear" => Ok(Self::linear()),
+            rates => {
+                let rates: Result<Vec<f64>, _> = rates.split(',').map(|rate| rate.trim().parse() ).collect();
+                let rates = rates.map_err(|_| "expected guideline, nes, linear or milliseconds per row separated by commas".to_string())?;
+                Self::from_rates(rates)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Timer {
-    last_update: f64,
     fall_update: f64,
     fall_rate: f64,
-
-    #[cfg(not(target_arch="wasm32"))]
-    pub test: Instant,
+    rest_update: f64, // time the current piece has been resting on the stack
+    curve: GravityCurve,
 }
 
 impl Timer {
-    fn new(fall_rate: f64) -> Self {
+    pub fn new(curve: GravityCurve) -> Self {
         Timer {
-            last_update: 0.0,
             fall_update: 0.0,
-            fall_rate,
+            fall_rate: curve.rate(1),
+            rest_update: 0.0,
+            curve,
+        }
+    }
+
+    // the fall rate from the gravity curve for the level
+    pub fn set_fall_rate(&mut self, level: u32) {
+        self.fall_rate = self.curve.rate(level);
+    }
+
+    // accumulates the milliseconds elapsed since the last update, identical on native and wasm
+    pub fn update(&mut self, elapsed: f64) {
+        self.fall_update += elapsed;
+    }
+
+    // divides the fall rate set from the curve while soft dropping
+    pub fn soft_drop(&mut self, factor: f64) {
+        self.fall_rate /= factor;
+    }
 
-            #[cfg(not(target_arch="wasm32"))]
-            test: Instant::now()
+    // returns the number of rows to fall, more than one when the fall rate is faster than the update rate
+    pub fn fall(&mut self) -> u32 {
+        let mut n_rows = 0;
+        while self.fall_update >= self.fall_rate {
+            self.fall_update -= self.fall_rate;
+            n_rows += 1;
         }
+        n_rows
     }
 
-    pub fn update(&mut self) {
-        set_elapsed(MILLIS_PER_UPDATE);
-        self.fall_update += get_elapsed();
+    // starts the wait for the next fall over, for --fall-reset
+    pub fn reset_fall(&mut self) {
+        self.fall_update = 0.0;
     }
 
-    pub fn fall(&mut self) -> bool {
-        if self.fall_update > self.fall_rate {
-            self.fall_update = 0.0;
+    // the fraction of the fall interval that has passed since the piece last fell
+    pub fn fall_progress(&self) -> f64 {
+        (self.fall_update / self.fall_rate).min(1.0)
+    }
 
+    // accumulates the resting time, returns true once the piece should lock
+    pub fn rest(&mut self, elapsed: f64, lock_delay: f64) -> bool {
+        self.rest_update += elapsed;
+        if self.rest_update >= self.fall_rate.max(lock_delay) {
+            self.rest_update = 0.0;
             return true
         }
         false
     }
+
+    pub fn reset_rest(&mut self) {
+        self.rest_update = 0.0;
+    }
 }
 
 impl Default for Timer {
     fn default() -> Self {
-        Self::new(SECOND / 2.0)
+        Self::new(GravityCurve::default())
+    }
+}
+// when the next garbage row rises from the floor in survival, sooner after each rise
+#[derive(Debug, Clone)]
+pub struct RiseTimer {
+    interval: f64,
+    until_next: f64,
+}
+
+impl RiseTimer {
+    pub fn new() -> Self {
+        RiseTimer {
+            interval: RISE_INTERVAL,
+            until_next: RISE_INTERVAL,
+        }
+    }
+
+    // returns true when a row should rise, the next one comes sooner
+    pub fn update(&mut self, elapsed: f64) -> bool {
+        self.until_next -= elapsed;
+        if self.until_next > 0.0 {
+            return false
+        }
+        self.interval = (self.interval * RISE_SPEEDUP).max(MIN_RISE_INTERVAL);
+        self.until_next += self.interval;
+        true
+    }
+
+    // the rows rise as often as they ever do from now on, for the kill screen
+    pub fn hurry(&mut self) {
+        self.interval = MIN_RISE_INTERVAL;
+        self.until_next = self.until_next.min(self.interval);
     }
-}
\ No newline at end of file
+
+    // milliseconds until the next row rises
+    pub fn remaining(&self) -> f64 {
+        self.until_next.max(0.0)
+    }
+
+    // the fraction of the interval that has passed since the last row rose
+    pub fn progress(&self) -> f64 {
+        1.0 - (self.until_next / self.interval).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for RiseTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// how long the stack has stayed at the kill screen height, it starts over whenever the stack drops back below
+#[derive(Debug, Clone)]
+pub struct KillTimer {
+    delay: f64,
+    high_for: f64,
+}
+
+impl KillTimer {
+    pub fn new(delay: f64) -> Self {
+        KillTimer {
+            delay,
+            high_for: 0.0,
+        }
+    }
+
+    // returns true once the stack has stayed high for the delay
+    pub fn update(&mut self, elapsed: f64, high: bool) -> bool {
+        self.high_for = if high { self.high_for + elapsed } else { 0.0 };
+        self.high_for >= self.delay
+    }
+
+    // milliseconds until the kill screen starts if the stack stays high, None while it's below the height
+    pub fn remaining(&self) -> Option<f64> {
+        if self.high_for > 0.0 { Some((self.delay - self.high_for).max(0.0)) } else { None }
+    }
+}
+
+// pieces per second over the last window of play, recalculated once a second so the readout doesn't jitter
+#[derive(Debug, Clone)]
+pub struct PieceRate {
+    window: f64, // milliseconds of play the rate is taken over
+    spawns: VecDeque<f64>, // the play time of each spawn within the window
+    counted: u32, // spawns seen so far, new ones are those past it
+    until_next: f64,
+    rate: f64,
+}
+
+impl PieceRate {
+    pub fn new(window: f64) -> Self {
+        PieceRate {
+            window,
+            spawns: VecDeque::new(),
+            counted: 0,
+            until_next: SECOND,
+            rate: 0.0,
+        }
+    }
+
+    // records the spawns since the last update at the play time, spread over the window rather than the game so far once it's long enough
+    pub fn update(&mut self, elapsed: f64, play_time: f64, spawned: u32) {
+        for _ in self.counted..spawned {
+            self.spawns.push_back(play_time);
+        }
+        self.counted = spawned;
+        self.until_next -= elapsed;
+        if self.until_next > 0.0 {
+            return
+        }
+        self.until_next += SECOND;
+        while self.spawns.front().is_some_and(|time| *time <= play_time - self.window ) {
+            self.spawns.pop_front();
+        }
+        self.rate = self.spawns.len() as f64 / (play_time.min(self.window) / SECOND);
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+// auto-repeat for a held input, fires once the delay has passed and then every interval
+#[derive(Debug, Clone)]
+pub struct Repeat {
+    interval: f64,
+    until_next: f64,
+}
+
+impl Repeat {
+    pub fn new(delay: f64, interval: f64) -> Self {
+        Repeat {
+            interval,
+            until_next: delay,
+        }
+    }
+
+    // returns the number of repeats that fired during the elapsed time, unbounded once past the delay with no interval
+    pub fn update(&mut self, elapsed: f64) -> u32 {
+        self.until_next -= elapsed;
+        if self.interval <= 0.0 {
+            return if self.until_next <= 0.0 { u32::MAX } else { 0 }
+        }
+        let mut n_repeats = 0;
+        while self.until_next <= 0.0 {
+            n_repeats += 1;
+            self.until_next += self.interval;
+        }
+        n_repeats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_rise_sooner_each_time() {
+        let mut rise = RiseTimer::default();
+        assert!(!rise.update(RISE_INTERVAL / 2.0));
+        assert_eq!(rise.progress(), 0.5);
+        assert!(rise.update(RISE_INTERVAL / 2.0));
+        assert_eq!(rise.remaining(), RISE_INTERVAL * RISE_SPEEDUP);
+        rise.hurry();
+        assert_eq!(rise.remaining(), MIN_RISE_INTERVAL);
+        assert_eq!(rise.progress(), 0.0);
+    }
+
+    #[test]
+    fn levels_outside_the_curve_take_the_nearest_rate() {
+        let guideline = GravityCurve::guideline();
+        assert_eq!(guideline.rate(0), SECOND);
+        assert_eq!(guideline.rate(1), SECOND);
+        assert_eq!(guideline.rate(100), guideline.rate(GravityCurve::LEVELS));
+        let nes = GravityCurve::nes();
+        assert_eq!(nes.rate(100), SECOND / GravityCurve::NES_FPS);
+        assert_eq!("1000,500".parse::<GravityCurve>().unwrap().rate(5), 500.0);
+    }
+
+    #[test]
+    fn curves_that_slow_down_or_stop_are_rejected() {
+        for rates in ["1000,1200", "1000,0", "1000,nan", ""] {
+            assert!(rates.parse::<GravityCurve>().is_err(), "{}", rates);
+        }
+    }
+
+    #[test]
+    fn the_piece_rate_only_counts_the_window() {
+        let mut rate = PieceRate::new(2.0 * SECOND);
+        rate.update(SECOND / 2.0, SECOND / 2.0, 2);
+        assert_eq!(rate.rate(), 0.0); // not recalculated until a second has passed
+        rate.update(SECOND / 2.0, SECOND, 3);
+        assert_eq!(rate.rate(), 3.0);
+        rate.update(SECOND, 2.0 * SECOND, 4);
+        assert_eq!(rate.rate(), 2.0);
+        rate.update(SECOND, 3.0 * SECOND, 4); // the spawns at half a second and a second have left the window
+        assert_eq!(rate.rate(), 0.5);
+    }
+}
@@ -0,0 +1,35 @@
+// setups shared by the integration tests
+use tetrust::{Game, GameState};
+use tetrust::config::Config;
+use tetrust::primitives::Action;
+use tetrust::timing::millis_per_update;
+
+// a game past its countdown
+pub fn start(config: Config) -> Game {
+    let mut game = Game::new(Config{menu: false, ..config});
+    while !matches!(game.state(), GameState::Playing) {
+        game.tick(millis_per_update());
+    }
+    game
+}
+
+// a key pressed for an update, the same key pressed again without a release is taken for an os key repeat
+pub fn tap(game: &mut Game, action: Action) {
+    game.press(action);
+    game.tick(millis_per_update());
+    game.release(action);
+}
+
+// turns the current I upright over the gap of the garbage row on the floor, returning the gap's column
+pub fn over_the_gap(game: &mut Game) -> i16 {
+    let board = game.grid().blocks().to_ascii();
+    let gap = board.lines().last().unwrap().find('-').unwrap() as i16;
+    let column = |game: &Game| game.grid().curr_piece().bones()[0].coord.x;
+
+    tap(game, Action::RotateCW); // a bone per row
+    while column(game) != gap {
+        let toward = if column(game) < gap { Action::Right } else { Action::Left };
+        tap(game, toward);
+    }
+    gap
+}
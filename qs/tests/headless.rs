@@ -0,0 +1,38 @@
+// whole games played through the library's public api, stepped the way a frontend steps them but without a window
+use tetrust::PieceKind;
+use tetrust::config::Config;
+use tetrust::primitives::Action;
+use tetrust::timing::millis_per_update;
+
+mod common;
+use common::{start, tap, over_the_gap};
+
+#[test]
+fn an_i_dropped_into_the_gap_clears_the_line() {
+    let mut game = start(Config{garbage_rows: 1, clear_duration: 0.0, sequence: vec![PieceKind::I; 2], ..Config::default()});
+    let board = game.grid().blocks().to_ascii();
+    let letter = game.grid().curr_piece().bones()[0].color.letter();
+    let gap = over_the_gap(&mut game);
+    tap(&mut game, Action::Drop);
+
+    assert_eq!(game.grid().score().lines, 1);
+    let width = game.grid().width() as usize;
+    let column: String = (0..width).map(|x| if x == gap as usize { letter } else { '-' } ).collect();
+    let rows: Vec<&str> = board.lines().collect();
+    let mut expected = vec!["-".repeat(width); rows.len() - 3];
+    expected.extend(vec![column; 3]); // the three bones above the line are left
+    assert_eq!(game.grid().blocks().to_ascii(), expected.join("\n"));
+}
+
+#[test]
+fn a_piece_left_alone_falls_and_locks_on_the_floor() {
+    let mut game = start(Config{clear_duration: 0.0, sequence: vec![PieceKind::O; 2], ..Config::default()});
+    let floor = game.grid().height() - 1;
+    while game.grid().blocks().data().iter().all(Option::is_none) {
+        game.tick(millis_per_update());
+    }
+    let mut locked: Vec<i16> = game.grid().blocks().data().iter().flatten().map(|block| block.bone.coord.y ).collect();
+    locked.sort_unstable();
+    assert_eq!(locked, [floor - 1, floor - 1, floor, floor]);
+    assert_eq!(game.grid().curr_piece().kind(), PieceKind::O); // the next one dealt
+}
@@ -0,0 +1,37 @@
+// the animations timed at different update rates, alone in their own test binary since the update rate is set for the whole process
+use tetrust::{Game, PieceKind};
+use tetrust::config::Config;
+use tetrust::primitives::Action;
+use tetrust::timing::set_updates_per_sec;
+
+mod common;
+use common::{start, over_the_gap};
+
+// milliseconds from an instant drop until the animation is done, ticked a millisecond at a time so the updates don't round it
+fn drop_animating_for(game: &mut Game, animating: impl Fn(&Game) -> bool) -> u32 {
+    game.press(Action::Drop);
+    let mut elapsed = 0;
+    loop {
+        game.tick(1.0);
+        elapsed += 1;
+        if !animating(game) {
+            game.release(Action::Drop);
+            return elapsed
+        }
+    }
+}
+
+#[test]
+fn animations_last_as_long_at_any_update_rate() {
+    let clearing = |game: &Game| game.grid().blocks().data().iter().flatten().any(|block| block.clearing() );
+    let flashing = |game: &Game| game.grid().blocks().data().iter().flatten().any(|block| block.flash_alpha().is_some() );
+    let durations: Vec<(u32, u32)> = [16.0, 60.0].iter().map(|rate| {
+        set_updates_per_sec(*rate);
+        let mut game = start(Config{garbage_rows: 1, sequence: vec![PieceKind::I, PieceKind::O, PieceKind::O], ..Config::default()});
+        over_the_gap(&mut game);
+        let clear = drop_animating_for(&mut game, clearing);
+        let flash = drop_animating_for(&mut game, flashing);
+        (clear, flash)
+    }).collect();
+    assert_eq!(durations[0], durations[1]);
+}